@@ -0,0 +1,52 @@
+#![feature(test)]
+extern crate test;
+
+use hotdrink_rs::{
+    examples::constraint_systems::{
+        linear::linear_twoway,
+        linear_oneway, make_dense_cs, make_empty_cs, make_sparse_cs,
+        tree::{
+            multioutput_singleway, multioutput_threeway, multioutput_twoway, singleoutput_multiway,
+            singleoutput_singleway,
+        },
+    },
+    planner::{IncrementalPlanner, Plan},
+};
+use test::Bencher;
+
+const N_COMPONENTS: usize = 1;
+const N_VARIABLES: usize = 25000;
+
+macro_rules! bench_incremental_planner {
+    ( $( $name:ident: $make_cs:ident ),* ) => {
+        $(
+            #[bench]
+            fn $name(b: &mut Bencher) {
+                let cs = $make_cs::<()>(N_COMPONENTS, N_VARIABLES);
+                let mut comp = cs.component("0").unwrap().clone();
+                let variables = comp.variable_names();
+                let edited = variables[variables.len() / 2].to_owned();
+                let planner = IncrementalPlanner::new();
+                // Plan once, then repeatedly edit the same variable like a user typing in a field
+                let _ = planner.plan(&comp);
+                b.iter(|| {
+                    comp.edit(&edited, ()).unwrap();
+                    planner.plan(&comp)
+                });
+            }
+        )*
+    };
+}
+
+bench_incremental_planner! {
+    incremental_planner_on_dense: make_dense_cs,
+    incremental_planner_on_empty: make_empty_cs,
+    incremental_planner_on_linear_oneway: linear_oneway,
+    incremental_planner_on_linear_twoway: linear_twoway,
+    incremental_planner_on_sparse: make_sparse_cs,
+    incremental_planner_on_singleoutput_singleway: singleoutput_singleway,
+    incremental_planner_on_singleoutput_multiway: singleoutput_multiway,
+    incremental_planner_on_multioutput_singleway: multioutput_singleway,
+    incremental_planner_on_multioutput_multiway: multioutput_twoway,
+    incremental_planner_on_multioutput_threeway: multioutput_threeway
+}
@@ -21,6 +21,7 @@ fn write_component_simple<CF: ComponentFactory>(name: &str, size: usize) -> io::
 
 fn main() -> io::Result<()> {
     let mut args = std::env::args();
+    #[allow(clippy::map_flatten)]
     let size: usize = args.nth(1).map(|s| s.parse().ok()).flatten().unwrap_or(8);
 
     write_component::<Ladder>("ladder", size)?;
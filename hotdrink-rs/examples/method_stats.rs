@@ -0,0 +1,73 @@
+//! Prints statistics about which methods the time of solving is spent in,
+//! and writes the time of every method to a CSV file per component.
+
+use hotdrink_rs::{
+    examples::components::{
+        ComponentFactory, Ladder, LinearOneway, LinearTwoway, Random, Unprunable,
+    },
+    model::Component,
+    planner::ComponentSpec,
+    util::table::Table,
+};
+use rand::{distributions::Uniform, Rng};
+use std::time::Duration;
+
+const METHOD_STATS_DIR: &str = "target/method_stats";
+
+fn method_stats_row(
+    table: &mut Table,
+    name: &str,
+    input: usize,
+    make_component: fn(usize) -> Component<()>,
+) {
+    let mut component = make_component(input);
+    component.enable_stats();
+    let mut rng = rand::thread_rng();
+    let uniform = Uniform::new_inclusive(0, component.n_variables().saturating_sub(1));
+    for _ in 0..100 {
+        if component.n_variables() > 0 {
+            let random_number: usize = rng.sample(uniform);
+            component
+                .edit(&format!("var{}", random_number), ())
+                .unwrap();
+        }
+        component.solve().unwrap();
+    }
+    let stats = component.stats().unwrap();
+
+    // Keep the time of every method, and summarize them
+    let path = format!("{}/{}-{}.csv", METHOD_STATS_DIR, name, input);
+    std::fs::write(&path, stats.to_table().to_csv()).unwrap();
+    let method_time: Duration = stats.methods().map(|(_, s)| s.wall_time()).sum();
+    let slowest = stats
+        .methods()
+        .max_by_key(|(_, s)| s.wall_time())
+        .map(|((_, constraint, method), _)| format!("{}.{}", constraint, method))
+        .unwrap_or_default();
+    table.push_row(vec![
+        name.to_owned(),
+        input.to_string(),
+        format!("{:?}", stats.mean_planning_time()),
+        format!("{:?}", method_time / stats.solves().max(1) as u32),
+        slowest,
+    ]);
+}
+
+fn main() {
+    std::fs::create_dir_all(METHOD_STATS_DIR).unwrap();
+    let mut table = Table::new(vec![
+        "component",
+        "size",
+        "planning per solve",
+        "methods per solve",
+        "slowest method",
+    ]);
+    for &i in &[100, 500, 1000] {
+        method_stats_row(&mut table, "linear-oneway", i, LinearOneway::build);
+        method_stats_row(&mut table, "linear-twoway", i, LinearTwoway::build);
+        method_stats_row(&mut table, "ladder", i, Ladder::build);
+        method_stats_row(&mut table, "random", i, Random::build);
+        method_stats_row(&mut table, "unprunable", i, Unprunable::build);
+    }
+    println!("{}", table);
+}
@@ -11,6 +11,8 @@
 //! ```
 //!
 //! Both [`rayon::ThreadPool`] and [`rayon::Scope`] implement [`MethodExecutor`](hotdrink_rs::executor::MethodExecutor).
+//! An `Arc<ThreadPool>` does as well, and will only submit methods once their inputs are resolved,
+//! instead of letting them block a thread while waiting.
 //!
 //! Note that performing side effects like with `ready` is not common in methods,
 //! and is just used to make the example more clear.
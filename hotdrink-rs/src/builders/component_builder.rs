@@ -1,40 +1,52 @@
 //! A builder-struct for programmatically creating components.
 
 use super::{constraint_builder::ConstraintBuilder, value_experiments::Value};
+use crate::model::Component;
 use std::{
-    collections::HashMap,
+    fmt::Display,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 /// A builder for making programmatic construction of components easier.
 #[derive(Clone, Debug)]
 pub struct ComponentBuilder<T> {
-    variables: HashMap<String, Value<T>>,
+    name: String,
+    variables: Vec<(String, Value<T>)>,
     constraints: Vec<ConstraintBuilder<T>>,
+    method_timeout: Option<Duration>,
 }
 
 impl<T> ComponentBuilder<T> {
     /// Constructs a new `ComponentBuilder` with no variables or constraints.
-    pub fn new<S: Into<String>>(_: S) -> Self {
+    pub fn new<S: Into<String>>(name: S) -> Self {
         Self {
-            variables: HashMap::new(),
+            name: name.into(),
+            variables: Vec::new(),
             constraints: Vec::new(),
+            method_timeout: None,
+        }
+    }
+
+    /// Adds a variable, replacing any previous one with the same name.
+    fn insert(&mut self, name: String, value: Value<T>) {
+        match self.variables.iter_mut().find(|(n, _)| n == &name) {
+            Some((_, old)) => *old = value,
+            None => self.variables.push((name, value)),
         }
     }
 
     /// Adds an immutable variable.
     #[must_use]
     pub fn variable<S: Into<String>>(mut self, name: S, value: T) -> Self {
-        self.variables
-            .insert(name.into(), Value::Ref(Arc::new(value)));
+        self.insert(name.into(), Value::Ref(Arc::new(value)));
         self
     }
 
     /// Adds a mutable variable.
     #[must_use]
     pub fn variable_mut<S: Into<String>>(mut self, name: S, value: T) -> Self {
-        self.variables
-            .insert(name.into(), Value::MutRef(Arc::new(RwLock::new(value))));
+        self.insert(name.into(), Value::MutRef(Arc::new(RwLock::new(value))));
         self
     }
 
@@ -62,12 +74,156 @@ impl<T> ComponentBuilder<T> {
         self.constraints.push(constraint);
         self
     }
+
+    /// Sets the maximum time each method may run for, unless the method has its own timeout.
+    /// See [`Component::set_method_timeout`].
+    #[must_use]
+    pub fn method_timeout(mut self, timeout: Duration) -> Self {
+        self.method_timeout = Some(timeout);
+        self
+    }
+
+    /// Builds a [`Component`] with the variables and constraints of the builder.
+    /// The variables are given indices in the order they were first added.
+    ///
+    /// Methods can only mutate values in place if they are added with
+    /// [`variable_mut`](Self::variable_mut) or [`variables_mut`](Self::variables_mut).
+    ///
+    /// Returns a [`BuildError`] if a method refers to a variable that does not exist,
+    /// a method has no function, or two constraints have the same name.
+    pub fn build(self) -> Result<Component<T>, BuildError>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            if self.constraints[..i]
+                .iter()
+                .any(|other| other.name() == constraint.name())
+            {
+                return Err(BuildError::DuplicateConstraint(
+                    constraint.name().to_owned(),
+                ));
+            }
+        }
+        let (variables, values): (Vec<(String, bool)>, Vec<T>) = self
+            .variables
+            .into_iter()
+            .map(|(name, value)| ((name, value.is_mutable()), value.into_inner()))
+            .unzip();
+        let constraints = self
+            .constraints
+            .into_iter()
+            .map(|c| c.into_constraint(&variables))
+            .collect::<Result<_, _>>()?;
+        let name_to_index = variables
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name, i))
+            .collect();
+        let mut component = Component::new_with_map(self.name, name_to_index, values, constraints);
+        component.set_method_timeout(self.method_timeout);
+        Ok(component)
+    }
+}
+
+/// An error that occurs when building a [`Component`] from a [`ComponentBuilder`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// A method refers to a variable that was not added to the builder.
+    NoSuchVariable {
+        /// The constraint the method belongs to.
+        constraint: String,
+        /// The name of the method.
+        method: String,
+        /// The name of the missing variable.
+        variable: String,
+    },
+    /// A method was never given a function to run.
+    MissingApply {
+        /// The constraint the method belongs to.
+        constraint: String,
+        /// The name of the method.
+        method: String,
+    },
+    /// More than one constraint has this name.
+    DuplicateConstraint(String),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::NoSuchVariable {
+                constraint,
+                method,
+                variable,
+            } => write!(
+                f,
+                "Method {}.{} refers to unknown variable {}",
+                constraint, method, variable
+            ),
+            BuildError::MissingApply { constraint, method } => {
+                write!(f, "Method {}.{} has no function", constraint, method)
+            }
+            BuildError::DuplicateConstraint(name) => {
+                write!(f, "Constraint {} is defined more than once", name)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ComponentBuilder;
-    use crate::builders::ConstraintBuilder;
+    use super::{BuildError, ComponentBuilder};
+    use crate::{
+        builders::{
+            method_builder::MutabilityMismatch, ConstraintBuilder, MethodBuilder, MethodOutput,
+        },
+        event::{Event, Ready},
+        model::{undo::UndoLimit, Activation, Component},
+        planner::{ComponentSpec, ConstraintSpec, MethodFailure},
+        solver::Reason,
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    /// A component where `resize` makes `buffer` as long as `size`, and stores its old length in `length`.
+    /// It also records the address of the buffer it mutated.
+    fn resizer(mutable: bool, addresses: Arc<Mutex<Vec<usize>>>) -> Component<Vec<i32>> {
+        let builder = ComponentBuilder::new("Resizer")
+            .variable("size", vec![1])
+            .variable("length", vec![1]);
+        let builder = match mutable {
+            true => builder.variable_mut("buffer", vec![0]),
+            false => builder.variable("buffer", vec![0]),
+        };
+        builder
+            .constraint(
+                ConstraintBuilder::new("Resize").method(
+                    MethodBuilder::new("resize")
+                        .input("size")
+                        .input_mut("buffer")
+                        .outputs(vec![MethodOutput::new("length")])
+                        .apply(move |mut args| {
+                            let size: &Vec<i32> = args.remove(0).try_into_ref()?;
+                            let buffer: &mut Vec<i32> = args.remove(0).try_into_mut()?;
+                            addresses
+                                .lock()
+                                .unwrap()
+                                .push(buffer as *const Vec<i32> as usize);
+                            let length = buffer.len() as i32;
+                            buffer.resize(size[0] as usize, 0);
+                            Ok(vec![vec![length]])
+                        }),
+                ),
+            )
+            .build()
+            .unwrap()
+    }
 
     #[test]
     fn builder_builds() {
@@ -89,4 +245,316 @@ mod tests {
             )
             .constraint(ConstraintBuilder::new("Product"));
     }
+
+    #[test]
+    fn builds_component() {
+        let mut component: Component<i32> = ComponentBuilder::new("Component")
+            .variables(vec![("a", 3), ("b", 7)])
+            .variable_mut("c", 0)
+            .constraint(
+                ConstraintBuilder::new("Sum")
+                    .method(method!(
+                        fn m1(a: &i32, b: &i32) -> [c] {
+                            Ok(vec![*a + *b])
+                        }
+                    ))
+                    .method(method!(
+                        fn m2(a: &i32, c: &i32) -> [b] {
+                            Ok(vec![*c - *a])
+                        }
+                    )),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(component.name(), "Component");
+        assert_eq!(component.n_variables(), 3);
+        let names: Vec<_> = (0..3).filter_map(|i| component.variable_name(i)).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(component.constraints()[0].name(), "Sum");
+
+        component.edit("c", 20).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(17)));
+    }
+
+    #[test]
+    fn unknown_variables_are_reported() {
+        let result: Result<Component<i32>, _> = ComponentBuilder::new("Component")
+            .variable("a", 0)
+            .constraint(ConstraintBuilder::new("C").method(method!(
+                fn m(a: &i32) -> [b] {
+                    Ok(vec![*a])
+                }
+            )))
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(BuildError::NoSuchVariable {
+                constraint: "C".to_owned(),
+                method: "m".to_owned(),
+                variable: "b".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_constraints_are_reported() {
+        let result: Result<Component<i32>, _> = ComponentBuilder::new("Component")
+            .variable("a", 0)
+            .constraint(ConstraintBuilder::new("C").method(MethodBuilder::new("m").input("a")))
+            .constraint(ConstraintBuilder::new("C"))
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(BuildError::DuplicateConstraint("C".to_owned()))
+        );
+    }
+
+    #[test]
+    fn methods_without_function_are_reported() {
+        let result: Result<Component<i32>, _> = ComponentBuilder::new("Component")
+            .variable("a", 0)
+            .constraint(ConstraintBuilder::new("C").method(MethodBuilder::new("m").input("a")))
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(BuildError::MissingApply {
+                constraint: "C".to_owned(),
+                method: "m".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn builds_async_methods() {
+        let mut component: Component<i32> = ComponentBuilder::new("Component")
+            .variables(vec![("a", 3), ("b", 0)])
+            .constraint(
+                ConstraintBuilder::new("Double").method(
+                    MethodBuilder::new("double")
+                        .input("a")
+                        .outputs(vec![MethodOutput::new("b")])
+                        .apply_async(|args| async move { Ok(vec![*args[0] * 2]) }),
+                ),
+            )
+            .build()
+            .unwrap();
+        component.edit("a", 4).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(8)));
+    }
+
+    #[test]
+    fn builds_cancellable_methods() {
+        let mut component: Component<i32> = ComponentBuilder::new("Component")
+            .variables(vec![("a", 3), ("b", 0)])
+            .constraint(
+                ConstraintBuilder::new("Double").method(
+                    MethodBuilder::new("double")
+                        .input("a")
+                        .outputs(vec![MethodOutput::new("b")])
+                        .apply_cancellable(|mut args, token| {
+                            assert!(!token.is_cancelled());
+                            let a: &i32 = args.remove(0).try_into_ref()?;
+                            Ok(vec![a * 2])
+                        }),
+                ),
+            )
+            .build()
+            .unwrap();
+        component.edit("a", 4).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(8)));
+    }
+
+    /// A method that doubles `from` into `to`, and counts its calls.
+    fn counted_double(from: &str, to: &str, calls: Arc<AtomicUsize>) -> MethodBuilder<i32> {
+        MethodBuilder::new(format!("{}_to_{}", from, to))
+            .input(from)
+            .outputs(vec![MethodOutput::new(to)])
+            .apply(move |mut args| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let value: &i32 = args.remove(0).try_into_ref()?;
+                Ok(vec![value * 2])
+            })
+    }
+
+    #[test]
+    fn pure_methods_reuse_outputs() {
+        let pure_calls = Arc::new(AtomicUsize::new(0));
+        let impure_calls = Arc::new(AtomicUsize::new(0));
+        let mut component: Component<i32> = ComponentBuilder::new("Component")
+            .variables(vec![("a", 1), ("b", 0), ("c", 0), ("d", 0)])
+            .constraint(ConstraintBuilder::new("Pure").method(counted_double(
+                "a",
+                "b",
+                pure_calls.clone(),
+            )))
+            .constraint(
+                ConstraintBuilder::new("Impure")
+                    .method(counted_double("c", "d", impure_calls.clone()).pure(false)),
+            )
+            .build()
+            .unwrap();
+        component.solve().unwrap();
+        component.solve().unwrap();
+        assert_eq!(pure_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(impure_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(component.value("b"), Ok(Activation::from(2)));
+
+        // New input values are not reused
+        component.edit("a", 1).unwrap();
+        component.solve().unwrap();
+        assert_eq!(pure_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn equal_outputs_cut_off_propagation() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut component: Component<i32> = ComponentBuilder::new("Component")
+            .variables(vec![("a", 4), ("b", 0), ("c", 0)])
+            .constraint(
+                ConstraintBuilder::new("Half").method(
+                    MethodBuilder::new("half")
+                        .input("a")
+                        .outputs(vec![MethodOutput::new("b")])
+                        .compare_outputs()
+                        .apply(|mut args| {
+                            let a: &i32 = args.remove(0).try_into_ref()?;
+                            Ok(vec![a / 2])
+                        }),
+                ),
+            )
+            .constraint(ConstraintBuilder::new("Double").method(counted_double(
+                "b",
+                "c",
+                calls.clone(),
+            )))
+            .build()
+            .unwrap();
+        component.solve().unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        component
+            .subscribe("b", move |event| {
+                // Changed values are recorded as `Some`, unchanged ones as `None`
+                match event {
+                    Event::Ready(Ready::Changed(&value)) => {
+                        events_clone.lock().unwrap().push(Some(value))
+                    }
+                    Event::Ready(Ready::Unchanged) => events_clone.lock().unwrap().push(None),
+                    _ => {}
+                }
+            })
+            .unwrap();
+        events.lock().unwrap().clear();
+
+        // Half of 5 is still 2, so the doubling is not run again
+        component.edit("a", 5).unwrap();
+        component.solve().unwrap();
+        assert_eq!(*events.lock().unwrap(), vec![None]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(component.value("c"), Ok(Activation::from(4)));
+
+        component.edit("a", 6).unwrap();
+        component.solve().unwrap();
+        assert_eq!(*events.lock().unwrap(), vec![None, Some(3)]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(component.value("c"), Ok(Activation::from(6)));
+    }
+
+    #[test]
+    fn builds_methods_with_timeouts() {
+        let component: Component<i32> = ComponentBuilder::new("Component")
+            .variables(vec![("a", 3), ("b", 0)])
+            .constraint(
+                ConstraintBuilder::new("Double")
+                    .method(
+                        MethodBuilder::new("double")
+                            .input("a")
+                            .outputs(vec![MethodOutput::new("b")])
+                            .apply(|_| Ok(vec![0])),
+                    )
+                    .method(
+                        MethodBuilder::new("half")
+                            .input("b")
+                            .outputs(vec![MethodOutput::new("a")])
+                            .timeout(Duration::from_secs(1))
+                            .apply(|_| Ok(vec![0])),
+                    ),
+            )
+            .method_timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        assert_eq!(component.method_timeout(), Some(Duration::from_secs(5)));
+        let timeouts: Vec<_> = component.constraints()[0]
+            .methods()
+            .iter()
+            .map(|m| m.timeout())
+            .collect();
+        assert_eq!(timeouts, vec![None, Some(Duration::from_secs(1))]);
+    }
+
+    #[test]
+    fn values_are_mutated_in_place_without_undo() {
+        let addresses = Arc::new(Mutex::new(Vec::new()));
+        let mut component = resizer(true, addresses.clone());
+        component.set_undo_limit(UndoLimit::Limited(0));
+        for size in 2..5 {
+            component.edit("size", vec![size]).unwrap();
+            component.solve().unwrap();
+            assert_eq!(
+                component.value("buffer"),
+                Ok(Activation::from(vec![0; size as usize]))
+            );
+            assert_eq!(
+                component.value("length"),
+                Ok(Activation::from(vec![size - 1]))
+            );
+        }
+        let addresses = addresses.lock().unwrap();
+        assert_eq!(addresses.len(), 3);
+        assert!(addresses.iter().all(|&a| a == addresses[0]));
+    }
+
+    #[test]
+    fn values_kept_for_undo_are_copied() {
+        let addresses = Arc::new(Mutex::new(Vec::new()));
+        let mut component = resizer(true, addresses.clone());
+        component.edit("size", vec![2]).unwrap();
+        component.solve().unwrap();
+        component.edit("size", vec![3]).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("buffer"), Ok(Activation::from(vec![0; 3])));
+        component.undo().unwrap();
+        assert_eq!(component.value("buffer"), Ok(Activation::from(vec![0; 2])));
+        let addresses = addresses.lock().unwrap();
+        assert_ne!(addresses[0], addresses[1]);
+    }
+
+    #[test]
+    fn mutating_immutable_variables_is_reported() {
+        let mut component = resizer(false, Arc::new(Mutex::new(Vec::new())));
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let reasons_clone = reasons.clone();
+        component
+            .subscribe("length", move |event| {
+                if let Event::Error(errors) = event {
+                    let mut reasons = reasons_clone.lock().unwrap();
+                    reasons.extend(errors.iter().map(|e| e.reason().clone()));
+                }
+            })
+            .unwrap();
+        component.edit("size", vec![2]).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("buffer"), Ok(Activation::from(vec![0])));
+        assert_eq!(
+            reasons.lock().unwrap().pop(),
+            Some(Reason::MethodFailure(MethodFailure::MutabilityMismatch(
+                MutabilityMismatch::ExpectedMutableGotImmutable
+            )))
+        );
+    }
 }
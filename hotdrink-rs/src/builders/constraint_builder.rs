@@ -1,18 +1,21 @@
 //! A builder-struct for programmatically creating constraints.
 
-use super::method_builder::MethodBuilder;
+use super::{component_builder::BuildError, method_builder::MethodBuilder};
+use crate::model::Constraint;
 use std::fmt::Debug;
 
 /// A builder for making programmatic construction of constraints easier.
 #[derive(Clone, Debug)]
 pub struct ConstraintBuilder<T> {
+    name: String,
     methods: Vec<MethodBuilder<T>>,
 }
 
 impl<T> ConstraintBuilder<T> {
     /// Constructs a new `ConstraintBuilder`.
-    pub fn new<S: Into<String>>(_: S) -> Self {
+    pub fn new<S: Into<String>>(name: S) -> Self {
         Self {
+            name: name.into(),
             methods: Vec::new(),
         }
     }
@@ -22,6 +25,29 @@ impl<T> ConstraintBuilder<T> {
         self.methods.push(method);
         self
     }
+
+    /// Returns the name of the constraint.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Converts this [`ConstraintBuilder`] into a [`Constraint`],
+    /// checking that its methods only refer to `variables`.
+    pub(crate) fn into_constraint(
+        self,
+        variables: &[(String, bool)],
+    ) -> Result<Constraint<T>, BuildError>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let name = self.name;
+        let methods = self
+            .methods
+            .into_iter()
+            .map(|m| m.into_method(&name, variables))
+            .collect::<Result<_, _>>()?;
+        Ok(Constraint::new_with_name(name, methods))
+    }
 }
 
 #[cfg(test)]
@@ -1,9 +1,14 @@
 //! A module to simplify method construction.
 
-use crate::planner::MethodFailure;
+use super::component_builder::BuildError;
+use crate::{
+    executor::CancellationToken,
+    model::{Method, OutputEquality},
+    planner::{AsyncMethodFunction, MethodFailure, MethodFuture},
+};
 use itertools::Itertools;
 use std::sync::Arc;
-use std::{convert::TryInto, fmt::Debug};
+use std::{convert::TryInto, fmt::Debug, future::Future, time::Duration};
 
 /// A slightly different version of [`crate::planner::MethodResult`].
 pub type MethodResult<T> = Result<Vec<T>, MethodFailure>;
@@ -29,6 +34,13 @@ impl MethodInput {
     pub fn make_mut_ref<S: Into<String>>(name: S) -> Self {
         MethodInput::MutRef(name.into())
     }
+
+    /// Returns the name of the input variable.
+    pub fn name(&self) -> &str {
+        match self {
+            MethodInput::Ref(name) | MethodInput::MutRef(name) => name,
+        }
+    }
 }
 
 /// An output of a method.
@@ -43,6 +55,11 @@ impl MethodOutput {
     pub fn new<S: Into<String>>(name: S) -> Self {
         Self { name: name.into() }
     }
+
+    /// Returns the name of the output variable.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 /// An argument passed in to a method.
@@ -159,7 +176,11 @@ impl<'a, T> MethodArg<'a, T> {
     }
 }
 
-type MethodFunctionInner<T> = Arc<dyn for<'a> Fn(Vec<MethodArg<'a, T>>) -> MethodResult<T>>;
+type MethodFunctionInner<T> =
+    Arc<dyn for<'a> Fn(Vec<MethodArg<'a, T>>) -> MethodResult<T> + Send + Sync>;
+
+type CancellableFunctionInner<T> =
+    Arc<dyn for<'a> Fn(Vec<MethodArg<'a, T>>, &CancellationToken) -> MethodResult<T> + Send + Sync>;
 
 /// A builder for making programmatic construction of methods easier.
 #[derive(Clone)]
@@ -168,7 +189,11 @@ pub struct MethodBuilder<T> {
     inputs: Vec<MethodInput>,
     outputs: Vec<MethodOutput>,
     apply: Option<MethodFunctionInner<T>>,
+    apply_cancellable: Option<CancellableFunctionInner<T>>,
+    apply_async: Option<AsyncMethodFunction<T>>,
     pure: bool,
+    output_equality: Option<OutputEquality<T>>,
+    timeout: Option<Duration>,
 }
 
 impl<T> MethodBuilder<T> {
@@ -179,7 +204,11 @@ impl<T> MethodBuilder<T> {
             inputs: Vec::new(),
             outputs: Vec::new(),
             apply: None,
+            apply_cancellable: None,
+            apply_async: None,
             pure: true,
+            output_equality: None,
+            timeout: None,
         }
     }
 
@@ -214,12 +243,62 @@ impl<T> MethodBuilder<T> {
     /// Set the function to run when this method is applied.
     /// This function takes a slice with a length corresponding to its inputs as input,
     /// and should return a vector of length corresponding to its outputs.
+    /// It must be `Send + Sync`, since the built method may be run by a
+    /// [`MethodExecutor`](crate::executor::MethodExecutor) on other threads.
     #[must_use]
     pub fn apply(
         mut self,
-        apply: impl for<'a> Fn(Vec<MethodArg<'a, T>>) -> MethodResult<T> + 'static,
+        apply: impl for<'a> Fn(Vec<MethodArg<'a, T>>) -> MethodResult<T> + Send + Sync + 'static,
     ) -> Self {
         self.apply = Some(Arc::new(apply));
+        self.apply_cancellable = None;
+        self.apply_async = None;
+        self
+    }
+
+    /// Set the function to run when this method is applied, like [`apply`](Self::apply),
+    /// but also pass it a [`CancellationToken`].
+    ///
+    /// The token is cancelled once the result is no longer needed, for instance because
+    /// a newer solve has replaced it. Long-running functions can check it and return early,
+    /// since the result of a cancelled method is discarded anyway.
+    #[must_use]
+    pub fn apply_cancellable(
+        mut self,
+        apply: impl for<'a> Fn(Vec<MethodArg<'a, T>>, &CancellationToken) -> MethodResult<T>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.apply_cancellable = Some(Arc::new(apply));
+        self.apply = None;
+        self.apply_async = None;
+        self
+    }
+
+    /// Set an asynchronous function to run when this method is applied.
+    /// This function takes the values of its inputs, and returns a future
+    /// that resolves to a vector of length corresponding to its outputs.
+    ///
+    /// The future is polled on the executor the component is solved with.
+    /// Its inputs are never mutated in place, even if they are added with [`input_mut`](Self::input_mut).
+    #[must_use]
+    pub fn apply_async<F>(
+        mut self,
+        apply: impl Fn(Vec<Arc<T>>) -> F + Send + Sync + 'static,
+    ) -> Self
+    where
+        F: Future<Output = MethodResult<T>> + Send + 'static,
+    {
+        self.apply_async = Some(Arc::new(move |values| {
+            let future = apply(values);
+            Box::pin(async move {
+                let results = future.await?;
+                Ok(results.into_iter().map(Arc::new).collect())
+            }) as MethodFuture<T>
+        }));
+        self.apply = None;
+        self.apply_cancellable = None;
         self
     }
 
@@ -231,11 +310,153 @@ impl<T> MethodBuilder<T> {
     /// If it is not pure, it will be re-run every update.
     /// Set this to false if the method reads from or writes to something other than
     /// its inputs and outputs.
+    /// Methods are pure by default, see [`Method::set_pure`] for when inputs count as changed.
     #[must_use]
     pub fn pure(mut self, pure: bool) -> Self {
         self.pure = pure;
         self
     }
+
+    /// Compare the outputs of this method to the previous values of the variables,
+    /// so that equal values do not cause pure methods that read them to run again.
+    /// See [`Method::compare_outputs`].
+    #[must_use]
+    pub fn compare_outputs(mut self) -> Self
+    where
+        T: PartialEq + 'static,
+    {
+        self.output_equality = Some(Arc::new(|previous: &T, new: &T| previous == new));
+        self
+    }
+
+    /// Set the maximum time the method may run for.
+    ///
+    /// If it has not finished by then, its outputs get a [`Reason::Timeout`](crate::solver::Reason::Timeout) error.
+    /// This overrides the timeout of the component, see [`Method::set_timeout`].
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Converts this [`MethodBuilder`] into a [`Method`].
+    /// The index of each variable is its position in `variables`, along with whether it is mutable.
+    ///
+    /// Mutable inputs of mutable variables are mutated in place, and become outputs of the method.
+    /// Mutable inputs of immutable variables are passed as immutable references,
+    /// meaning that trying to mutate them gives [`MutabilityMismatch::ExpectedMutableGotImmutable`].
+    ///
+    /// Returns [`BuildError::NoSuchVariable`] if an input or output is not among `variables`,
+    /// and [`BuildError::MissingApply`] if no function was set.
+    /// Methods with an asynchronous function never mutate their inputs in place.
+    pub(crate) fn into_method(
+        self,
+        constraint: &str,
+        variables: &[(String, bool)],
+    ) -> Result<Method<T>, BuildError>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let (pure, output_equality, timeout) =
+            (self.pure, self.output_equality.clone(), self.timeout);
+        let mut method = self.into_method_without_options(constraint, variables)?;
+        method.set_pure(pure);
+        method.set_output_equality(output_equality);
+        method.set_timeout(timeout);
+        Ok(method)
+    }
+
+    /// Converts this [`MethodBuilder`] into a [`Method`] like [`into_method`](Self::into_method),
+    /// but ignores whether it is pure, how to compare outputs, and its timeout.
+    fn into_method_without_options(
+        self,
+        constraint: &str,
+        variables: &[(String, bool)],
+    ) -> Result<Method<T>, BuildError>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let index = |name: &str| variables.iter().position(|(v, _)| v == name);
+        let undefined = self
+            .inputs
+            .iter()
+            .map(MethodInput::name)
+            .chain(self.outputs.iter().map(MethodOutput::name))
+            .find(|name| index(name).is_none());
+        if let Some(variable) = undefined {
+            return Err(BuildError::NoSuchVariable {
+                constraint: constraint.to_owned(),
+                method: self.name,
+                variable: variable.to_owned(),
+            });
+        }
+        let arguments: Vec<usize> = self
+            .inputs
+            .iter()
+            .map(|i| index(i.name()).expect("inputs were checked"))
+            .collect();
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|o| index(o.name()).expect("outputs were checked"))
+            .collect();
+        let apply: CancellableFunctionInner<T> =
+            match (self.apply, self.apply_cancellable, self.apply_async) {
+                (Some(apply), _, _) => Arc::new(move |args, _| apply(args)),
+                (None, Some(apply), _) => apply,
+                (None, None, Some(apply)) => {
+                    return Ok(Method::new_async(self.name, arguments, outputs, apply));
+                }
+                (None, None, None) => {
+                    return Err(BuildError::MissingApply {
+                        constraint: constraint.to_owned(),
+                        method: self.name,
+                    })
+                }
+            };
+        let mutable: Vec<usize> = self
+            .inputs
+            .iter()
+            .zip(&arguments)
+            .enumerate()
+            .filter(|(_, (input, &vi))| matches!(input, MethodInput::MutRef(_)) && variables[vi].1)
+            .map(|(p, _)| p)
+            .collect();
+
+        if mutable.is_empty() {
+            return Ok(Method::new_cancellable(
+                self.name,
+                arguments,
+                outputs,
+                Arc::new(move |values: &mut [Arc<T>], token: &CancellationToken| {
+                    let args = values.iter().map(|v| MethodArg::Ref(&**v)).collect();
+                    let results = apply(args, token)?;
+                    Ok(results.into_iter().map(Arc::new).collect())
+                }),
+            ));
+        }
+
+        let is_mutable: Vec<bool> = (0..arguments.len()).map(|p| mutable.contains(&p)).collect();
+        Ok(Method::new_in_place(
+            self.name,
+            arguments,
+            mutable,
+            outputs,
+            Arc::new(move |values: &mut [Arc<T>], token: &CancellationToken| {
+                // Only copies the values that are referred to from elsewhere
+                let args = values
+                    .iter_mut()
+                    .zip(&is_mutable)
+                    .map(|(value, &is_mutable)| match is_mutable {
+                        true => MethodArg::MutRef(Arc::make_mut(value)),
+                        false => MethodArg::Ref(&**value),
+                    })
+                    .collect();
+                let results = apply(args, token)?;
+                Ok(results.into_iter().map(Arc::new).collect())
+            }),
+        ))
+    }
 }
 
 impl<T> Debug for MethodBuilder<T> {
@@ -19,7 +19,7 @@ pub mod constraint_builder;
 pub mod method_builder;
 pub mod value_experiments;
 
-pub use component_builder::ComponentBuilder;
+pub use component_builder::{BuildError, ComponentBuilder};
 pub use constraint_builder::ConstraintBuilder;
 pub use method_builder::MethodArg;
 pub use method_builder::MethodBuilder;
@@ -49,6 +49,20 @@ impl<T> Value<T> {
         }
     }
 
+    /// Returns the inner value, cloning it if the value is shared.
+    pub fn into_inner(self) -> T
+    where
+        T: Clone,
+    {
+        match self {
+            Value::Ref(value) => Arc::try_unwrap(value).unwrap_or_else(|value| T::clone(&value)),
+            Value::MutRef(value) => match Arc::try_unwrap(value) {
+                Ok(lock) => lock.into_inner().expect("Lock was poisoned"),
+                Err(value) => value.read().expect("Lock was poisoned").clone(),
+            },
+        }
+    }
+
     /// Returns true if the inner value can be mutated in-place.
     pub fn is_mutable(&self) -> bool {
         matches!(self, Value::MutRef(_))
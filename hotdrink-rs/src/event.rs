@@ -1,12 +1,14 @@
 //! Types for representing events from the constraint system.
 
 use crate::model::generation_id::GenerationId;
+use derivative::Derivative;
 use std::fmt::Debug;
 
 /// The value inside a [`Ready`](Event::Ready) event.
 ///
 /// It may either be a new value, or a notification that the value is unchanged.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Derivative, Debug, PartialEq, Eq)]
+#[derivative(Copy(bound = ""), Clone(bound = ""))]
 pub enum Ready<'a, T> {
     /// A new value has been produced.
     Changed(&'a T),
@@ -15,7 +17,8 @@ pub enum Ready<'a, T> {
 }
 
 /// An event from the constraint system.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Derivative, Debug, PartialEq, Eq)]
+#[derivative(Copy(bound = ""), Clone(bound = ""))]
 pub enum Event<'a, T, E> {
     /// The value is being computed.
     Pending,
@@ -25,6 +28,54 @@ pub enum Event<'a, T, E> {
     Error(&'a Vec<E>),
 }
 
+/// An owned version of [`Ready`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OwnedReady<T> {
+    /// A new value has been produced.
+    Changed(T),
+    /// No new value has been produced, but previous errors no longer apply and can be cleared.
+    Unchanged,
+}
+
+/// An owned version of [`Event`], which can be kept after the callback it was passed to returns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OwnedEvent<T, E> {
+    /// The value is being computed.
+    Pending,
+    /// The computation succeeded.
+    Ready(OwnedReady<T>),
+    /// The computation failed.
+    Error(Vec<E>),
+}
+
+impl<T, E> OwnedEvent<T, E> {
+    /// Returns true if the event is not [`Pending`](Self::Pending).
+    pub fn is_settled(&self) -> bool {
+        !matches!(self, Self::Pending)
+    }
+
+    /// Borrows the event as an [`Event`].
+    pub fn as_event(&self) -> Event<'_, T, E> {
+        match self {
+            Self::Pending => Event::Pending,
+            Self::Ready(OwnedReady::Changed(value)) => Event::Ready(Ready::Changed(value)),
+            Self::Ready(OwnedReady::Unchanged) => Event::Ready(Ready::Unchanged),
+            Self::Error(errors) => Event::Error(errors),
+        }
+    }
+}
+
+impl<T: Clone, E: Clone> From<Event<'_, T, E>> for OwnedEvent<T, E> {
+    fn from(event: Event<'_, T, E>) -> Self {
+        match event {
+            Event::Pending => Self::Pending,
+            Event::Ready(Ready::Changed(value)) => Self::Ready(OwnedReady::Changed(value.clone())),
+            Event::Ready(Ready::Unchanged) => Self::Ready(OwnedReady::Unchanged),
+            Event::Error(errors) => Self::Error(errors.clone()),
+        }
+    }
+}
+
 /// An event from [`ConstraintSystem::solve`](crate::model::ConstraintSystem::solve) with information about
 /// which variable it is, and which generation the computation is from.
 #[derive(Debug)]
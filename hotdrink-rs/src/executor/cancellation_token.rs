@@ -0,0 +1,48 @@
+//! A token that lets method bodies know when their results are no longer needed.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A token that is cancelled once nobody needs the result of a method call,
+/// for instance because newer values are being computed.
+///
+/// Long-running method bodies can check it with [`is_cancelled`](Self::is_cancelled) and return early.
+/// Whatever a cancelled method returns is discarded, and its outputs end with
+/// [`Reason::Cancelled`](crate::solver::Reason::Cancelled).
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Constructs a new [`CancellationToken`] that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token and all its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancellationToken;
+
+    #[test]
+    fn clones_are_cancelled_together() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+}
@@ -1,9 +1,10 @@
 //! Trait and types for method executors.
 
+use super::Spawner;
 use derivative::Derivative;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc, Mutex,
+    Arc, Mutex, Weak,
 };
 use std::{fmt::Debug, sync::atomic::AtomicUsize};
 
@@ -17,6 +18,15 @@ pub trait MethodExecutor {
         &self,
         f: impl FnOnce() + Send + 'static,
     ) -> Result<TerminationHandle, Self::ExecError>;
+
+    /// Returns a [`Spawner`] that can submit work to the [`MethodExecutor`] later, from any thread.
+    ///
+    /// If one is provided, methods are only submitted once their inputs are resolved.
+    /// Otherwise, which is the default, they are submitted right away and wait for their inputs on the executor.
+    /// Of the executors in this crate, [`ThreadExecutor`](super::ThreadExecutor) and a shared rayon thread pool provide one.
+    fn spawner(&self) -> Option<Spawner> {
+        None
+    }
 }
 
 /// As long as at least one clone of this handle exists,
@@ -62,10 +72,45 @@ impl TerminationHandle {
         self.num_references.load(Ordering::SeqCst)
     }
 
+    /// Sets the termination flag, even if other handles still exist.
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    /// Returns the termination flag, which is false once the result is no longer needed.
+    /// Unlike a clone of the handle, it does not keep the computation alive.
+    pub(crate) fn result_needed(&self) -> Arc<AtomicBool> {
+        self.inner.result_needed.clone()
+    }
+
     /// Gives the handle a task to perform when all handles are dropped.
     pub fn on_drop(&self, on_drop: impl FnOnce() + Send + Sync + 'static) {
         self.inner.on_drop(on_drop);
     }
+
+    /// Returns a reference to the handle that does not keep the computation alive.
+    pub(crate) fn downgrade(&self) -> WeakTerminationHandle {
+        WeakTerminationHandle(Arc::downgrade(&self.inner))
+    }
+}
+
+/// A reference to a [`TerminationHandle`] that can abort the computation,
+/// but that does not keep it alive like a clone of the handle would.
+#[derive(Clone, Debug)]
+pub(crate) struct WeakTerminationHandle(Weak<InnerHandle>);
+
+impl WeakTerminationHandle {
+    /// Sets the termination flag, and performs the task given with [`TerminationHandle::on_drop`] right away
+    /// instead of when all handles are dropped.
+    pub fn abort(&self) {
+        if let Some(inner) = self.0.upgrade() {
+            inner.cancel();
+            let on_drop = inner.on_drop.lock().unwrap().take();
+            if let Some(f) = on_drop {
+                f();
+            }
+        }
+    }
 }
 
 /// A function to call upon calling [`Drop::drop`].
@@ -105,10 +150,13 @@ impl Drop for InnerHandle {
 
 #[cfg(test)]
 mod tests {
-    #![allow(unused_variables, clippy::mutex_atomic)]
+    #![allow(unused_variables, clippy::mutex_atomic, clippy::bool_assert_comparison)]
 
     use super::TerminationHandle;
-    use std::sync::atomic::Ordering;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
 
     #[test]
     pub fn termination_handle_does_not_set_flag_while_in_scope() {
@@ -139,4 +187,16 @@ mod tests {
         };
         assert_eq!(flag.load(Ordering::SeqCst), false);
     }
+
+    #[test]
+    pub fn aborting_weak_handle_sets_flag_and_runs_task() {
+        let (th, flag) = TerminationHandle::new();
+        let ran = Arc::new(AtomicBool::new(false));
+        let ran_clone = ran.clone();
+        th.on_drop(move || ran_clone.store(true, Ordering::SeqCst));
+        th.downgrade().abort();
+        assert!(!flag.load(Ordering::SeqCst));
+        assert!(ran.load(Ordering::SeqCst));
+        assert_eq!(th.num_references(), 1);
+    }
 }
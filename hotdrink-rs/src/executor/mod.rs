@@ -1,9 +1,21 @@
 //! A trait and implementations for method executors.
 
+mod cancellation_token;
 mod dummy_executor;
 mod method_executor;
+mod poll;
 #[cfg(feature = "rayon")]
 mod rayon_executor;
+mod scheduler;
+mod thread_executor;
+mod timeout;
 
+pub use cancellation_token::CancellationToken;
 pub use dummy_executor::DummyExecutor;
+pub(crate) use method_executor::WeakTerminationHandle;
 pub use method_executor::{MethodExecutor, TerminationHandle};
+pub(crate) use poll::drive;
+pub(crate) use scheduler::schedule_when_ready;
+pub use scheduler::{Spawner, Work};
+pub use thread_executor::ThreadExecutor;
+pub(crate) use timeout::watch;
@@ -0,0 +1,171 @@
+//! Driving the futures of asynchronous methods to completion.
+//!
+//! A future is polled on the executor again whenever it is woken,
+//! by submitting the poll to the [`Spawner`] of the executor.
+//! Executors without a [`Spawner`] block on the future instead.
+
+use super::{Spawner, Work};
+use futures::task::{waker_ref, ArcWake};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+
+/// A future with its output already taken care of.
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A [`Task`] that is polled on a [`Spawner`] when woken.
+struct Spawned {
+    task: Mutex<Option<Task>>,
+    spawner: Spawner,
+    /// Cleared once the task has completed, so that later polls are not needed.
+    result_needed: Arc<AtomicBool>,
+}
+
+impl Spawned {
+    /// Polls the task once, unless it has completed.
+    /// The lock is held while polling, so that a wakeup during the poll causes another one.
+    fn poll(self: &Arc<Self>) {
+        let mut slot = self.task.lock().unwrap();
+        if let Some(task) = slot.as_mut() {
+            let waker = waker_ref(self);
+            let mut cx = Context::from_waker(&waker);
+            if task.as_mut().poll(&mut cx).is_ready() {
+                *slot = None;
+                self.result_needed.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+impl ArcWake for Spawned {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        let spawned = arc_self.clone();
+        let result_needed = arc_self.result_needed.clone();
+        (arc_self.spawner)(Work::new(move || spawned.poll(), result_needed));
+    }
+}
+
+/// Polls `future` until it completes, and calls `on_done` with its output.
+///
+/// The future is dropped without calling `on_done` if `is_cancelled` returns true before a poll.
+pub(crate) fn drive<F: Future + Send + 'static>(
+    spawner: Option<Spawner>,
+    future: F,
+    is_cancelled: impl Fn() -> bool + Send + 'static,
+    on_done: impl FnOnce(F::Output) + Send + 'static,
+) {
+    let mut future = Box::pin(future);
+    let mut on_done = Some(on_done);
+    let task = futures::future::poll_fn(move |cx| {
+        if is_cancelled() {
+            log::trace!("Dropping cancelled future");
+            return Poll::Ready(());
+        }
+        future.as_mut().poll(cx).map(|output| {
+            if let Some(on_done) = on_done.take() {
+                on_done(output);
+            }
+        })
+    });
+    match spawner {
+        Some(spawner) => Arc::new(Spawned {
+            task: Mutex::new(Some(Box::pin(task))),
+            spawner,
+            result_needed: Arc::new(AtomicBool::new(true)),
+        })
+        .poll(),
+        None => futures::executor::block_on(task),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::drive;
+    use crate::executor::{Spawner, Work};
+    use futures::channel::oneshot;
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    /// A spawner that queues work until it is run manually.
+    fn queue() -> (Spawner, Arc<Mutex<VecDeque<Work>>>) {
+        let queue: Arc<Mutex<VecDeque<Work>>> = Arc::default();
+        let queue_clone = queue.clone();
+        let spawner: Spawner = Arc::new(move |work| queue_clone.lock().unwrap().push_back(work));
+        (spawner, queue)
+    }
+
+    fn run_next(queue: &Mutex<VecDeque<Work>>) {
+        let work = queue.lock().unwrap().pop_front();
+        work.expect("queue was empty").run();
+    }
+
+    #[test]
+    fn futures_are_polled_again_when_woken() {
+        let (spawner, queue) = queue();
+        let (sender, receiver) = oneshot::channel();
+        let output = Arc::new(Mutex::new(None));
+        let output_clone = output.clone();
+        drive(
+            Some(spawner),
+            receiver,
+            || false,
+            move |value| *output_clone.lock().unwrap() = Some(value),
+        );
+        assert!(queue.lock().unwrap().is_empty());
+        assert_eq!(*output.lock().unwrap(), None);
+
+        sender.send(3).unwrap();
+        assert_eq!(queue.lock().unwrap().len(), 1);
+        let result_needed = queue.lock().unwrap()[0].result_needed().clone();
+        assert!(result_needed.load(Ordering::SeqCst));
+        run_next(&queue);
+        assert_eq!(*output.lock().unwrap(), Some(Ok(3)));
+        // Nothing is left to poll
+        assert!(!result_needed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn cancelled_futures_are_dropped() {
+        let (spawner, queue) = queue();
+        let (sender, receiver) = oneshot::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled_clone = cancelled.clone();
+        let output = Arc::new(Mutex::new(None));
+        let output_clone = output.clone();
+        drive(
+            Some(spawner),
+            receiver,
+            move || cancelled_clone.load(Ordering::SeqCst),
+            move |value| *output_clone.lock().unwrap() = Some(value),
+        );
+
+        cancelled.store(true, Ordering::SeqCst);
+        sender.send(3).unwrap();
+        run_next(&queue);
+        assert_eq!(*output.lock().unwrap(), None);
+    }
+
+    #[test]
+    fn futures_are_blocked_on_without_spawner() {
+        let output = Arc::new(Mutex::new(None));
+        let output_clone = output.clone();
+        drive(
+            None,
+            async { 3 },
+            || false,
+            move |value| *output_clone.lock().unwrap() = Some(value),
+        );
+        assert_eq!(*output.lock().unwrap(), Some(3));
+    }
+}
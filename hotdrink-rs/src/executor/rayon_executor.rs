@@ -1,25 +1,57 @@
-use super::{MethodExecutor, TerminationHandle};
+use super::{MethodExecutor, Spawner, TerminationHandle};
+use std::sync::{atomic::Ordering, Arc};
 
+/// A borrowed thread pool can not be captured to run deferred methods later,
+/// so methods are submitted right away and wait for their inputs in the pool.
+/// Use an `Arc<ThreadPool>` to defer them instead.
 impl MethodExecutor for rayon::ThreadPool {
     type ExecError = ();
     fn schedule(
         &self,
         f: impl FnOnce() + Send + 'static,
     ) -> Result<TerminationHandle, Self::ExecError> {
-        let (th, _) = TerminationHandle::new();
-        self.spawn(f);
+        let (th, result_needed) = TerminationHandle::new();
+        self.spawn(move || {
+            // Skip the work if nobody needs its result anymore
+            if result_needed.load(Ordering::SeqCst) {
+                f()
+            }
+        });
         Ok(th)
     }
 }
 
+/// Like a borrowed thread pool, a scope submits methods right away,
+/// so that it waits for all of them before returning.
 impl MethodExecutor for rayon::Scope<'_> {
     type ExecError = ();
     fn schedule(
         &self,
         f: impl FnOnce() + Send + 'static,
     ) -> Result<TerminationHandle, Self::ExecError> {
-        let (th, _) = TerminationHandle::new();
-        self.spawn(|_| f());
+        let (th, result_needed) = TerminationHandle::new();
+        self.spawn(move |_| {
+            if result_needed.load(Ordering::SeqCst) {
+                f()
+            }
+        });
         Ok(th)
     }
 }
+
+/// A shared thread pool can also defer methods until their inputs are resolved,
+/// meaning that no threads are blocked while waiting for them.
+impl MethodExecutor for Arc<rayon::ThreadPool> {
+    type ExecError = ();
+    fn schedule(
+        &self,
+        f: impl FnOnce() + Send + 'static,
+    ) -> Result<TerminationHandle, Self::ExecError> {
+        (**self).schedule(f)
+    }
+
+    fn spawner(&self) -> Option<Spawner> {
+        let pool = self.clone();
+        Some(Arc::new(move |work| pool.spawn(move || work.run())))
+    }
+}
@@ -0,0 +1,246 @@
+//! Scheduling of work once the [`Activation`]s it depends on are resolved.
+//!
+//! Methods in a plan depend on the outputs of the methods before them.
+//! Rather than having a method wait for its inputs on a worker thread,
+//! it is only submitted to the executor once all of them are resolved.
+//! This requires a [`Spawner`] from [`MethodExecutor::spawner`], since the last input
+//! is usually resolved on a worker thread after scheduling has returned.
+
+use super::{MethodExecutor, TerminationHandle};
+use crate::model::Activation;
+use derivative::Derivative;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+/// Work to be submitted to a [`Spawner`].
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct Work {
+    #[derivative(Debug = "ignore")]
+    work: Box<dyn FnOnce() + Send>,
+    result_needed: Arc<AtomicBool>,
+}
+
+impl Work {
+    /// Constructs new [`Work`] from a function to run,
+    /// and a flag that is cleared once its result is no longer needed.
+    pub fn new(work: impl FnOnce() + Send + 'static, result_needed: Arc<AtomicBool>) -> Self {
+        Self {
+            work: Box::new(work),
+            result_needed,
+        }
+    }
+
+    /// Returns the flag that is cleared once the result of the work is no longer needed.
+    /// Executors can use it to stop workers that are still running the work.
+    pub fn result_needed(&self) -> &Arc<AtomicBool> {
+        &self.result_needed
+    }
+
+    /// Runs the work.
+    pub fn run(self) {
+        (self.work)()
+    }
+}
+
+/// A function that submits work to an executor, and that can be called from any thread.
+pub type Spawner = Arc<dyn Fn(Work) + Send + Sync>;
+
+/// Schedules `f` on `me` once all `dependencies` are resolved.
+///
+/// If they are all resolved already, or if `me` does not provide a [`Spawner`],
+/// `f` is scheduled right away.
+pub(crate) fn schedule_when_ready<T, E: MethodExecutor>(
+    me: &E,
+    dependencies: Vec<Activation<T>>,
+    f: impl FnOnce() + Send + 'static,
+) -> Result<TerminationHandle, E::ExecError> {
+    // The dependencies are dropped before scheduling,
+    // so that they do not keep values alive while `f` is running.
+    let spawner = match me.spawner() {
+        Some(spawner) if !dependencies.is_empty() => spawner,
+        _ => {
+            drop(dependencies);
+            return me.schedule(f);
+        }
+    };
+
+    // One extra count is held while listeners are being registered,
+    // so that `f` is not submitted before we know if it must be deferred.
+    let remaining = Arc::new(AtomicUsize::new(dependencies.len() + 1));
+    let (handle, result_needed) = TerminationHandle::new();
+    let work = Arc::new(Mutex::new(Some(Work::new(f, result_needed.clone()))));
+    for dependency in &dependencies {
+        let remaining = remaining.clone();
+        let work = work.clone();
+        let spawner = spawner.clone();
+        let result_needed = result_needed.clone();
+        dependency.on_resolved(Box::new(move || {
+            if remaining.fetch_sub(1, Ordering::SeqCst) == 1 {
+                let work = work.lock().unwrap().take();
+                // Skip the work if nobody needs its result
+                if let (Some(work), true) = (work, result_needed.load(Ordering::SeqCst)) {
+                    log::trace!("Inputs are ready, submitting deferred work");
+                    spawner(work);
+                }
+            }
+        }));
+    }
+    drop(dependencies);
+
+    if remaining.fetch_sub(1, Ordering::SeqCst) == 1 {
+        // Everything was resolved already
+        let work = work.lock().unwrap().take().expect("work was not submitted");
+        return me.schedule(move || work.run());
+    }
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Spawner, Work};
+    use crate::{
+        component,
+        executor::{MethodExecutor, TerminationHandle, ThreadExecutor},
+        model::{Activation, Component},
+        ret,
+    };
+    use std::{
+        collections::VecDeque,
+        sync::{atomic::Ordering, Arc, Mutex},
+    };
+
+    /// An executor that queues work until it is run manually.
+    #[derive(Clone, Default)]
+    struct QueueExecutor {
+        queue: Arc<Mutex<VecDeque<Work>>>,
+        defer: bool,
+    }
+
+    impl QueueExecutor {
+        fn len(&self) -> usize {
+            self.queue.lock().unwrap().len()
+        }
+
+        fn run_next(&self) {
+            let work = self.queue.lock().unwrap().pop_front();
+            work.expect("queue was empty").run();
+        }
+    }
+
+    impl MethodExecutor for QueueExecutor {
+        type ExecError = ();
+
+        fn schedule(
+            &self,
+            f: impl FnOnce() + Send + 'static,
+        ) -> Result<TerminationHandle, Self::ExecError> {
+            let (handle, result_needed) = TerminationHandle::new();
+            self.queue
+                .lock()
+                .unwrap()
+                .push_back(Work::new(f, result_needed));
+            Ok(handle)
+        }
+
+        fn spawner(&self) -> Option<Spawner> {
+            let queue = self.queue.clone();
+            self.defer
+                .then(|| Arc::new(move |work| queue.lock().unwrap().push_back(work)) as Spawner)
+        }
+    }
+
+    fn chain() -> Component<i32> {
+        component! {
+            component Chain {
+                let a: i32 = 0, b: i32 = 0, c: i32 = 0;
+                constraint Ab {
+                    ab(a: &i32) -> [b] = ret![*a + 1];
+                }
+                constraint Bc {
+                    bc(b: &i32) -> [c] = ret![*b + 1];
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn methods_are_submitted_once_inputs_are_resolved() {
+        let executor = QueueExecutor {
+            defer: true,
+            ..Default::default()
+        };
+        let mut component = chain();
+        component.edit("a", 1).unwrap();
+        component.par_solve(&executor).unwrap();
+
+        // Only the first method can run
+        assert_eq!(executor.len(), 1);
+        executor.run_next();
+        assert_eq!(component.value("b"), Ok(Activation::from(2)));
+
+        // Which submits the next one
+        assert_eq!(executor.len(), 1);
+        executor.run_next();
+        assert_eq!(executor.len(), 0);
+        assert_eq!(component.value("c"), Ok(Activation::from(3)));
+    }
+
+    #[test]
+    fn deferred_methods_know_if_their_results_are_needed() {
+        let executor = QueueExecutor {
+            defer: true,
+            ..Default::default()
+        };
+        let mut component = chain();
+        component.edit("a", 1).unwrap();
+        component.par_solve(&executor).unwrap();
+        executor.run_next();
+        let result_needed = executor.queue.lock().unwrap()[0].result_needed().clone();
+        assert!(result_needed.load(Ordering::SeqCst));
+
+        // Nobody needs the value of `c` once the component is gone
+        drop(component);
+        assert!(!result_needed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn methods_are_submitted_right_away_without_spawner() {
+        let executor = QueueExecutor::default();
+        let mut component = chain();
+        component.edit("a", 1).unwrap();
+        component.par_solve(&executor).unwrap();
+        assert_eq!(executor.len(), 2);
+        executor.run_next();
+        executor.run_next();
+        assert_eq!(component.value("c"), Ok(Activation::from(3)));
+    }
+
+    #[test]
+    fn thread_executor_defers_methods() {
+        assert!(ThreadExecutor.spawner().is_some());
+        let mut component = chain();
+        component.edit("a", 1).unwrap();
+        let handle = component.par_solve(&ThreadExecutor).unwrap();
+        futures::executor::block_on(handle).unwrap();
+        assert_eq!(component.value("c"), Ok(Activation::from(3)));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn shared_rayon_thread_pool_defers_methods() {
+        let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
+        // Borrowed pools and scopes can not be captured, so they run methods right away
+        assert!(pool.spawner().is_none());
+        pool.scope(|scope| assert!(scope.spawner().is_none()));
+        let pool = Arc::new(pool);
+        assert!(pool.spawner().is_some());
+        let mut component = chain();
+        component.edit("a", 1).unwrap();
+        let handle = component.par_solve(&pool).unwrap();
+        futures::executor::block_on(handle).unwrap();
+        assert_eq!(component.value("c"), Ok(Activation::from(3)));
+    }
+}
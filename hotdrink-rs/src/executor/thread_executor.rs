@@ -0,0 +1,35 @@
+//! A method executor that runs each method on a new thread.
+
+use super::{MethodExecutor, Spawner, TerminationHandle};
+use std::sync::{atomic::Ordering, Arc};
+
+/// A method executor that runs each method on a new thread.
+/// Unlike a thread pool, a method that never finishes does not hold back other methods.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ThreadExecutor;
+
+impl MethodExecutor for ThreadExecutor {
+    type ExecError = std::io::Error;
+
+    fn schedule(
+        &self,
+        f: impl FnOnce() + Send + 'static,
+    ) -> Result<TerminationHandle, Self::ExecError> {
+        let (th, result_needed) = TerminationHandle::new();
+        std::thread::Builder::new().spawn(move || {
+            // Skip the work if nobody needs its result anymore
+            if result_needed.load(Ordering::SeqCst) {
+                f()
+            }
+        })?;
+        Ok(th)
+    }
+
+    fn spawner(&self) -> Option<Spawner> {
+        Some(Arc::new(|work| {
+            if let Err(e) = std::thread::Builder::new().spawn(move || work.run()) {
+                log::error!("Could not spawn thread for deferred work: {}", e);
+            }
+        }))
+    }
+}
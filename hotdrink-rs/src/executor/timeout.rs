@@ -0,0 +1,175 @@
+//! Timeouts for methods that take too long to finish.
+//!
+//! All timeouts are watched by a single timer thread, which sleeps until the earliest one expires.
+//! Dropping the [`Watchdog`] of a timeout stops watching it, for instance because the method finished.
+//!
+//! A timeout can not stop a synchronous method that is already running.
+//! Its outputs get an error right away, but the method keeps running until it returns,
+//! unless it checks its [`CancellationToken`](super::CancellationToken) and returns early.
+
+use derivative::Derivative;
+use lazy_static::lazy_static;
+use std::{
+    collections::BTreeMap,
+    io,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A function to call once a timeout expires.
+type OnTimeout = Box<dyn FnOnce() + Send>;
+
+/// Identifies a timeout by when it expires, and a counter to tell apart those that expire at the same time.
+type TimeoutKey = (Instant, u64);
+
+/// The timeouts that are being watched, ordered by when they expire.
+#[derive(Default)]
+struct Timeouts {
+    pending: BTreeMap<TimeoutKey, OnTimeout>,
+    next_id: u64,
+}
+
+/// The timeouts shared with the timer thread,
+/// along with a way to wake it up when they change.
+struct Timer {
+    timeouts: Mutex<Timeouts>,
+    changed: Condvar,
+}
+
+lazy_static! {
+    /// The shared timer, or why its thread could not be started.
+    static ref TIMER: Result<Arc<Timer>, String> = Timer::start();
+}
+
+impl Timer {
+    /// Starts the timer thread.
+    /// Fails on platforms without threads, like `wasm32`, since the timer can not wait there.
+    fn start() -> Result<Arc<Self>, String> {
+        if cfg!(target_arch = "wasm32") {
+            return Err("timeouts are not supported on this platform".to_owned());
+        }
+        let timer = Arc::new(Self {
+            timeouts: Mutex::default(),
+            changed: Condvar::new(),
+        });
+        let timer_clone = timer.clone();
+        std::thread::Builder::new()
+            .name("hotdrink-timer".to_owned())
+            .spawn(move || timer_clone.run())
+            .map_err(|e| format!("could not start timer thread: {}", e))?;
+        Ok(timer)
+    }
+
+    /// Calls the functions of expired timeouts, and sleeps until the next one expires.
+    fn run(&self) {
+        let mut timeouts = self.timeouts.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            match timeouts.pending.keys().next().copied() {
+                Some(key @ (expires_at, _)) if expires_at <= now => {
+                    let on_timeout = timeouts.pending.remove(&key).unwrap();
+                    // Release the lock so that timeouts can be added or dropped meanwhile
+                    drop(timeouts);
+                    if panic::catch_unwind(AssertUnwindSafe(on_timeout)).is_err() {
+                        log::error!("Handling a timeout panicked");
+                    }
+                    timeouts = self.timeouts.lock().unwrap();
+                }
+                Some((expires_at, _)) => {
+                    timeouts = self
+                        .changed
+                        .wait_timeout(timeouts, expires_at - now)
+                        .unwrap()
+                        .0;
+                }
+                None => timeouts = self.changed.wait(timeouts).unwrap(),
+            }
+        }
+    }
+}
+
+/// Stops watching the timeout when dropped.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub(crate) struct Watchdog {
+    #[derivative(Debug = "ignore")]
+    timer: Arc<Timer>,
+    key: TimeoutKey,
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        let on_timeout = self
+            .timer
+            .timeouts
+            .lock()
+            .unwrap()
+            .pending
+            .remove(&self.key);
+        // Dropped after releasing the lock, since it may own another watchdog
+        drop(on_timeout);
+    }
+}
+
+/// Calls `on_timeout` on the timer thread once `duration` has passed,
+/// unless the returned [`Watchdog`] is dropped before that.
+///
+/// Returns an error if the timer thread could not be started, for instance on `wasm32`.
+pub(crate) fn watch(
+    duration: Duration,
+    on_timeout: impl FnOnce() + Send + 'static,
+) -> io::Result<Watchdog> {
+    let timer = TIMER
+        .as_ref()
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.clone()))?;
+    let mut timeouts = timer.timeouts.lock().unwrap();
+    let key = (Instant::now() + duration, timeouts.next_id);
+    timeouts.next_id += 1;
+    timeouts.pending.insert(key, Box::new(on_timeout));
+    drop(timeouts);
+    // The new timeout may expire before the one the timer is waiting for
+    timer.changed.notify_one();
+    Ok(Watchdog {
+        timer: timer.clone(),
+        key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::watch;
+    use std::{sync::mpsc, time::Duration};
+
+    #[test]
+    fn expired_timeouts_are_reported() {
+        let (sender, receiver) = mpsc::channel();
+        let _watchdog = watch(Duration::from_millis(10), move || sender.send(()).unwrap()).unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(10)), Ok(()));
+    }
+
+    #[test]
+    fn dropped_watchdogs_do_not_report() {
+        let (sender, receiver) = mpsc::channel();
+        drop(watch(Duration::from_millis(50), move || sender.send(()).unwrap()).unwrap());
+        // The sender is dropped along with the watchdog
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(10)),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn earlier_timeouts_are_reported_first() {
+        let (sender, receiver) = mpsc::channel();
+        let sender_clone = sender.clone();
+        let _late = watch(Duration::from_millis(200), move || sender.send(2).unwrap()).unwrap();
+        let _early = watch(Duration::from_millis(10), move || {
+            sender_clone.send(1).unwrap()
+        })
+        .unwrap();
+        let timeout = Duration::from_secs(10);
+        assert_eq!(receiver.recv_timeout(timeout), Ok(1));
+        assert_eq!(receiver.recv_timeout(timeout), Ok(2));
+    }
+}
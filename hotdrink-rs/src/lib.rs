@@ -14,6 +14,12 @@
 //! The project uses multiple nightly features, and must be built using nightly Rust.
 //! I recommend using `rustup`, which can be downloaded [here](https://rustup.rs/).
 //!
+//! # Features
+//!
+//! - `rayon`: Lets methods run on a `rayon` thread pool.
+//! - `tracing`: Emits [`tracing`](https://docs.rs/tracing) spans for each solve, each planner invocation and each method execution.
+//!   The span of a method is a child of the solve that scheduled it, even if it runs on another thread.
+//!
 //! # Introduction
 //!
 //! Before getting started, here is a quick introduction to the terminology and how it works.
@@ -110,5 +116,6 @@ pub mod executor;
 pub mod model;
 pub mod planner;
 pub mod solver;
+pub mod stats;
 pub mod util;
-pub(crate) mod variable_ranking;
+pub mod variable_ranking;
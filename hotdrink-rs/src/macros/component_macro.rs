@@ -5,6 +5,11 @@
 /// This can be used to construct constraint systems declaratively by
 /// combining the desired components in a [`ConstraintSystem`](crate::model::ConstraintSystem).
 ///
+/// Methods declared with this macro never mutate their inputs in place.
+/// To do that, build the component with a [`ComponentBuilder`](crate::builders::ComponentBuilder) instead,
+/// and declare the variables with [`variable_mut`](crate::builders::ComponentBuilder::variable_mut)
+/// and the inputs with [`input_mut`](crate::builders::MethodBuilder::input_mut).
+///
 /// # Examples
 ///
 /// ```rust
@@ -25,29 +30,252 @@
 ///     }
 /// };
 /// ```
+///
+/// Constraints are required by default, but can be given a weaker [`Strength`](crate::planner::Strength).
+/// If constraints conflict, the weakest ones are left unenforced.
+///
+/// ```rust
+/// # use hotdrink_rs::{model::Component, component, ret};
+/// let mut component: Component<i32> = component! {
+///     component Comp {
+///         let a: i32 = 0, b: i32 = 0;
+///         constraint Equal {
+///             a_to_b(a: &i32) -> [b] = ret![*a];
+///             b_to_a(b: &i32) -> [a] = ret![*b];
+///         }
+///         constraint Double: weak {
+///             a_to_b(a: &i32) -> [b] = ret![*a * 2];
+///             b_to_a(b: &i32) -> [a] = ret![*b / 2];
+///         }
+///     }
+/// };
+/// component.solve().unwrap();
+/// assert_eq!(component.unenforced_constraints(), &["Double".to_owned()]);
+/// ```
+///
+/// Constraints can also have a precondition that must hold before one of their methods is run,
+/// and a postcondition that must hold after it.
+/// Both receive the values of the variables of the constraint, in the order they are first referenced by its methods.
+/// If a condition does not hold, the outputs of the method get an error instead of a new value.
+///
+/// ```rust
+/// # use hotdrink_rs::{model::Component, component, event::Event, ret, solver::Reason};
+/// # use std::sync::{Arc, Mutex};
+/// let mut component: Component<i32> = component! {
+///     component Comp {
+///         let a: i32 = 0, b: i32 = 0;
+///         constraint NonNegative {
+///             precondition |v: &[&i32]| *v[0] >= 0;
+///             postcondition |v: &[&i32]| *v[1] >= 0;
+///             a_to_b(a: &i32) -> [b] = ret![*a];
+///         }
+///     }
+/// };
+/// let reasons = Arc::new(Mutex::new(Vec::new()));
+/// let reasons_clone = reasons.clone();
+/// component
+///     .subscribe("b", move |event| {
+///         if let Event::Error(errors) = event {
+///             let mut reasons = reasons_clone.lock().unwrap();
+///             reasons.extend(errors.iter().map(|e| e.reason().clone()));
+///         }
+///     })
+///     .unwrap();
+/// component.edit("a", -1).unwrap();
+/// component.solve().unwrap();
+/// assert_eq!(
+///     *reasons.lock().unwrap(),
+///     vec![Reason::PreConditionFailure("NonNegative".to_owned())]
+/// );
+/// ```
+///
+/// Methods marked with `async` can await futures in their body.
+/// The resulting future is polled on the executor the component is solved with.
+///
+/// ```rust
+/// # use hotdrink_rs::{model::{Activation, Component}, component, ret};
+/// let mut component: Component<i32> = component! {
+///     component Comp {
+///         let a: i32 = 0, b: i32 = 0;
+///         constraint Double {
+///             async double(a: &i32) -> [b] = {
+///                 let a = futures::future::ready(*a).await;
+///                 ret![a * 2]
+///             };
+///         }
+///     }
+/// };
+/// component.edit("a", 3).unwrap();
+/// component.solve().unwrap();
+/// assert_eq!(component.value("b"), Ok(Activation::from(6)));
+/// ```
+///
+/// A method body written as `|token| body` receives a [`CancellationToken`](crate::executor::CancellationToken),
+/// which is cancelled once its result is no longer needed.
+/// The result of a cancelled method is discarded, so long-running bodies can return early.
+///
+/// ```rust
+/// # use hotdrink_rs::{model::{Activation, Component}, component, fail, ret};
+/// let mut component: Component<i32> = component! {
+///     component Comp {
+///         let a: i32 = 0, b: i32 = 0;
+///         constraint Sum {
+///             sum(a: &i32) -> [b] = |token| {
+///                 let mut sum = 0;
+///                 for i in 0..*a {
+///                     if token.is_cancelled() {
+///                         return fail!("Cancelled");
+///                     }
+///                     sum += i;
+///                 }
+///                 ret![sum]
+///             };
+///         }
+///     }
+/// };
+/// component.edit("a", 4).unwrap();
+/// component.solve().unwrap();
+/// assert_eq!(component.value("b"), Ok(Activation::from(6)));
+/// ```
 #[macro_export]
 macro_rules! component {
     (@value_or_default: $t:ty ) => {{ <$t>::default() }};
     (@value_or_default: $t:ty = $value:expr) => {{ $value }};
+    (@strength) => { $crate::planner::Strength::Required };
+    (@strength required) => { $crate::planner::Strength::Required };
+    (@strength strong) => { $crate::planner::Strength::Strong };
+    (@strength medium) => { $crate::planner::Strength::Medium };
+    (@strength weak) => { $crate::planner::Strength::Weak };
+    (@condition) => { None };
+    (@condition $condition:expr) => { Some($crate::macros::condition($condition)) };
+    // Match a precondition of the constraint.
+    (
+        @constraint $constraint_name:ident [$( $strength:ident )?] [$( $pre:tt )*] [$( $post:tt )*] [$( $methods:tt )*]
+        precondition $precondition:expr; $( $rest:tt )*
+    ) => {
+        $crate::component!(@constraint $constraint_name [$( $strength )?] [$precondition] [$( $post )*] [$( $methods )*] $( $rest )*)
+    };
+    // Match a postcondition of the constraint.
+    (
+        @constraint $constraint_name:ident [$( $strength:ident )?] [$( $pre:tt )*] [$( $post:tt )*] [$( $methods:tt )*]
+        postcondition $postcondition:expr; $( $rest:tt )*
+    ) => {
+        $crate::component!(@constraint $constraint_name [$( $strength )?] [$( $pre )*] [$postcondition] [$( $methods )*] $( $rest )*)
+    };
+    // Match an asynchronous method, its inputs, outputs and body.
+    (
+        @constraint $constraint_name:ident [$( $strength:ident )?] [$( $pre:tt )*] [$( $post:tt )*] [$( $methods:tt )*]
+        async $method_name:ident
+            ($($inp:ident: $inp_ty:ty),*)
+            $(-> [$($out:ident),+])?
+            = $m_expr:expr;
+        $( $rest:tt )*
+    ) => {
+        $crate::component!(@constraint $constraint_name [$( $strength )?] [$( $pre )*] [$( $post )*] [$( $methods )* {
+            $crate::macros::RawMethod::new_async(
+                stringify!($method_name),
+                vec![ $( stringify!($inp) ),* ],
+                vec![ $( $( stringify!($out) ),* )? ],
+                std::sync::Arc::new(move |values| {
+                    let future: $crate::planner::MethodFuture<_> = Box::pin(async move {
+                        $crate::component!(@method values ($($inp: $inp_ty),*) = $m_expr)
+                    });
+                    future
+                })
+            )
+        },] $( $rest )*)
+    };
+    // Match a method that receives a cancellation token, its inputs, outputs and body.
+    (
+        @constraint $constraint_name:ident [$( $strength:ident )?] [$( $pre:tt )*] [$( $post:tt )*] [$( $methods:tt )*]
+        $method_name:ident
+            ($($inp:ident: $inp_ty:ty),*)
+            $(-> [$($out:ident),+])?
+            = |$token:ident| $m_expr:expr;
+        $( $rest:tt )*
+    ) => {
+        $crate::component!(@constraint $constraint_name [$( $strength )?] [$( $pre )*] [$( $post )*] [$( $methods )* {
+            $crate::macros::RawMethod::new_cancellable(
+                stringify!($method_name),
+                vec![ $( stringify!($inp) ),* ],
+                vec![ $( $( stringify!($out) ),* )? ],
+                std::sync::Arc::new(move |values: &mut [std::sync::Arc<_>], $token: &$crate::executor::CancellationToken| {
+                    $crate::component!(@method values ($($inp: $inp_ty),*) = $m_expr)
+                })
+            )
+        },] $( $rest )*)
+    };
+    // Match a method, its inputs, outputs and body.
+    (
+        @constraint $constraint_name:ident [$( $strength:ident )?] [$( $pre:tt )*] [$( $post:tt )*] [$( $methods:tt )*]
+        $method_name:ident
+            ($($inp:ident: $inp_ty:ty),*)
+            $(-> [$($out:ident),+])?
+            = $m_expr:expr;
+        $( $rest:tt )*
+    ) => {
+        $crate::component!(@constraint $constraint_name [$( $strength )?] [$( $pre )*] [$( $post )*] [$( $methods )* {
+            $crate::macros::RawMethod::new(
+                stringify!($method_name),
+                vec![ $( stringify!($inp) ),* ],
+                vec![ $( $( stringify!($out) ),* )? ],
+                std::sync::Arc::new(move |values| {
+                    $crate::component!(@method values ($($inp: $inp_ty),*) = $m_expr)
+                })
+            )
+        },] $( $rest )*)
+    };
+    // All methods have been matched.
+    (
+        @constraint $constraint_name:ident [$( $strength:ident )?] [$( $precondition:expr )?] [$( $postcondition:expr )?] [$( $methods:tt )+]
+    ) => {
+        $crate::macros::RawConstraint::new_with_conditions(
+            stringify!($constraint_name),
+            vec![ $( $methods )+ ],
+            $crate::component!(@condition $( $precondition )?),
+            $crate::component!(@condition $( $postcondition )?),
+        ).with_strength($crate::component!(@strength $( $strength )?))
+    };
+    // The body of a method, which converts the values to the types of its inputs.
+    (@method $values:ident ($($inp:ident: $inp_ty:ty),*) = $m_expr:expr) => {{
+        #[allow(unused_mut, unused_assignments)]
+        let mut var_idx = 0;
+        $(
+            // Assign the value to the variable
+            let $inp = &$values.get(var_idx);
+            // Verify that it exists
+            if $inp.is_none() {
+                return Err($crate::planner::MethodFailure::NoSuchVariable(stringify!($inp).to_owned()));
+            }
+            // Convert it to the appropriate type
+            let $inp = std::convert::TryInto::<$inp_ty>::try_into(&**$inp.unwrap());
+            // Verify that it worked
+            if $inp.is_err() {
+                return Err($crate::planner::MethodFailure::TypeConversionFailure(stringify!($inp), stringify!($inp_ty)));
+            }
+            let $inp = $inp.unwrap();
+
+            #[allow(unused_assignments)]
+            {
+                var_idx += 1;
+            }
+        )*
+
+        use std::sync::Arc;
+        let e: $crate::planner::MethodResult<_> = $m_expr;
+        // Evaluate user code and wrap in [`Arc`]s
+        e.map(|v| v.into_iter().map(Arc::new).collect())
+    }};
     (
         // Match a component, its name, and constraints.
         component $component_name:ident {
             // Match variables, their types, and default values.
             let $($i:ident: $val_ty:ty $( = $e:expr )? ),*;
             $(
-                // Match a constraint, its name, and methods.
-                constraint $constraint_name:ident {
-                    // Match a precondition of the constraint.
-                    $( precondition $precondition:expr; )?
-                    // Match a postcondition of the constraint.
-                    $( postcondition $postcondition:expr; )?
-                    $(
-                        // Match a method, its inputs, outputs and body.
-                        $method_name:ident
-                            ($($inp:ident: $inp_ty:ty),*)
-                            $(-> [$($out:ident),+])?
-                            = $m_expr:expr;
-                    )+
+                // Match a constraint, its name, optional strength, and body.
+                // The body may start with a precondition and a postcondition, followed by methods.
+                constraint $constraint_name:ident $( : $strength:ident )? {
+                    $( $body:tt )*
                 }
             )*
         }
@@ -65,47 +293,7 @@ macro_rules! component {
             values,
             // Constraints
             vec![ $(
-                $crate::macros::RawConstraint::new_with_assert(
-                    stringify!($constraint_name),
-                    // Methods
-                    vec![ $(
-                        #[allow(unused_mut)]
-                        $crate::macros::RawMethod::new(
-                            stringify!($method_name),
-                            vec![ $( stringify!($inp) ),* ],
-                            vec![ $( $( stringify!($out) ),* )? ],
-                            {
-                                #[allow(unused_assignments)]
-                                std::sync::Arc::new(move |values| {
-                                    let mut var_idx = 0;
-                                    $(
-                                        // Assign the value to the variable
-                                        let $inp = &values.get(var_idx);
-                                        // Verify that it exists
-                                        if $inp.is_none() {
-                                            return Err($crate::planner::MethodFailure::NoSuchVariable(stringify!($inp).to_owned()));
-                                        }
-                                        // Convert it to the appropriate type
-                                        let $inp = std::convert::TryInto::<$inp_ty>::try_into(&**$inp.unwrap());
-                                        // Verify that it worked
-                                        if $inp.is_err() {
-                                            return Err($crate::planner::MethodFailure::TypeConversionFailure(stringify!($inp), stringify!($inp_ty)));
-                                        }
-                                        let $inp = $inp.unwrap();
-
-                                        var_idx += 1;
-                                    )*
-
-                                    use std::sync::Arc;
-                                    let e: $crate::planner::MethodResult<_> = $m_expr;
-                                    // Evaluate user code and wrap in [`Arc`]s
-                                    e.map(|v| v.into_iter().map(Arc::new).collect())
-                                })
-                            }
-                        )
-                    ),* ], // End of methods
-                    None,
-                )
+                $crate::component!(@constraint $constraint_name [$( $strength )?] [] [] [] $( $body )*)
             ),* ] // End of constraints
         ).into_component()
     }};
@@ -200,7 +388,7 @@ macro_rules! fail {
 mod tests {
     use crate::{
         component_type,
-        model::Component,
+        model::{Activation, Component},
         planner::{ComponentSpec, ConstraintSpec, MethodFailure, MethodSpec},
     };
     use std::convert::TryFrom;
@@ -329,6 +517,24 @@ mod tests {
         assert_eq!(m.apply(all_into![3]), Ok(all_into![6]));
     }
 
+    #[test]
+    fn async_methods_are_awaited() {
+        let mut component: Component<i32> = component! {
+            component Comp {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Double {
+                    async double(a: &i32) -> [b] = {
+                        let a = futures::future::ready(*a).await;
+                        ret![a * 2]
+                    };
+                }
+            }
+        };
+        component.edit("a", 3).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(6)));
+    }
+
     #[test]
     fn component_macro_provides_default_values() {
         let _: Component<i32> = component! {
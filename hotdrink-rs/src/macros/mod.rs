@@ -9,5 +9,5 @@ pub(crate) mod raw_constraint;
 pub(crate) mod raw_method;
 
 pub use raw_component::RawComponent;
-pub use raw_constraint::RawConstraint;
+pub use raw_constraint::{condition, Assert, Condition, RawConstraint};
 pub use raw_method::RawMethod;
@@ -4,7 +4,7 @@
 //! [`Constraint`]: crate::model::Constraint
 
 use super::raw_method::RawMethod;
-use crate::model::Constraint;
+use crate::{model::Constraint, planner::Strength};
 use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 /// A type for an assertion statement for the constraint.
@@ -12,11 +12,31 @@ use std::{collections::HashMap, fmt::Debug, sync::Arc};
 /// constraint holds, and may also work as documentation.
 pub type Assert<T> = Arc<dyn Fn(&[T]) -> bool>;
 
+/// A condition of a constraint that is checked when running its methods.
+/// It receives the values of the variables of the constraint,
+/// in the order they are first referenced by its methods.
+///
+/// A precondition is checked before running a method to enforce the constraint,
+/// and a postcondition is checked after it to ensure that the constraint holds.
+pub type Condition<T> = Arc<dyn Fn(&[&T]) -> bool + Send + Sync>;
+
+/// Turns a closure into a [`Condition`].
+/// This helps infer the types of the closure's arguments.
+pub fn condition<T, F>(f: F) -> Condition<T>
+where
+    F: Fn(&[&T]) -> bool + Send + Sync + 'static,
+{
+    Arc::new(f)
+}
+
 /// An intermediate struct for constructing [`Constraint`]s.
 pub struct RawConstraint<T> {
     name: String,
     methods: Vec<RawMethod<T>>,
     assert: Option<Assert<T>>,
+    precondition: Option<Condition<T>>,
+    postcondition: Option<Condition<T>>,
+    strength: Strength,
 }
 
 impl<T> RawConstraint<T> {
@@ -26,35 +46,79 @@ impl<T> RawConstraint<T> {
             name: name.into(),
             methods,
             assert: None,
+            precondition: None,
+            postcondition: None,
+            strength: Strength::Required,
         }
     }
 
     /// Constructs a new [`RawConstraint`] with an optional assert statement.
+    ///
+    /// The assertion is not checked when solving,
+    /// see [`new_with_conditions`](Self::new_with_conditions) for conditions that are.
     pub fn new_with_assert<S: Into<String>>(
         name: S,
         methods: Vec<RawMethod<T>>,
         assert: Option<Assert<T>>,
+    ) -> Self {
+        Self {
+            assert,
+            ..Self::new(name, methods)
+        }
+    }
+
+    /// Constructs a new [`RawConstraint`] with an optional precondition and postcondition.
+    pub fn new_with_conditions<S: Into<String>>(
+        name: S,
+        methods: Vec<RawMethod<T>>,
+        precondition: Option<Condition<T>>,
+        postcondition: Option<Condition<T>>,
     ) -> Self {
         Self {
             name: name.into(),
             methods,
-            assert,
+            assert: None,
+            precondition,
+            postcondition,
+            strength: Strength::Required,
         }
     }
 
+    /// Sets the strength of the constraint.
+    #[must_use]
+    pub fn with_strength(mut self, strength: Strength) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    /// Returns the name of the constraint.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the names of the variables the methods of the constraint reference.
+    /// A variable may appear more than once.
+    pub(crate) fn variables(&self) -> impl Iterator<Item = &str> {
+        self.methods.iter().flat_map(RawMethod::variables)
+    }
+
     /// Converts this [`RawConstraint`] into a [`Constraint`].
     pub fn into_constraint(self, var_to_idx: &HashMap<String, usize>) -> Constraint<T>
     where
         T: Clone,
     {
-        Constraint::new_with_name_and_assert(
+        let mut constraint = Constraint::new_with_name_and_conditions(
             self.name.to_owned(),
             self.methods
                 .into_iter()
                 .map(|m| m.into_method(var_to_idx))
                 .collect(),
-            self.assert,
-        )
+            self.precondition,
+            self.postcondition,
+        );
+        constraint.set_assert(self.assert);
+        constraint.set_strength(self.strength);
+        constraint
     }
 }
 
@@ -63,12 +127,13 @@ impl<T> Debug for RawConstraint<T> {
         f.debug_struct("RawConstraint")
             .field("name", &self.name)
             .field("methods", &self.methods)
+            .field("strength", &self.strength)
             .finish()
     }
 }
 
 impl<T> PartialEq for RawConstraint<T> {
     fn eq(&self, other: &Self) -> bool {
-        (&self.name, &self.methods) == (&other.name, &other.methods)
+        (&self.name, &self.methods, self.strength) == (&other.name, &other.methods, other.strength)
     }
 }
@@ -4,7 +4,7 @@
 //! [`Method`]: crate::model::Method
 
 use crate::model::Method;
-use crate::planner::{MethodFunction, MethodSpec};
+use crate::planner::{AsyncMethodFunction, MethodFunction, MethodFunctionMut, MethodSpec};
 use itertools::Itertools;
 use std::{collections::HashMap, fmt::Debug};
 
@@ -13,7 +13,14 @@ pub struct RawMethod<T> {
     name: String,
     inputs: Vec<String>,
     outputs: Vec<String>,
-    apply: MethodFunction<T>,
+    apply: RawFunction<T>,
+}
+
+/// The function of a [`RawMethod`].
+enum RawFunction<T> {
+    Sync(MethodFunction<T>),
+    Cancellable(MethodFunctionMut<T>),
+    Async(AsyncMethodFunction<T>),
 }
 
 impl<T> RawMethod<T> {
@@ -28,38 +35,78 @@ impl<T> RawMethod<T> {
             name: name.into(),
             inputs: inputs.into_iter().map_into().collect(),
             outputs: outputs.into_iter().map_into().collect(),
-            apply,
+            apply: RawFunction::Sync(apply),
+        }
+    }
+
+    /// Constructs a new [`RawMethod`] whose function receives a [`CancellationToken`](crate::executor::CancellationToken).
+    pub fn new_cancellable<S: Into<String>>(
+        name: S,
+        inputs: Vec<S>,
+        outputs: Vec<S>,
+        apply: MethodFunctionMut<T>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            inputs: inputs.into_iter().map_into().collect(),
+            outputs: outputs.into_iter().map_into().collect(),
+            apply: RawFunction::Cancellable(apply),
+        }
+    }
+
+    /// Constructs a new [`RawMethod`] whose function returns a future.
+    pub fn new_async<S: Into<String>>(
+        name: S,
+        inputs: Vec<S>,
+        outputs: Vec<S>,
+        apply: AsyncMethodFunction<T>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            inputs: inputs.into_iter().map_into().collect(),
+            outputs: outputs.into_iter().map_into().collect(),
+            apply: RawFunction::Async(apply),
         }
     }
 
+    /// Returns the names of the variables the method reads from and writes to.
+    pub(crate) fn variables(&self) -> impl Iterator<Item = &str> {
+        self.inputs.iter().chain(&self.outputs).map(String::as_str)
+    }
+
     /// Converts this [`RawMethod`] into a [`Method`].
     #[allow(clippy::expect_fun_call)]
     pub fn into_method(self, var_to_idx: &HashMap<String, usize>) -> Method<T>
     where
         T: Clone,
     {
-        Method::new(
-            self.name.to_string(),
-            self.inputs
-                .into_iter()
-                .map(|i| {
-                    var_to_idx
-                        .get(&i)
-                        .expect(&format!("Undefined variable {}", i))
-                })
-                .copied()
-                .collect(),
-            self.outputs
-                .into_iter()
-                .map(|o| {
-                    var_to_idx
-                        .get(&o)
-                        .expect(&format!("Undefined variable {}", o))
-                })
-                .copied()
-                .collect(),
-            self.apply.clone(),
-        )
+        let inputs = self
+            .inputs
+            .into_iter()
+            .map(|i| {
+                var_to_idx
+                    .get(&i)
+                    .expect(&format!("Undefined variable {}", i))
+            })
+            .copied()
+            .collect();
+        let outputs = self
+            .outputs
+            .into_iter()
+            .map(|o| {
+                var_to_idx
+                    .get(&o)
+                    .expect(&format!("Undefined variable {}", o))
+            })
+            .copied()
+            .collect();
+        match self.apply {
+            RawFunction::Sync(apply) => Method::new(self.name, inputs, outputs, apply),
+            RawFunction::Cancellable(apply) => {
+                Method::new_cancellable(self.name, inputs, outputs, apply)
+            }
+            RawFunction::Async(apply) => Method::new_async(self.name, inputs, outputs, apply),
+        }
     }
 }
 
@@ -2,6 +2,7 @@
 //! Updating the values of a constraint system will not happen immediately, but the activations will be ready,
 //! and act as futures or promises that eventually get the new value.
 
+use super::generation_id::GenerationId;
 use crate::{event::Event, executor::TerminationHandle, solver::SolveError};
 use derivative::Derivative;
 use futures::Future;
@@ -16,7 +17,9 @@ use std::{
 #[derive(Derivative)]
 #[derivative(Clone(bound = ""), Debug, PartialEq, Eq)]
 pub struct PendingData<T> {
-    previous: Activation<T>,
+    /// The value to fall back to if the computation fails.
+    /// This is only missing while the value is lent out, see [`ActivationInner::lend_previous`].
+    previous: Option<Activation<T>>,
     dependencies: Vec<Activation<T>>,
 }
 
@@ -24,7 +27,7 @@ impl<T> PendingData<T> {
     /// Constructs a new [`PendingData<T>`].
     pub fn new(previous: Activation<T>, dependencies: Vec<Activation<T>>) -> Self {
         Self {
-            previous,
+            previous: Some(previous),
             dependencies,
         }
     }
@@ -64,8 +67,12 @@ pub enum State<T> {
     Error(ErrorData<T>),
 }
 
-/// A callback to an [`Event`] sent from a call to [`ConstraintSystem::solve`](crate::model::ConstraintSystem::solve).
-pub type EventCallback<T, E> = Arc<dyn Fn(Event<'_, T, E>) + Send + Sync>;
+/// A callback to an [`Event`] sent from a call to [`ConstraintSystem::solve`](crate::model::ConstraintSystem::solve),
+/// along with the generation it is from.
+pub type EventCallback<T, E> = Arc<dyn Fn(GenerationId, Event<'_, T, E>) + Send + Sync>;
+
+/// A function to call once an [`Activation`] is resolved.
+pub(crate) type Listener = Box<dyn FnOnce() + Send + Sync>;
 
 /// Contains a slot for a value to be produced,
 /// and one for a waker to be called when this happens.
@@ -75,6 +82,10 @@ pub struct ActivationInner<T> {
     state: State<T>,
     #[derivative(Debug = "ignore", PartialEq = "ignore")]
     waker: Option<Waker>,
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    listeners: Vec<Listener>,
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    settle_listeners: Vec<Listener>,
 }
 
 impl<T> From<T> for ActivationInner<T> {
@@ -82,6 +93,8 @@ impl<T> From<T> for ActivationInner<T> {
         Self {
             state: State::Ready(Arc::new(value)),
             waker: None,
+            listeners: Vec::new(),
+            settle_listeners: Vec::new(),
         }
     }
 }
@@ -91,6 +104,75 @@ impl<T> ActivationInner<T> {
         Self {
             state: State::Pending(PendingData::new(previous, dependencies)),
             waker: None,
+            listeners: Vec::new(),
+            settle_listeners: Vec::new(),
+        }
+    }
+
+    /// Calls the listeners registered with [`Activation::on_resolved`] and [`Activation::on_settled`]
+    /// if the state is no longer pending.
+    /// This must be called after changing the state, without holding a lock on it.
+    pub(crate) fn notify_listeners(this: &Arc<RwLock<Self>>) {
+        let (listeners, settle_listeners, previous) = {
+            let mut inner = this.write().unwrap();
+            let previous = match &inner.state {
+                State::Pending(_) => return,
+                State::Ready(_) => None,
+                State::Error(error_data) => Some(error_data.previous().clone()),
+            };
+            (
+                std::mem::take(&mut inner.listeners),
+                std::mem::take(&mut inner.settle_listeners),
+                previous,
+            )
+        };
+        for listener in settle_listeners {
+            listener();
+        }
+        for listener in listeners {
+            match &previous {
+                // An error resolves to the previous value
+                Some(previous) => previous.on_resolved(listener),
+                None => listener(),
+            }
+        }
+    }
+
+    /// Lends out the value of the previous activation, so that it can be mutated in place.
+    ///
+    /// This only succeeds if the previous activation is resolved, and nothing but this pending
+    /// activation refers to it. Otherwise, the value may be observed by others, and must be copied.
+    /// The previous activation is then pending until the value is returned to the returned
+    /// shared state with [`set_value_arc`](Self::set_value_arc).
+    pub(crate) fn lend_previous(this: &Arc<RwLock<Self>>) -> Option<Arc<RwLock<Self>>> {
+        // Hold the lock so that the previous activation can not be cloned meanwhile
+        let inner = this.write().unwrap();
+        let previous = match &inner.state {
+            State::Pending(pd) => pd.previous.as_ref()?,
+            _ => return None,
+        };
+        if Arc::strong_count(&previous.inner) != 1 {
+            return None;
+        }
+        let mut previous_inner = previous.inner.write().unwrap();
+        if !matches!(previous_inner.state, State::Ready(_)) {
+            return None;
+        }
+        previous_inner.state = State::Pending(PendingData {
+            previous: None,
+            dependencies: Vec::new(),
+        });
+        Some(previous.inner.clone())
+    }
+
+    /// Returns the value of the previous activation while pending, if it is resolved successfully.
+    pub(crate) fn previous_value(&self) -> Option<Arc<T>> {
+        match &self.state {
+            State::Pending(pd) => match pd.previous.as_ref()?.inner.read().unwrap().state() {
+                State::Ready(value) => Some(value.clone()),
+                _ => None,
+            },
+            _ => None,
         }
     }
 
@@ -118,7 +200,11 @@ impl<T> ActivationInner<T> {
                 previous_errors.errors.extend(errors);
             }
             State::Pending(pd) => {
-                self.state = State::Error(ErrorData::new(pd.previous.clone(), errors));
+                let previous = pd
+                    .previous
+                    .clone()
+                    .expect("Lent values are returned before setting errors");
+                self.state = State::Error(ErrorData::new(previous, errors));
             }
             _ => panic!("State set to error twice"),
         }
@@ -162,9 +248,43 @@ impl<T> Activation<T> {
         if let State::Pending(_) = &inner.state {
             inner.set_error(vec![e]);
         }
+        drop(inner);
+        ActivationInner::notify_listeners(&self.inner);
         self.producer = None;
     }
 
+    /// Calls `listener` once the activation resolves to a value,
+    /// or right away if it already has.
+    pub(crate) fn on_resolved(&self, listener: Listener) {
+        let mut inner = self.inner.write().unwrap();
+        match &inner.state {
+            State::Pending(_) => inner.listeners.push(listener),
+            State::Ready(_) => {
+                drop(inner);
+                listener();
+            }
+            State::Error(error_data) => {
+                let previous = error_data.previous().clone();
+                drop(inner);
+                previous.on_resolved(listener);
+            }
+        }
+    }
+
+    /// Calls `listener` once the activation is ready or has failed,
+    /// or right away if it already is.
+    /// Unlike [`on_resolved`](Self::on_resolved), it does not wait for the previous value of a failed activation.
+    pub(crate) fn on_settled(&self, listener: Listener) {
+        let mut inner = self.inner.write().unwrap();
+        match &inner.state {
+            State::Pending(_) => inner.settle_listeners.push(listener),
+            State::Ready(_) | State::Error(_) => {
+                drop(inner);
+                listener();
+            }
+        }
+    }
+
     /// Removes the reference to the termination handle.
     pub fn detach(&mut self) {
         self.producer = None;
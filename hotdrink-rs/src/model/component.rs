@@ -1,12 +1,22 @@
 //! Types for a [`Component`], an independent subgraph of a constraint system with values and constraints between them.
 
 use super::{
-    activation::State,
+    activation::{EventCallback, State},
+    component_plan::ComponentPlan,
     constraint::Constraint,
-    errors::{NoSuchConstraint, NoSuchVariable},
+    errors::{
+        AddConstraintError, NoSuchConstraint, NoSuchVariable, PlanMismatch, QueryError,
+        RemoveVariableError, TransactionError, VariableExists,
+    },
+    event_stream::EventStream,
+    explanation::{Explanation, RejectedMethod},
     filtered_callback::FilteredCallback,
     generation_id::GenerationId,
     method::Method,
+    plan_cache::{PlanCache, PlanKey},
+    solve_handle::SolveHandle,
+    subscription::{NamedEventCallback, Subscription, SubscriptionId},
+    transaction::{last_edits, Transaction},
     undo::{NoMoreRedo, NoMoreUndo, UndoLimit},
     variable::Variable,
     variables::Variables,
@@ -14,22 +24,40 @@ use super::{
 use crate::{
     event::{Event, EventWithLocation, Ready},
     executor::{DummyExecutor, MethodExecutor},
+    macros::RawConstraint,
     model::activation::Activation,
     planner::{
-        hierarchical_planner, priority_adjuster::adjust_priorities, ComponentSpec, ConstraintSpec,
-        MethodSpec, PlanError, Vertex,
+        dependencies, priority_adjuster::adjust_priorities, ComponentSpec, ConstraintSpec,
+        HierarchicalPlanner, MethodSpec, OwnedPlan, Plan, PlanError, SharedPlanner, Strength,
+        Vertex,
     },
     solver::{self, SolveError},
-    variable_ranking::{SortRanker, VariableRanker},
+    stats::{Instant, SharedStats, Stats},
+    variable_ranking::{Rank, RankingStrategy, SharedRankingStrategy, SortRanker, VariableRanker},
 };
 use itertools::Itertools;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{self, Debug, Write},
     ops::{Index, IndexMut},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
 
+/// The next revision of the constraints of any component, see [`ComponentSpec::revision`].
+static NEXT_REVISION: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a revision that has not been used before.
+fn next_revision() -> usize {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// The callbacks subscribed to every variable of a component, see [`Component::subscribe_all`].
+type ComponentCallbacks<T> = Arc<Mutex<Vec<(SubscriptionId, NamedEventCallback<T>)>>>;
+
 /// A collection of variables along with constraints that should be maintained between them.
 /// Variables can get new values, values can be retrieved from the component, and the constraints can be enforced.
 /// Subscribing to variables will send a notification when the new values are ready.
@@ -39,12 +67,37 @@ pub struct Component<T> {
     name: String,
     name_to_index: HashMap<String, usize>,
     callbacks: Arc<Mutex<Vec<FilteredCallback<T, SolveError>>>>,
+    /// The callbacks subscribed to every variable, which new variables are subscribed to as well.
+    #[derivative(Debug = "ignore")]
+    component_callbacks: ComponentCallbacks<T>,
     variables: Variables<Activation<T>>,
     constraints: Vec<Constraint<T>>,
     ranker: SortRanker,
+    #[derivative(
+        Debug = "ignore",
+        Default(value = "Arc::new(RankingStrategy::Recency)")
+    )]
+    ranking_strategy: SharedRankingStrategy,
+    /// The priority levels of variables by name, where missing ones are 0.
+    priority_levels: HashMap<String, i32>,
+    #[derivative(Debug = "ignore", Default(value = "Arc::new(HierarchicalPlanner)"))]
+    planner: SharedPlanner<Component<T>>,
+    unenforced: Vec<String>,
     n_ready: usize,
     current_generation: usize,
     total_generation: usize,
+    method_timeout: Option<Duration>,
+    #[derivative(Debug = "ignore")]
+    stats: Option<SharedStats>,
+    plan_cache: Option<PlanCache<T>>,
+    /// The ranking that the last executed plan was made for.
+    last_ranking: Option<Vec<usize>>,
+    /// Identifies the current variables and constraints, and is shared with clones until either changes.
+    #[derivative(Debug = "ignore", Default(value = "next_revision()"))]
+    revision: usize,
+    /// Whether the variables or constraints have changed since the last solve.
+    #[derivative(Default(value = "true"))]
+    modified: bool,
 }
 
 impl<T> Component<T> {
@@ -57,37 +110,165 @@ impl<T> Component<T> {
     }
 
     /// Add a callback to be called when a given variable is updated.
+    ///
+    /// Any number of callbacks can subscribe to the same variable.
+    /// The callback is called with the current state of the variable right away,
+    /// and stays subscribed until [`unsubscribe`](Self::unsubscribe) is called for the variable.
     pub fn subscribe<'s>(
         &mut self,
         variable: &'s str,
         callback: impl Fn(Event<'_, T, SolveError>) + Send + Sync + 'static,
     ) -> Result<(), NoSuchVariable<'s>>
+    where
+        T: 'static,
+    {
+        let subscription = self.subscribe_guarded(variable, callback)?;
+        subscription.detach();
+        Ok(())
+    }
+
+    /// Like [`subscribe`](Self::subscribe), but only this callback is unsubscribed
+    /// when the returned [`Subscription`] is dropped.
+    pub fn subscribe_guarded<'s>(
+        &mut self,
+        variable: &'s str,
+        callback: impl Fn(Event<'_, T, SolveError>) + Send + Sync + 'static,
+    ) -> Result<Subscription, NoSuchVariable<'s>>
     where
         T: 'static,
     {
         if let Some(&index) = self.name_to_index.get(variable) {
             // Call the callback with current variable state
-            let activation = &self.variables[index];
-            let inner = activation.inner().read().unwrap();
-            match inner.state() {
-                State::Pending(_) => callback(Event::Pending),
-                State::Ready(value) => callback(Event::Ready(Ready::Changed(value))),
-                State::Error(data) => callback(Event::Error(data.errors())),
-            }
+            send_current_state(&self.variables[index], &callback);
 
-            // Update the stored callback
-            self.callbacks.lock().unwrap()[index].subscribe(callback);
+            // Store the callback along with the others
+            let id = SubscriptionId::next();
+            self.callbacks.lock().unwrap()[index]
+                .subscribe(id, Arc::new(move |_, event| callback(event)));
 
-            Ok(())
+            Ok(self.subscription(id))
         } else {
             Err(NoSuchVariable(variable))
         }
     }
 
-    /// Unsubscribe from a variable to avoid receiving further events.
+    /// Add a callback to be called when any variable of the component is updated,
+    /// along with the name of the variable.
+    ///
+    /// This includes variables that are added later. Like with [`subscribe`](Self::subscribe),
+    /// the callback is called with the current state of each variable right away,
+    /// and is unsubscribed when the returned [`Subscription`] is dropped.
+    pub fn subscribe_all(
+        &mut self,
+        callback: impl Fn(&str, Event<'_, T, SolveError>) + Send + Sync + 'static,
+    ) -> Subscription
+    where
+        T: 'static,
+    {
+        self.subscribe_all_with_generation(move |variable, _, event| callback(variable, event))
+    }
+
+    /// Like [`subscribe_all`](Self::subscribe_all), but also passes the generation each event is from.
+    pub(crate) fn subscribe_all_with_generation(
+        &mut self,
+        callback: impl Fn(&str, GenerationId, Event<'_, T, SolveError>) + Send + Sync + 'static,
+    ) -> Subscription
+    where
+        T: 'static,
+    {
+        let callback: NamedEventCallback<T> = Arc::new(callback);
+        let id = SubscriptionId::next();
+        let generation = GenerationId::new(self.current_generation, self.total_generation);
+        let mut callbacks = self.callbacks.lock().unwrap();
+        for (name, &index) in self.name_to_index.iter().sorted_by_key(|(_, &index)| index) {
+            send_current_state(&self.variables[index], |event| {
+                callback(name, generation, event)
+            });
+            callbacks[index].subscribe(id, with_name(name, &callback));
+        }
+        drop(callbacks);
+        self.component_callbacks
+            .lock()
+            .unwrap()
+            .push((id, callback));
+        self.subscription(id)
+    }
+
+    /// Returns a [`Stream`](futures::Stream) of the events from every variable of the component.
+    ///
+    /// Each item contains the name of the component and the variable, the generation of the event, and the event itself.
+    /// Like with [`subscribe_all`](Self::subscribe_all), the current state of each variable comes first,
+    /// and events from old generations are left out.
+    /// The stream stops receiving events when it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{event::{OwnedEvent, OwnedReady}, examples::components::numbers::sum, model::Component};
+    /// let mut component: Component<i32> = sum();
+    /// let mut events = component.events();
+    /// component.edit("a", 3).unwrap();
+    /// component.solve().unwrap();
+    /// futures::executor::block_on(async {
+    ///     // Skip the current values of `a`, `b` and `c`, and the edit of `a`
+    ///     for _ in 0..4 {
+    ///         events.next_settled().await;
+    ///     }
+    ///     let (_, variable, _, event) = events.next_settled().await.unwrap();
+    ///     assert_eq!(variable, "c");
+    ///     assert_eq!(event, OwnedEvent::Ready(OwnedReady::Changed(3)));
+    /// });
+    /// ```
+    pub fn events(&mut self) -> EventStream<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let component = self.name.clone();
+        EventStream::new(|sender| {
+            self.subscribe_all_with_generation(move |variable, generation, event| {
+                let item = (
+                    component.clone(),
+                    variable.to_owned(),
+                    generation,
+                    event.into(),
+                );
+                // The stream may have been dropped
+                let _ = sender.unbounded_send(item);
+            })
+        })
+    }
+
+    /// Returns a [`Subscription`] that removes the callbacks with the specified id when dropped.
+    fn subscription(&self, id: SubscriptionId) -> Subscription
+    where
+        T: 'static,
+    {
+        let callbacks = Arc::downgrade(&self.callbacks);
+        let component_callbacks = Arc::downgrade(&self.component_callbacks);
+        Subscription::new(id, move || {
+            if let Some(callbacks) = callbacks.upgrade() {
+                for fcb in callbacks.lock().unwrap().iter_mut() {
+                    fcb.unsubscribe(id);
+                }
+            }
+            if let Some(component_callbacks) = component_callbacks.upgrade() {
+                component_callbacks
+                    .lock()
+                    .unwrap()
+                    .retain(|(other, _)| *other != id);
+            }
+        })
+    }
+
+    /// Unsubscribe all callbacks from a variable to avoid receiving further events.
+    ///
+    /// Callbacks added with [`subscribe_all`](Self::subscribe_all) are not affected.
     pub fn unsubscribe<'s>(&mut self, variable: &'s str) -> Result<(), NoSuchVariable<'s>> {
         if let Some(&index) = self.name_to_index.get(variable) {
-            self.callbacks.lock().unwrap()[index].unsubscribe();
+            // Keep the callbacks of the whole component
+            let component_callbacks = self.component_callbacks.lock().unwrap();
+            self.callbacks.lock().unwrap()[index]
+                .retain(|id| component_callbacks.iter().any(|(other, _)| *other == id));
             Ok(())
         } else {
             Err(NoSuchVariable(variable))
@@ -101,8 +282,14 @@ impl<T> Component<T> {
         value: impl Into<T>,
     ) -> Result<(), NoSuchVariable<'s>> {
         let idx = self.variable_index(variable)?;
+        self.edit_at(idx, value.into());
+        Ok(())
+    }
+
+    /// Edit the value of the variable with index `idx`.
+    pub(crate) fn edit_at(&mut self, idx: usize, value: T) {
         self.ranker.touch(idx);
-        let value = value.into();
+        self.modified = true;
 
         // Call callback
         self.callbacks.lock().unwrap()[idx].call(EventWithLocation::new(
@@ -113,6 +300,63 @@ impl<T> Component<T> {
 
         // Create a new activation
         self.variables.set(idx, Activation::from(value));
+    }
+
+    /// Edits multiple variables as one change.
+    ///
+    /// The edits are only applied if all of them succeed, and they are undone together.
+    /// Each edited variable is moved to the top of the ranking once.
+    /// If [`Transaction::solve`] is called, the component is solved afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{component, ret, model::{Activation, Component}};
+    /// let mut component: Component<i32> = component! {
+    ///     component Point {
+    ///         let x: i32 = 0, y: i32 = 0;
+    ///     }
+    /// };
+    /// component
+    ///     .transaction(|tx| {
+    ///         tx.edit("x", 3);
+    ///         tx.edit("y", 4);
+    ///         tx.solve();
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(component.value("y"), Ok(Activation::from(4)));
+    /// component.undo().unwrap();
+    /// assert_eq!(component.value("x"), Ok(Activation::from(0)));
+    /// assert_eq!(component.value("y"), Ok(Activation::from(0)));
+    /// ```
+    pub fn transaction(
+        &mut self,
+        f: impl FnOnce(&mut Transaction<T>),
+    ) -> Result<(), TransactionError>
+    where
+        T: Send + Sync + 'static + Debug,
+    {
+        let mut transaction = Transaction::new();
+        f(&mut transaction);
+        let (edits, solve) = transaction.finish()?;
+
+        // Find all variables before applying anything
+        let edits = edits
+            .into_iter()
+            .map(
+                |(variable, value)| match self.name_to_index.get(&variable) {
+                    Some(&idx) => Ok((idx, value)),
+                    None => Err(TransactionError::NoSuchVariable(variable)),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+        for (idx, value) in last_edits(edits) {
+            self.edit_at(idx, value);
+        }
+
+        if solve {
+            self.solve()?;
+        }
         Ok(())
     }
 
@@ -131,6 +375,7 @@ impl<T> Component<T> {
         variable: &'a str,
     ) -> Result<&mut Variable<Activation<T>>, NoSuchVariable<'a>> {
         let idx = self.variable_index(variable)?;
+        self.modified = true;
         self.variables.get_mut(idx).ok_or(NoSuchVariable(variable))
     }
 
@@ -145,7 +390,10 @@ impl<T> Component<T> {
     }
 
     /// Returns the index of the specified variable, if it exists.
-    fn variable_index<'s>(&self, variable: &'s str) -> Result<usize, NoSuchVariable<'s>> {
+    pub(crate) fn variable_index<'s>(
+        &self,
+        variable: &'s str,
+    ) -> Result<usize, NoSuchVariable<'s>> {
         match self.name_to_index.get(variable) {
             Some(&index) => Ok(index),
             None => Err(NoSuchVariable(variable)),
@@ -190,10 +438,13 @@ impl<T> Component<T> {
         &mut self,
         name: &'a str,
     ) -> Result<&mut Constraint<T>, NoSuchConstraint<'a>> {
-        self.constraints
-            .iter_mut()
-            .find(|c| c.name() == name)
-            .ok_or(NoSuchConstraint(name))
+        let position = self
+            .constraints
+            .iter()
+            .position(|c| c.name() == name)
+            .ok_or(NoSuchConstraint(name))?;
+        self.invalidate_plans();
+        Ok(&mut self.constraints[position])
     }
 
     /// Constructs a new component from a precomputed map from variable names to indices.
@@ -214,6 +465,14 @@ impl<T> Component<T> {
         component
     }
 
+    /// Sets the planner to use when solving the component.
+    ///
+    /// The default is [`HierarchicalPlanner`].
+    pub fn set_planner(&mut self, planner: impl Plan<Self> + Send + Sync + 'static) {
+        self.invalidate_plans();
+        self.planner = Arc::new(planner);
+    }
+
     /// Enforces all constraints in the component.
     ///
     /// Returns [`PlanError`] if the system is overconstrained.
@@ -221,28 +480,192 @@ impl<T> Component<T> {
     where
         T: Send + Sync + 'static + Debug,
     {
-        self.par_solve(&DummyExecutor)
+        self.par_solve(&DummyExecutor).map(drop)
     }
 
     /// Enforces all constraints in the component using the specified [`MethodExecutor`].
     ///
+    /// Returns a [`SolveHandle`] to wait for or cancel the scheduled methods,
+    /// or [`PlanError`] if the system is overconstrained.
+    pub fn par_solve(&mut self, pool: &impl MethodExecutor) -> Result<SolveHandle<T>, PlanError>
+    where
+        T: Send + Sync + 'static + Debug,
+    {
+        let outputs = self.solve_uncommitted(pool)?;
+
+        // Commit changes
+        self.variables.commit();
+
+        Ok(SolveHandle::new(outputs))
+    }
+
+    /// Makes a plan for enforcing all constraints in the component, without running any methods.
+    /// The plan can be inspected or changed, and then run with [`Component::execute`].
+    ///
     /// Returns [`PlanError`] if the system is overconstrained.
-    pub fn par_solve(&mut self, pool: &impl MethodExecutor) -> Result<(), PlanError>
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{component, ret, executor::DummyExecutor, model::{Activation, Component}};
+    /// let mut component: Component<i32> = component! {
+    ///     component Comp {
+    ///         let a: i32 = 0, b: i32 = 0;
+    ///         constraint Double {
+    ///             double(a: &i32) -> [b] = ret![*a * 2];
+    ///             half(b: &i32) -> [a] = ret![*b / 2];
+    ///         }
+    ///     }
+    /// };
+    /// component.edit("a", 3).unwrap();
+    /// let plan = component.plan().unwrap();
+    /// assert_eq!(plan.to_string(), "Double.double(a) -> [b]\n");
+    /// assert_eq!(plan.written(), vec!["b"]);
+    /// component.execute(&plan, &DummyExecutor).unwrap();
+    /// assert_eq!(component.value("b"), Ok(Activation::from(6)));
+    /// ```
+    pub fn plan(&self) -> Result<ComponentPlan<T>, PlanError> {
+        self.plan_timed().0
+    }
+
+    /// Makes a plan to run right away, and records the time spent planning it.
+    pub(crate) fn plan_to_solve(&self) -> Result<ComponentPlan<T>, PlanError> {
+        let (plan, planning_time) = self.plan_timed();
+        if let (Some(stats), Some(planning_time)) = (&self.stats, planning_time) {
+            stats.lock().unwrap().record_planning(planning_time);
+        }
+        plan
+    }
+
+    /// Makes a plan like [`Component::plan`], and returns the time spent in the planner,
+    /// or `None` if the plan was reused from the cache.
+    fn plan_timed(&self) -> (Result<ComponentPlan<T>, PlanError>, Option<Duration>) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("plan").entered();
+        let run_planner = || {
+            let planning_started = Instant::now();
+            let plan = self.planner.plan(self);
+            (plan, Some(planning_started.elapsed()))
+        };
+        let (plan, planning_time) = match &self.plan_cache {
+            None => run_planner(),
+            Some(cache) => {
+                let key = self.plan_key();
+                match cache.get(&key) {
+                    Some(plan) => (Ok(plan), None),
+                    None => {
+                        let (plan, planning_time) = run_planner();
+                        if let Ok(plan) = &plan {
+                            cache.insert(key, plan.clone());
+                        }
+                        (plan, planning_time)
+                    }
+                }
+            }
+        };
+        let plan = plan.map_err(|e| self.locate(e)).map(|plan| {
+            ComponentPlan::new(
+                self.name.clone(),
+                self.revision,
+                self.names_of((0..self.n_variables()).collect()),
+                plan,
+            )
+        });
+        (plan, planning_time)
+    }
+
+    /// Runs the enabled methods of a plan made by [`Component::plan`] using the specified [`MethodExecutor`],
+    /// like [`Component::par_solve`] does with a new plan.
+    ///
+    /// Returns [`PlanMismatch`] if the plan was made for another component,
+    /// or before variables or constraints of this one were changed, like when adding, removing,
+    /// disabling or pinning them.
+    pub fn execute(
+        &mut self,
+        plan: &ComponentPlan<T>,
+        pool: &impl MethodExecutor,
+    ) -> Result<SolveHandle<T>, PlanMismatch>
+    where
+        T: Send + Sync + 'static + Debug,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "solve",
+            component = %self.name(),
+            generation = tracing::field::Empty,
+        )
+        .entered();
+
+        if plan.component() != self.name || plan.revision() != self.revision {
+            return Err(PlanMismatch(plan.component().to_owned()));
+        }
+        let outputs = self.execute_uncommitted(plan, pool);
+
+        // Commit changes
+        self.variables.commit();
+
+        Ok(SolveHandle::new(outputs))
+    }
+
+    /// Enforces all constraints like [`Component::par_solve`], but without committing the changes.
+    /// Returns the new activations of the variables that were written to.
+    pub(crate) fn solve_uncommitted(
+        &mut self,
+        pool: &impl MethodExecutor,
+    ) -> Result<Vec<Activation<T>>, PlanError>
+    where
+        T: Send + Sync + 'static + Debug,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "solve",
+            component = %self.name(),
+            generation = tracing::field::Empty,
+        )
+        .entered();
+
+        let plan = self.plan_to_solve()?;
+        Ok(self.execute_uncommitted(&plan, pool))
+    }
+
+    /// Runs the enabled methods of a plan without committing the changes.
+    /// Returns the new activations of the variables that were written to.
+    pub(crate) fn execute_uncommitted(
+        &mut self,
+        plan: &ComponentPlan<T>,
+        pool: &impl MethodExecutor,
+    ) -> Vec<Activation<T>>
     where
         T: Send + Sync + 'static + Debug,
     {
-        let plan = hierarchical_planner(self)?;
+        let plan: OwnedPlan<Method<T>> = plan.enabled().cloned().collect();
+        if let Some(stats) = &self.stats {
+            stats.lock().unwrap().record_solve();
+        }
+        self.modified = false;
+        self.last_ranking = Some(self.ranking());
         self.ranker = adjust_priorities(&plan, &self.ranker);
 
+        // Store the constraints that were dropped due to conflicts with stronger ones
+        let enforced: HashSet<&str> = plan.iter().map(|ec| ec.name()).collect();
+        self.unenforced = self
+            .constraints
+            .iter()
+            .filter(|c| {
+                c.is_active() && c.strength() != Strength::Required && !enforced.contains(c.name())
+            })
+            .map(|c| c.name().to_owned())
+            .collect();
+
         let component_name = self.name().to_owned();
 
         // Clone the variable information for use in the general callback
         let variable_information_clone = self.callbacks.clone();
 
         // Set the new callbacks to respond to.
-        self.current_generation += 1;
-        self.total_generation += 1;
-        let generation = GenerationId::new(self.current_generation, self.total_generation);
+        let generation = self.begin_generation();
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("generation", &tracing::field::debug(generation));
         // Up the generation of variables that are written to
         for p in &plan {
             let m = p.method();
@@ -258,18 +681,90 @@ impl<T> Component<T> {
             &mut self.variables,
             component_name,
             generation,
+            self.method_timeout,
+            self.stats.as_ref(),
             pool,
             move |ge| {
-                let mut lock = variable_information_clone.lock().unwrap();
-                let fcb = &mut lock[ge.variable()];
-                fcb.call(ge);
+                let lock = variable_information_clone.lock().unwrap();
+                // The variable may have been removed since the method started
+                if let Some(fcb) = lock.get(ge.variable()) {
+                    fcb.call(ge);
+                }
             },
         );
 
-        // Commit changes
+        plan.iter()
+            .flat_map(|p| p.method().outputs())
+            .map(|&o| self.variables[o].clone())
+            .collect()
+    }
+
+    /// Starts a new generation, and returns its id.
+    pub(crate) fn begin_generation(&mut self) -> GenerationId {
+        self.current_generation += 1;
+        self.total_generation += 1;
+        GenerationId::new(self.current_generation, self.total_generation)
+    }
+
+    /// Returns true if the variables have changed since the last commit.
+    pub(crate) fn has_uncommitted_changes(&self) -> bool {
+        self.variables.is_modified()
+    }
+
+    /// Stores a checkpoint of the current values that can be returned to with undo and redo.
+    pub(crate) fn commit(&mut self) {
         self.variables.commit();
+    }
 
-        Ok(())
+    /// Gives a variable a new activation produced by solving.
+    pub(crate) fn set_solved(&mut self, index: usize, activation: Activation<T>) {
+        if let Some(previous) = self.variables.get_mut(index) {
+            previous.detach();
+        }
+        self.variables.set(index, activation);
+    }
+
+    /// Returns the callbacks of the variables.
+    pub(crate) fn callbacks(&self) -> &Arc<Mutex<Vec<FilteredCallback<T, SolveError>>>> {
+        &self.callbacks
+    }
+
+    /// Returns the planner used when solving the component.
+    pub(crate) fn planner(&self) -> SharedPlanner<Self> {
+        self.planner.clone()
+    }
+
+    /// Returns the map from variable names to indices.
+    pub(crate) fn name_to_index(&self) -> &HashMap<String, usize> {
+        &self.name_to_index
+    }
+
+    /// Replaces the ranking of the variables, where the first one has the highest priority.
+    pub(crate) fn set_ranking(&mut self, ranking: &[usize]) {
+        self.ranker = SortRanker::of_size(self.n_variables());
+        for &vi in ranking.iter().rev() {
+            self.ranker.touch(vi);
+        }
+    }
+
+    /// Sets the names of the constraints that were left unenforced in the last solve.
+    pub(crate) fn set_unenforced(&mut self, unenforced: Vec<String>) {
+        self.unenforced = unenforced;
+    }
+
+    /// Constructs a new component from existing activations,
+    /// where the first variable in `ranking` has the highest priority.
+    pub(crate) fn from_activations(
+        name: String,
+        name_to_index: HashMap<String, usize>,
+        activations: Vec<Activation<T>>,
+        constraints: Vec<Constraint<T>>,
+        ranking: &[usize],
+    ) -> Self {
+        let mut component = Component::new(name, activations, constraints);
+        component.name_to_index = name_to_index;
+        component.set_ranking(ranking);
+        component
     }
 
     /// Pins a variable.
@@ -282,12 +777,8 @@ impl<T> Component<T> {
         T: 'static,
     {
         let idx = self.variable_index(variable)?;
-        self.constraints.push(Constraint::new(vec![Method::new(
-            "pin".to_owned() + &idx.to_string(),
-            vec![idx],
-            vec![idx],
-            Arc::new(Ok),
-        )]));
+        self.invalidate_plans();
+        self.constraints.push(Self::pin_constraint(idx));
         Ok(())
     }
 
@@ -299,56 +790,540 @@ impl<T> Component<T> {
         T: 'static,
     {
         let target = self.variable_index(variable)?;
-        self.constraints.drain_filter(|c| {
-            c.methods()
-                .get(0)
-                .map(|m| m.name() == Some(&("pin".to_owned() + &target.to_string())))
-                .unwrap_or(false)
-        });
+        self.invalidate_plans();
+        self.constraints
+            .drain_filter(|c| Self::pinned_variable(c) == Some(target));
+        Ok(())
+    }
+
+    /// Constructs the stay constraint used to pin a variable.
+    fn pin_constraint(index: usize) -> Constraint<T>
+    where
+        T: 'static,
+    {
+        Constraint::new(vec![Method::new(
+            "pin".to_owned() + &index.to_string(),
+            vec![index],
+            vec![index],
+            Arc::new(Ok),
+        )])
+    }
+
+    /// Returns the variable pinned by the constraint, if it was added by [`Component::pin`].
+    fn pinned_variable(constraint: &Constraint<T>) -> Option<usize> {
+        let method = constraint.methods().get(0)?;
+        let &index = method.outputs().first()?;
+        (method.name() == Some(&("pin".to_owned() + &index.to_string()))).then(|| index)
+    }
+
+    /// Adds a new variable with the specified name and value.
+    ///
+    /// It starts with the lowest priority, and has no undo history.
+    pub fn add_variable<'a>(
+        &mut self,
+        name: &'a str,
+        value: impl Into<T>,
+    ) -> Result<(), VariableExists<'a>>
+    where
+        T: 'static,
+    {
+        if self.name_to_index.contains_key(name) {
+            return Err(VariableExists(name));
+        }
+        let index = self.n_variables();
+        self.invalidate_plans();
+        self.last_ranking = None;
+        self.name_to_index.insert(name.to_owned(), index);
+        self.variables.push(Activation::from(value.into()));
+        let mut fcb = FilteredCallback::new();
+        for (id, callback) in self.component_callbacks.lock().unwrap().iter() {
+            fcb.subscribe(*id, with_name(name, callback));
+        }
+        self.callbacks.lock().unwrap().push(fcb);
+        self.ranker.resize(index + 1);
+        self.n_ready += 1;
+        Ok(())
+    }
+
+    /// Removes the variable with the specified name.
+    ///
+    /// Fails if any constraints reference the variable,
+    /// but [`Component::remove_variable_and_constraints`] can be used to remove them too.
+    /// Pins of the variable are always removed.
+    pub fn remove_variable<'a>(&mut self, name: &'a str) -> Result<(), RemoveVariableError<'a>>
+    where
+        T: 'static,
+    {
+        let index = self.variable_index(name)?;
+        let constraints: Vec<String> = self
+            .constraints
+            .iter()
+            .filter(|c| c.variables().contains(&index) && Self::pinned_variable(c).is_none())
+            .map(|c| c.name().to_owned())
+            .collect();
+        if !constraints.is_empty() {
+            return Err(RemoveVariableError::InUse(name, constraints));
+        }
+        self.remove_variable_at(index);
+        Ok(())
+    }
+
+    /// Removes the variable with the specified name, along with the constraints that reference it.
+    ///
+    /// Returns the names of the removed constraints, not including pins.
+    pub fn remove_variable_and_constraints<'a>(
+        &mut self,
+        name: &'a str,
+    ) -> Result<Vec<String>, NoSuchVariable<'a>>
+    where
+        T: 'static,
+    {
+        let index = self.variable_index(name)?;
+        let removed = self
+            .constraints
+            .iter()
+            .filter(|c| c.variables().contains(&index) && Self::pinned_variable(c).is_none())
+            .map(|c| c.name().to_owned())
+            .collect();
+        self.remove_variable_at(index);
+        Ok(removed)
+    }
+
+    /// Removes the variable at `index` and the constraints that reference it,
+    /// and moves the variables after it one index down.
+    fn remove_variable_at(&mut self, index: usize)
+    where
+        T: 'static,
+    {
+        log::trace!("Removing variable {} from {}", index, self.name);
+        self.invalidate_plans();
+        self.last_ranking = None;
+        let shift = |vi: usize| if vi > index { vi - 1 } else { vi };
+        let ranking: Vec<usize> = self
+            .ranking()
+            .into_iter()
+            .filter(|&vi| vi != index)
+            .map(shift)
+            .collect();
+
+        self.constraints = self
+            .constraints
+            .iter()
+            .filter(|c| !c.variables().contains(&index))
+            .map(|c| match Self::pinned_variable(c) {
+                Some(vi) => Self::pin_constraint(shift(vi)),
+                None => c.reindexed(c.name().to_owned(), shift),
+            })
+            .collect();
+        self.name_to_index.retain(|_, vi| *vi != index);
+        let name_to_index = &self.name_to_index;
+        self.priority_levels
+            .retain(|name, _| name_to_index.contains_key(name));
+        for vi in self.name_to_index.values_mut() {
+            *vi = shift(*vi);
+        }
+        self.variables.remove(index);
+        // Pending methods still report events by the old indices,
+        // so the variables that moved must ignore events from before the removal.
+        self.total_generation += 1;
+        let generation = GenerationId::new(self.current_generation, self.total_generation);
+        let mut callbacks = self.callbacks.lock().unwrap();
+        callbacks.remove(index);
+        for fcb in &mut callbacks[index..] {
+            fcb.set_target(generation);
+        }
+        drop(callbacks);
+        self.set_ranking(&ranking);
+        self.n_ready = self.n_ready.saturating_sub(1);
+    }
+
+    /// Adds a constraint whose methods refer to variables by name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{component, ret, macros::{RawConstraint, RawMethod}, model::{Activation, Component}};
+    /// # use std::sync::Arc;
+    /// let mut component: Component<i32> = component! {
+    ///     component Comp {
+    ///         let a: i32 = 0;
+    ///     }
+    /// };
+    /// component.add_variable("b", 0).unwrap();
+    /// component
+    ///     .add_constraint(RawConstraint::new(
+    ///         "Equal",
+    ///         vec![RawMethod::new("a_to_b", vec!["a"], vec!["b"], Arc::new(Ok))],
+    ///     ))
+    ///     .unwrap();
+    /// component.edit("a", 3).unwrap();
+    /// component.solve().unwrap();
+    /// assert_eq!(component.value("b"), Ok(Activation::from(3)));
+    /// ```
+    pub fn add_constraint(&mut self, constraint: RawConstraint<T>) -> Result<(), AddConstraintError>
+    where
+        T: Clone,
+    {
+        if self.constraint(constraint.name()).is_ok() {
+            return Err(AddConstraintError::ConstraintExists(
+                constraint.name().to_owned(),
+            ));
+        }
+        if let Some(variable) = constraint
+            .variables()
+            .find(|v| !self.name_to_index.contains_key(*v))
+        {
+            return Err(AddConstraintError::NoSuchVariable(variable.to_owned()));
+        }
+        let constraint = constraint.into_constraint(&self.name_to_index);
+        self.invalidate_plans();
+        self.constraints.push(constraint);
+        Ok(())
+    }
+
+    /// Removes the constraint with the specified name.
+    pub fn remove_constraint<'a>(&mut self, name: &'a str) -> Result<(), NoSuchConstraint<'a>> {
+        let position = self
+            .constraints
+            .iter()
+            .position(|c| c.name() == name)
+            .ok_or(NoSuchConstraint(name))?;
+        self.invalidate_plans();
+        self.constraints.remove(position);
         Ok(())
     }
 
     /// Returns true if any variables have been updated since
     /// the last solve, meaning that any constraints may be broken.
+    /// Changes to the constraints count as well.
     pub fn is_modified(&self) -> bool {
-        true
+        self.modified
+    }
+
+    /// Marks the component as modified or not, such as when it was solved along with other components.
+    pub(crate) fn set_modified(&mut self, modified: bool) {
+        self.modified = modified;
     }
 
-    /// Returns the current ranking of variables,
-    /// based on when they were last updated.
+    /// Returns the indices of the variables from highest to lowest priority,
+    /// as decided by the ranking strategy of the component, see [`Component::set_ranking_strategy`].
     pub fn ranking(&self) -> Vec<usize> {
-        self.ranker.ranking()
+        self.rank(self.ranker.ranking())
     }
 
-    /// Constructs a string-representation of a graph formatted in the [dot language](https://graphviz.org/doc/info/lang.html).
-    /// This can be used for visualization of the constraint graph that the component represents.
-    /// This function includes every method in a constraint.
-    pub fn to_dot_detailed(&self) -> Result<String, fmt::Error> {
-        // Generate map from index to name
-        let mut index_to_name = HashMap::new();
-        for (k, v) in &self.name_to_index {
-            index_to_name.insert(v, k);
+    /// Returns the names of the variables from highest to lowest priority, like [`Component::ranking`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{component, ret, model::Component};
+    /// let mut component: Component<i32> = component! {
+    ///     component Comp {
+    ///         let a: i32 = 0, b: i32 = 0, c: i32 = 0;
+    ///     }
+    /// };
+    /// component.edit("b", 1).unwrap();
+    /// let saved: Vec<String> = component.ranking_names().into_iter().map(String::from).collect();
+    /// assert_eq!(saved, ["b", "a", "c"]);
+    /// component.edit("c", 1).unwrap();
+    /// let saved: Vec<&str> = saved.iter().map(String::as_str).collect();
+    /// component.set_ranking_by_name(&saved).unwrap();
+    /// assert_eq!(component.ranking_names(), ["b", "a", "c"]);
+    /// ```
+    pub fn ranking_names(&self) -> Vec<&str> {
+        let mut names = vec![""; self.n_variables()];
+        for (name, &vi) in &self.name_to_index {
+            names[vi] = name;
         }
+        self.ranking().into_iter().map(|vi| names[vi]).collect()
+    }
 
-        // Start writing dot formatted text
-        let mut buffer = String::new();
-        writeln!(buffer, "strict digraph \"{}\" {{", self.name())?;
-        writeln!(buffer, "  rankdir=LR;")?;
+    /// Replaces the ranking of the variables by recency, where the first one has the highest priority.
+    /// Variables that are not listed get the lowest priorities, in the order they were declared.
+    ///
+    /// This restores a ranking from [`Component::ranking_names`] for the same ranking strategy.
+    pub fn set_ranking_by_name<'a>(
+        &mut self,
+        ranking: &[&'a str],
+    ) -> Result<(), NoSuchVariable<'a>> {
+        let ranking = ranking
+            .iter()
+            .map(|name| self.variable_index(name))
+            .collect::<Result<Vec<usize>, _>>()?;
+        self.set_ranking(&ranking);
+        Ok(())
+    }
 
-        // Set variable shapes to box
-        for vi in 0..self.n_variables() {
-            if let Some(name) = index_to_name.get(&vi) {
-                writeln!(buffer, "  {} [shape=box];", name)?;
-            }
-        }
+    /// Sets how variables are ranked when planning.
+    /// This can be one of the [`RankingStrategy`] variants, or any other implementation of [`Rank`].
+    ///
+    /// The default is [`RankingStrategy::Recency`].
+    pub fn set_ranking_strategy(&mut self, strategy: impl Rank + Send + Sync + 'static) {
+        self.ranking_strategy = Arc::new(strategy);
+    }
 
-        for c in self.constraints() {
-            writeln!(buffer, "  subgraph {} {{", c.name())?;
-            writeln!(buffer, "    color=gray;")?;
-            writeln!(buffer, "    style=filled;")?;
-            writeln!(buffer, "    style=rounded;")?;
-            writeln!(buffer, "    label={};", c.name())?;
-            for m in c.methods() {
+    /// Returns how variables are ranked when planning.
+    pub fn ranking_strategy(&self) -> SharedRankingStrategy {
+        self.ranking_strategy.clone()
+    }
+
+    /// Sets the priority level of a variable, used by [`RankingStrategy::Fixed`] and [`RankingStrategy::Hybrid`].
+    /// Variables with higher levels have higher priority.
+    pub fn set_priority<'s>(
+        &mut self,
+        variable: &'s str,
+        level: i32,
+    ) -> Result<(), NoSuchVariable<'s>> {
+        self.variable_index(variable)?;
+        self.priority_levels.insert(variable.to_owned(), level);
+        Ok(())
+    }
+
+    /// Returns the priority level of a variable, which is 0 unless it has been set.
+    pub fn priority<'s>(&self, variable: &'s str) -> Result<i32, NoSuchVariable<'s>> {
+        self.variable_index(variable)?;
+        Ok(self
+            .priority_levels
+            .get(variable)
+            .copied()
+            .unwrap_or_default())
+    }
+
+    /// Ranks the variables with the strategy of the component, given their ranking by recency.
+    fn rank(&self, recency: Vec<usize>) -> Vec<usize> {
+        let mut levels = vec![0; self.n_variables()];
+        for (name, &level) in &self.priority_levels {
+            if let Some(&vi) = self.name_to_index.get(name) {
+                levels[vi] = level;
+            }
+        }
+        self.ranking_strategy.rank(recency, &levels)
+    }
+
+    /// Returns the names of the variables that are recomputed from `variable`
+    /// by the plan for the current ranking, in the order they were declared.
+    ///
+    /// Editing `variable` may select other methods.
+    /// Use [`Component::affected_by_edit`] to get the variables that an edit would change.
+    pub fn downstream_of(&self, variable: &str) -> Result<Vec<String>, QueryError> {
+        let index = self.query_index(variable)?;
+        let plan = self.dry_plan(None)?;
+        Ok(self.names_of(dependencies::downstream(&plan, index)))
+    }
+
+    /// Returns the names of the variables that the value of `variable` is computed from
+    /// by the plan for the current ranking, in the order they were declared.
+    pub fn upstream_of(&self, variable: &str) -> Result<Vec<String>, QueryError> {
+        let index = self.query_index(variable)?;
+        let plan = self.dry_plan(None)?;
+        Ok(self.names_of(dependencies::upstream(&plan, index)))
+    }
+
+    /// Returns the names of the variables that would be recomputed if `variable` was edited,
+    /// in the order they were declared.
+    ///
+    /// This plans as if `variable` had been edited, but does not run any methods or change the component.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{component, ret, model::Component};
+    /// let component: Component<i32> = component! {
+    ///     component Rectangle {
+    ///         let width: i32 = 2, height: i32 = 3, area: i32 = 6;
+    ///         constraint Area {
+    ///             a(width: &i32, height: &i32) -> [area] = ret![*width * *height];
+    ///             h(width: &i32, area: &i32) -> [height] = ret![*area / *width];
+    ///         }
+    ///     }
+    /// };
+    /// assert_eq!(component.affected_by_edit("width"), Ok(vec!["area".to_owned()]));
+    /// assert_eq!(component.affected_by_edit("area"), Ok(vec!["height".to_owned()]));
+    /// ```
+    pub fn affected_by_edit(&self, variable: &str) -> Result<Vec<String>, QueryError> {
+        let index = self.query_index(variable)?;
+        let plan = self.dry_plan(Some(index))?;
+        Ok(self.names_of(dependencies::downstream(&plan, index)))
+    }
+
+    /// Explains why a variable was written to by the last solve, or why it was not.
+    ///
+    /// This reports the constraint and method that wrote to it, the variables with higher priority
+    /// whose stay constraints forced that choice, and the other methods of the constraint,
+    /// along with the higher-priority variables they would have overwritten.
+    /// The plan is made again by the planner of the component, see [`Component::set_planner`],
+    /// with the ranking of the last solve, or the current ranking if the component has not been solved.
+    /// Variables that the plan does not write to count as kept.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{component, ret, model::Component};
+    /// let mut component: Component<i32> = component! {
+    ///     component Rectangle {
+    ///         let width: i32 = 2, height: i32 = 3, area: i32 = 6;
+    ///         constraint Area {
+    ///             a(width: &i32, height: &i32) -> [area] = ret![*width * *height];
+    ///             h(width: &i32, area: &i32) -> [height] = ret![*area / *width];
+    ///         }
+    ///     }
+    /// };
+    /// component.edit("height", 4).unwrap();
+    /// component.edit("width", 5).unwrap();
+    /// component.solve().unwrap();
+    /// let explanation = component.explain("area").unwrap();
+    /// assert_eq!(explanation.method(), Some("a"));
+    /// assert_eq!(explanation.forced_by(), ["width", "height"]);
+    /// assert_eq!(
+    ///     explanation.to_string(),
+    ///     "area was written by Area.a to keep width, height\n\
+    ///      Area.h was rejected since it would overwrite height"
+    /// );
+    /// ```
+    pub fn explain(&self, variable: &str) -> Result<Explanation, QueryError> {
+        let index = self.query_index(variable)?;
+        let ranking = self.last_ranking.clone().unwrap_or_else(|| self.ranking());
+        let plan = self.plan_with_ranking(&ranking)?;
+        let written: HashSet<usize> = plan
+            .iter()
+            .flat_map(|ec| ec.method().outputs())
+            .copied()
+            .collect();
+        let writer = match plan
+            .iter()
+            .find(|ec| ec.method().outputs().contains(&index))
+        {
+            Some(writer) => writer,
+            None => return Ok(Explanation::unwritten(variable.to_owned())),
+        };
+        let names = self.names_of((0..self.n_variables()).collect());
+
+        // The variables that were kept and have a higher priority, from highest to lowest
+        let kept: Vec<usize> = ranking
+            .iter()
+            .take_while(|&&vi| vi != index)
+            .filter(|vi| !written.contains(vi))
+            .copied()
+            .collect();
+        let mut forcing: HashSet<usize> =
+            dependencies::upstream(&plan, index).into_iter().collect();
+        let mut rejected = Vec::new();
+        let constraint = self
+            .constraints
+            .iter()
+            .find(|c| c.name() == writer.name())
+            .expect("planned constraints belong to the component");
+        for method in constraint.methods() {
+            if method.name() == writer.method().name() {
+                continue;
+            }
+            let overwrites: Vec<usize> = kept
+                .iter()
+                .filter(|vi| method.outputs().contains(vi))
+                .copied()
+                .collect();
+            forcing.extend(&overwrites);
+            rejected.push(RejectedMethod::new(
+                method.name().unwrap_or_default().to_owned(),
+                overwrites.iter().map(|&vi| names[vi].clone()).collect(),
+            ));
+        }
+        let forced_by = kept
+            .iter()
+            .filter(|vi| forcing.contains(vi))
+            .map(|&vi| names[vi].clone())
+            .collect();
+
+        Ok(Explanation::written(
+            variable.to_owned(),
+            writer.name().to_owned(),
+            writer.method().name().unwrap_or_default().to_owned(),
+            forced_by,
+            rejected,
+        ))
+    }
+
+    /// Returns the index of a variable that is queried.
+    fn query_index(&self, variable: &str) -> Result<usize, QueryError> {
+        self.variable_index(variable)
+            .map_err(|_| QueryError::NoSuchVariable(variable.to_owned()))
+    }
+
+    /// Returns the names of the variables with the specified indices.
+    fn names_of(&self, indices: Vec<usize>) -> Vec<String> {
+        let mut names = vec![String::new(); self.n_variables()];
+        for (name, &vi) in &self.name_to_index {
+            names[vi] = name.clone();
+        }
+        indices.into_iter().map(|vi| names[vi].clone()).collect()
+    }
+
+    /// Plans without solving or changing the component,
+    /// as if the variable at index `edited` had been edited if it is specified.
+    fn dry_plan(&self, edited: Option<usize>) -> Result<OwnedPlan<Method<T>>, PlanError> {
+        match edited {
+            None => self.planner.plan_uncached(self).map_err(|e| self.locate(e)),
+            Some(edited) => {
+                let mut ranker = self.ranker.clone();
+                ranker.touch(edited);
+                self.plan_with_ranking(&self.rank(ranker.ranking()))
+            }
+        }
+    }
+
+    /// Plans without solving or changing the component, as if its variables were ranked by `ranking`.
+    fn plan_with_ranking(&self, ranking: &[usize]) -> Result<OwnedPlan<Method<T>>, PlanError> {
+        let ranked = Component::from_activations(
+            self.name.clone(),
+            self.name_to_index.clone(),
+            self.values().into_iter().cloned().collect(),
+            self.constraints.clone(),
+            ranking,
+        );
+        self.planner
+            .plan_uncached(&ranked)
+            .map_err(|e| self.locate(e))
+    }
+
+    /// Adds the name of the component to a planning error.
+    fn locate(&self, error: PlanError) -> PlanError {
+        match error {
+            PlanError::Overconstrained(mut conflict) => {
+                conflict.set_component(self.name());
+                PlanError::Overconstrained(conflict)
+            }
+        }
+    }
+
+    /// Constructs a string-representation of a graph formatted in the [dot language](https://graphviz.org/doc/info/lang.html).
+    /// This can be used for visualization of the constraint graph that the component represents.
+    /// This function includes every method in a constraint.
+    pub fn to_dot_detailed(&self) -> Result<String, fmt::Error> {
+        // Generate map from index to name
+        let mut index_to_name = HashMap::new();
+        for (k, v) in &self.name_to_index {
+            index_to_name.insert(v, k);
+        }
+
+        // Start writing dot formatted text
+        let mut buffer = String::new();
+        writeln!(buffer, "strict digraph \"{}\" {{", self.name())?;
+        writeln!(buffer, "  rankdir=LR;")?;
+
+        // Set variable shapes to box
+        for vi in 0..self.n_variables() {
+            if let Some(name) = index_to_name.get(&vi) {
+                writeln!(buffer, "  {} [shape=box];", name)?;
+            }
+        }
+
+        for c in self.constraints() {
+            writeln!(buffer, "  subgraph {} {{", c.name())?;
+            writeln!(buffer, "    color=gray;")?;
+            writeln!(buffer, "    style=filled;")?;
+            writeln!(buffer, "    style=rounded;")?;
+            writeln!(buffer, "    label={};", c.name())?;
+            for m in c.methods() {
                 writeln!(
                     buffer,
                     "    {}_{} [label={}];",
@@ -447,6 +1422,16 @@ impl<T> Component<T> {
         }
     }
 
+    /// Returns true if there is a previous set/update call to jump back to.
+    pub(crate) fn can_undo(&self) -> bool {
+        self.variables.can_undo()
+    }
+
+    /// Returns true if there is a next set/update call to jump forward to.
+    pub(crate) fn can_redo(&self) -> bool {
+        self.variables.can_redo()
+    }
+
     /// Jumps back to the previous set/update call.
     pub fn undo(&mut self) -> Result<(), NoMoreUndo> {
         // Lock callbacks and which events to respond to
@@ -511,6 +1496,98 @@ impl<T> Component<T> {
         self.variables.set_limit(limit);
     }
 
+    /// Sets the maximum time each method may run for, unless the method has its own timeout.
+    ///
+    /// The outputs of a method that does not finish in time get a [`Reason::Timeout`](crate::solver::Reason::Timeout) error.
+    /// A method that times out keeps running unless it checks its cancellation token, see [`Method::set_timeout`].
+    pub fn set_method_timeout(&mut self, timeout: Option<Duration>) {
+        self.method_timeout = timeout;
+    }
+
+    /// Returns the maximum time each method may run for, unless the method has its own timeout.
+    pub fn method_timeout(&self) -> Option<Duration> {
+        self.method_timeout
+    }
+
+    /// Starts collecting statistics about the calls to each method and the time spent planning.
+    ///
+    /// Statistics that are already being collected are kept.
+    pub fn enable_stats(&mut self) {
+        if self.stats.is_none() {
+            self.stats = Some(SharedStats::default());
+        }
+    }
+
+    /// Stops collecting statistics, and discards the ones collected so far.
+    pub fn disable_stats(&mut self) {
+        self.stats = None;
+    }
+
+    /// Returns the statistics collected so far, or `None` if they are not being collected.
+    pub fn stats(&self) -> Option<Stats> {
+        self.stats
+            .as_ref()
+            .map(|stats| stats.lock().unwrap().clone())
+    }
+
+    /// Sets the statistics to record solving in, so that they can be shared with a constraint system.
+    pub(crate) fn set_shared_stats(&mut self, stats: Option<SharedStats>) {
+        self.stats = stats;
+    }
+
+    /// Starts keeping the `capacity` most recently used plans, so that solving after returning to a previous
+    /// ranking of variables reuses its plan instead of making a new one.
+    ///
+    /// Plans are removed when constraints or variables are added, removed, changed or pinned,
+    /// or when the planner is replaced.
+    /// A cache that is already enabled is replaced by an empty one.
+    pub fn enable_plan_cache(&mut self, capacity: usize) {
+        self.plan_cache = Some(PlanCache::new(capacity));
+    }
+
+    /// Stops keeping plans, and discards the ones kept so far.
+    pub fn disable_plan_cache(&mut self) {
+        self.plan_cache = None;
+    }
+
+    /// Returns the plan cache, or `None` if it is not enabled.
+    pub fn plan_cache(&self) -> Option<&PlanCache<T>> {
+        self.plan_cache.as_ref()
+    }
+
+    /// Removes the cached plans and starts a new revision, since the constraints, variables or planner have changed.
+    /// The component is solved again by [`ConstraintSystem::solve`](super::ConstraintSystem::solve).
+    fn invalidate_plans(&mut self) {
+        if let Some(cache) = &self.plan_cache {
+            cache.invalidate();
+        }
+        self.revision = next_revision();
+        self.modified = true;
+    }
+
+    /// Returns what planning depends on, for looking up plans in the cache.
+    fn plan_key(&self) -> PlanKey {
+        let active: Vec<&Constraint<T>> =
+            self.constraints.iter().filter(|c| c.is_active()).collect();
+        let writable: HashSet<usize> = active
+            .iter()
+            .flat_map(|c| c.methods())
+            .flat_map(|m| m.outputs())
+            .copied()
+            .collect();
+        PlanKey::new(
+            self.ranking()
+                .into_iter()
+                .filter(|vi| writable.contains(vi))
+                .collect(),
+            active.iter().map(|c| c.name().to_owned()).collect(),
+            active
+                .iter()
+                .filter_map(|c| Self::pinned_variable(c))
+                .collect(),
+        )
+    }
+
     /// Enables a specific constraint.
     pub fn enable_constraint<'a>(&mut self, name: &'a str) -> Result<(), NoSuchConstraint<'a>> {
         self.constraint_mut(name).map(|c| c.set_active(true))
@@ -520,6 +1597,21 @@ impl<T> Component<T> {
     pub fn disable_constraint<'a>(&mut self, name: &'a str) -> Result<(), NoSuchConstraint<'a>> {
         self.constraint_mut(name).map(|c| c.set_active(false))
     }
+
+    /// Sets the strength of a specific constraint.
+    pub fn set_constraint_strength<'a>(
+        &mut self,
+        name: &'a str,
+        strength: Strength,
+    ) -> Result<(), NoSuchConstraint<'a>> {
+        self.constraint_mut(name).map(|c| c.set_strength(strength))
+    }
+
+    /// Returns the names of the active constraints that were left unenforced in the last solve,
+    /// since they conflicted with stronger constraints.
+    pub fn unenforced_constraints(&self) -> &[String] {
+        &self.unenforced
+    }
 }
 
 impl<T> ComponentSpec for Component<T> {
@@ -553,24 +1645,39 @@ impl<T> ComponentSpec for Component<T> {
     }
 
     fn constraints_mut(&mut self) -> &mut Vec<Self::Constraint> {
+        self.invalidate_plans();
         &mut self.constraints
     }
 
     fn add_constraint(&mut self, constraint: Self::Constraint) {
+        self.invalidate_plans();
         self.constraints.push(constraint)
     }
 
     fn pop_constraint(&mut self) -> Option<Self::Constraint> {
+        self.invalidate_plans();
         self.constraints.pop()
     }
 
     fn remove_constraint(&mut self, idx: usize) -> Self::Constraint {
+        self.invalidate_plans();
         self.constraints.remove(idx)
     }
 
     fn ranking(&self) -> Vec<usize> {
         self.ranking()
     }
+
+    fn variable_name(&self, index: usize) -> Option<&str> {
+        self.name_to_index
+            .iter()
+            .find(|(_, &i)| i == index)
+            .map(|(name, _)| name.as_str())
+    }
+
+    fn revision(&self) -> Option<usize> {
+        Some(self.revision)
+    }
 }
 
 impl<T> Index<&str> for Component<T> {
@@ -599,16 +1706,36 @@ impl<T> IndexMut<&str> for Component<T> {
     ///
     /// Panics if the constraint is not present in the `Component`.
     fn index_mut(&mut self, index: &str) -> &mut Self::Output {
-        for constraint in &mut self.constraints {
-            if constraint.name() == index {
-                return constraint;
-            }
+        match self.constraint_mut(index) {
+            Ok(constraint) => constraint,
+            Err(_) => panic!("No constraint named {}", index),
         }
+    }
+}
 
-        panic!("No constraint named {}", index)
+/// Calls `callback` with the current state of `activation`.
+fn send_current_state<T>(
+    activation: &Activation<T>,
+    callback: impl FnOnce(Event<'_, T, SolveError>),
+) {
+    let inner = activation.inner().read().unwrap();
+    match inner.state() {
+        State::Pending(_) => callback(Event::Pending),
+        State::Ready(value) => callback(Event::Ready(Ready::Changed(value))),
+        State::Error(data) => callback(Event::Error(data.errors())),
     }
 }
 
+/// Wraps a callback to the events of every variable, so that it is called with the name of `variable`.
+fn with_name<T: 'static>(
+    variable: &str,
+    callback: &NamedEventCallback<T>,
+) -> EventCallback<T, SolveError> {
+    let variable = variable.to_owned();
+    let callback = callback.clone();
+    Arc::new(move |generation, event| callback(&variable, generation, event))
+}
+
 impl<T: PartialEq> PartialEq for Component<T> {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
@@ -616,6 +1743,7 @@ impl<T: PartialEq> PartialEq for Component<T> {
             && self.variables == other.variables
             && self.constraints == other.constraints
             && self.ranker == other.ranker
+            && self.priority_levels == other.priority_levels
     }
 }
 
@@ -625,8 +1753,32 @@ impl<T: PartialEq> Eq for Component<T> {}
 mod tests {
     use super::Component;
     use crate::{
-        component, examples::components::numbers::sum, executor::DummyExecutor,
-        model::activation::Activation, ret,
+        component,
+        event::{Event, OwnedEvent, OwnedReady, Ready},
+        examples::components::numbers::sum,
+        executor::{DummyExecutor, MethodExecutor, TerminationHandle, ThreadExecutor},
+        macros::{Assert, RawConstraint, RawMethod},
+        model::{
+            activation::{Activation, State},
+            errors::{
+                AddConstraintError, NoSuchConstraint, NoSuchVariable, PlanMismatch, QueryError,
+                RemoveVariableError, TransactionError, VariableExists,
+            },
+            undo::{NoMoreUndo, UndoLimit},
+            Method,
+        },
+        planner::{
+            hierarchical_planner, ComponentSpec, Conflict, ConstraintSpec, OwnedPlan, Plan,
+            PlanError, SimplePlanner, Strength,
+        },
+        ret,
+        solver::{Reason, SolveError},
+        variable_ranking::{Rank, RankingStrategy},
+    };
+    use futures::{FutureExt, StreamExt};
+    use std::{
+        sync::{mpsc, Arc, Mutex},
+        time::Duration,
     };
 
     #[test]
@@ -815,4 +1967,1097 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn set_planner_is_used_when_solving() {
+        #[derive(Clone, Copy, Debug)]
+        struct Failing;
+        impl Plan<Component<i32>> for Failing {
+            fn plan(&self, _: &Component<i32>) -> Result<OwnedPlan<Method<i32>>, PlanError> {
+                Err(PlanError::Overconstrained(Conflict::default()))
+            }
+        }
+
+        let mut component: Component<i32> = sum();
+        component.set_planner(Failing);
+        assert!(matches!(
+            component.solve(),
+            Err(PlanError::Overconstrained(_))
+        ));
+
+        // The simple planner ignores the ranking, so the edit to a may be overwritten
+        component.set_planner(SimplePlanner);
+        component.edit("a", 3).unwrap();
+        assert_eq!(component.solve(), Ok(()));
+        assert_eq!(
+            &component.values(),
+            &[
+                &Activation::from(0),
+                &Activation::from(0),
+                &Activation::from(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn weak_constraints_are_reported_as_unenforced() {
+        let mut component: Component<i32> = component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Equal {
+                    a_to_b(a: &i32) -> [b] = ret![*a];
+                    b_to_a(b: &i32) -> [a] = ret![*b];
+                }
+                constraint Double: weak {
+                    a_to_b(a: &i32) -> [b] = ret![*a * 2];
+                    b_to_a(b: &i32) -> [a] = ret![*b / 2];
+                }
+            }
+        };
+
+        component.edit("a", 3).unwrap();
+        component.solve().unwrap();
+        assert_eq!(
+            component.values(),
+            vec![&Activation::from(3), &Activation::from(3)]
+        );
+        assert_eq!(component.unenforced_constraints(), &["Double".to_owned()]);
+
+        // Once the conflict is gone, nothing should be left unenforced
+        component.disable_constraint("Equal").unwrap();
+        component.solve().unwrap();
+        assert_eq!(
+            component.values(),
+            vec![&Activation::from(3), &Activation::from(6)]
+        );
+        assert!(component.unenforced_constraints().is_empty());
+
+        // Making both required should fail
+        component.enable_constraint("Equal").unwrap();
+        component
+            .set_constraint_strength("Double", Strength::Required)
+            .unwrap();
+        let mut conflict = Conflict::new(
+            vec!["Equal".to_owned(), "Double".to_owned()],
+            vec!["a".to_owned(), "b".to_owned()],
+        );
+        conflict.set_component("A");
+        let error = PlanError::Overconstrained(conflict);
+        assert_eq!(component.solve(), Err(error.clone()));
+        assert_eq!(
+            error.to_string(),
+            "No valid plan was found since the system is overconstrained in component A: \
+             constraints Equal, Double conflict over a, b"
+        );
+    }
+
+    fn equal(name: &str, a: &str, b: &str) -> RawConstraint<i32> {
+        RawConstraint::new(
+            name,
+            vec![
+                RawMethod::new("a_to_b", vec![a], vec![b], Arc::new(Ok)),
+                RawMethod::new("b_to_a", vec![b], vec![a], Arc::new(Ok)),
+            ],
+        )
+    }
+
+    #[test]
+    fn add_and_remove_variables() {
+        let mut component: Component<i32> = sum();
+        assert_eq!(component.add_variable("a", 0), Err(VariableExists("a")));
+        component.add_variable("d", 0).unwrap();
+        component.add_constraint(equal("Equal", "c", "d")).unwrap();
+        let latest = Arc::new(Mutex::new(None));
+        let latest_clone = latest.clone();
+        component
+            .subscribe("d", move |event| {
+                if let Event::Ready(Ready::Changed(&value)) = event {
+                    *latest_clone.lock().unwrap() = Some(value);
+                }
+            })
+            .unwrap();
+
+        component.edit("a", 3).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("d"), Ok(Activation::from(3)));
+        assert_eq!(*latest.lock().unwrap(), Some(3));
+
+        // The variable is in use
+        assert_eq!(
+            component.remove_variable("d"),
+            Err(RemoveVariableError::InUse("d", vec!["Equal".to_owned()]))
+        );
+        component.remove_constraint("Equal").unwrap();
+        component.remove_variable("d").unwrap();
+        assert_eq!(component.n_variables(), 3);
+        assert!(component.value("d").is_err());
+
+        // Undo still works for the remaining variables
+        assert_eq!(component.undo(), Ok(()));
+        assert_eq!(
+            component.values(),
+            vec![
+                &Activation::from(0),
+                &Activation::from(0),
+                &Activation::from(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn removing_a_variable_moves_later_variables() {
+        let mut component: Component<i32> = sum();
+        component.add_variable("d", 0).unwrap();
+        component.add_constraint(equal("Equal", "c", "d")).unwrap();
+        component.pin("c").unwrap();
+        component.edit("b", 2).unwrap();
+        component.solve().unwrap();
+
+        assert_eq!(
+            component.remove_variable_and_constraints("a"),
+            Ok(vec!["Sum".to_owned()])
+        );
+        assert_eq!(component.n_variables(), 3);
+        assert_eq!(component.constraints().len(), 2);
+        assert_eq!(component.ranking()[0], 0);
+        assert_eq!(
+            component.values(),
+            vec![
+                &Activation::from(2),
+                &Activation::from(0),
+                &Activation::from(0)
+            ]
+        );
+
+        // The pin on c should remain
+        component.edit("d", 5).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("d"), Ok(Activation::from(0)));
+        component.unpin("c").unwrap();
+        component.edit("d", 5).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("c"), Ok(Activation::from(5)));
+    }
+
+    #[test]
+    fn add_constraint_checks_names() {
+        let mut component: Component<i32> = sum();
+        assert_eq!(
+            component.add_constraint(equal("Sum", "a", "b")),
+            Err(AddConstraintError::ConstraintExists("Sum".to_owned()))
+        );
+        assert_eq!(
+            component.add_constraint(equal("Equal", "a", "d")),
+            Err(AddConstraintError::NoSuchVariable("d".to_owned()))
+        );
+        assert!(component.remove_constraint("Equal").is_err());
+        assert_eq!(component.constraints().len(), 1);
+    }
+
+    #[test]
+    fn failing_conditions_are_reported_as_errors() {
+        let mut component: Component<i32> = component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Small {
+                    precondition |v: &[&i32]| *v[0] < 10;
+                    postcondition |v: &[&i32]| *v[1] < 4;
+                    double(a: &i32) -> [b] = ret![*a * 2];
+                }
+            }
+        };
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let reasons_clone = reasons.clone();
+        component
+            .subscribe("b", move |event| {
+                if let Event::Error(errors) = event {
+                    let mut reasons = reasons_clone.lock().unwrap();
+                    reasons.extend(errors.iter().map(|e| e.reason().clone()));
+                }
+            })
+            .unwrap();
+
+        // Both hold
+        component.edit("a", 1).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(2)));
+        assert!(reasons.lock().unwrap().is_empty());
+
+        // The output is too large
+        component.edit("a", 2).unwrap();
+        component.solve().unwrap();
+        assert_eq!(
+            reasons.lock().unwrap().pop(),
+            Some(Reason::PostConditionFailure("Small".to_owned()))
+        );
+
+        // The method should not run at all
+        component.edit("a", 10).unwrap();
+        component.solve().unwrap();
+        assert_eq!(
+            reasons.lock().unwrap().pop(),
+            Some(Reason::PreConditionFailure("Small".to_owned()))
+        );
+    }
+
+    #[test]
+    fn assertions_are_not_checked() {
+        let mut component: Component<i32> = component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0;
+            }
+        };
+        let assert: Assert<i32> = Arc::new(|values: &[i32]| values[0] == values[1]);
+        let double = RawMethod::new(
+            "double",
+            vec!["a"],
+            vec!["b"],
+            Arc::new(|values: Vec<Arc<i32>>| Ok(vec![Arc::new(*values[0] * 2)])),
+        );
+        component
+            .add_constraint(RawConstraint::new_with_assert(
+                "Double",
+                vec![double],
+                Some(assert),
+            ))
+            .unwrap();
+        component.edit("a", 1).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(2)));
+    }
+
+    #[test]
+    fn transactions_are_undone_as_one_change() {
+        let mut component: Component<i32> = sum();
+        component
+            .transaction(|tx| {
+                tx.edit("a", 1);
+                tx.edit("b", 2);
+                tx.edit("a", 3);
+            })
+            .unwrap();
+        assert_eq!(component.ranking(), vec![0, 1, 2]);
+        component.solve().unwrap();
+        assert_eq!(
+            component.values(),
+            vec![
+                &Activation::from(3),
+                &Activation::from(2),
+                &Activation::from(5)
+            ]
+        );
+        assert_eq!(component.undo(), Ok(()));
+        assert_eq!(
+            component.values(),
+            vec![
+                &Activation::from(0),
+                &Activation::from(0),
+                &Activation::from(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_transactions_are_rolled_back() {
+        let mut component: Component<i32> = sum();
+        assert_eq!(
+            component.transaction(|tx| {
+                tx.edit("a", 1);
+                tx.edit("d", 2);
+                tx.solve();
+            }),
+            Err(TransactionError::NoSuchVariable("d".to_owned()))
+        );
+        assert_eq!(
+            component.transaction(|tx| {
+                tx.edit("a", 1);
+                tx.edit("b", i64::MAX);
+            }),
+            Err(TransactionError::Conversion("b".to_owned()))
+        );
+        assert_eq!(component.value("a"), Ok(Activation::from(0)));
+        assert_eq!(component.undo(), Err(NoMoreUndo));
+    }
+
+    /// Queues methods until they are run manually.
+    #[derive(Default)]
+    struct QueueExecutor {
+        queue: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+    }
+
+    impl QueueExecutor {
+        fn run_all(&self) {
+            let queue: Vec<_> = self.queue.lock().unwrap().drain(..).collect();
+            for f in queue {
+                f();
+            }
+        }
+    }
+
+    impl MethodExecutor for QueueExecutor {
+        type ExecError = ();
+
+        fn schedule(
+            &self,
+            f: impl FnOnce() + Send + 'static,
+        ) -> Result<TerminationHandle, Self::ExecError> {
+            self.queue.lock().unwrap().push(Box::new(f));
+            Ok(TerminationHandle::new().0)
+        }
+    }
+
+    #[test]
+    fn superseded_methods_see_cancellation() {
+        let (started, wait_for_start) = mpsc::channel();
+        let (cancelled, wait_for_cancel) = mpsc::channel();
+        let started = Mutex::new(started);
+        let cancelled = Mutex::new(cancelled);
+        let mut component: Component<i32> = component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Slow {
+                    slow(a: &i32) -> [b] = |token| {
+                        started.lock().unwrap().send(()).unwrap();
+                        // The first call waits until it is cancelled
+                        while *a == 1 && !token.is_cancelled() {
+                            std::thread::yield_now();
+                        }
+                        if *a == 1 {
+                            cancelled.lock().unwrap().send(token.is_cancelled()).unwrap();
+                        }
+                        ret![*a * 2]
+                    };
+                }
+            }
+        };
+        // Old values are not kept alive by the undo history
+        component.set_undo_limit(UndoLimit::Limited(0));
+
+        component.edit("a", 1).unwrap();
+        component.par_solve(&ThreadExecutor).unwrap();
+        let first = component.value("b").unwrap().weak_clone();
+        wait_for_start.recv().unwrap();
+
+        component.edit("a", 2).unwrap();
+        component.par_solve(&ThreadExecutor).unwrap();
+        let timeout = Duration::from_secs(10);
+        assert_eq!(wait_for_cancel.recv_timeout(timeout), Ok(true));
+
+        // The first result is discarded
+        match first.inner().read().unwrap().state() {
+            State::Error(error_data) => {
+                assert_eq!(error_data.errors()[0].reason(), &Reason::Cancelled)
+            }
+            state => panic!("Expected cancellation, got {:?}", state),
+        }
+        let second = futures::executor::block_on(component.value("b").unwrap());
+        assert_eq!(second, Ok(Arc::new(4)));
+    }
+
+    #[test]
+    fn cancelled_methods_are_skipped() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let calls_clone = calls.clone();
+        let mut component: Component<i32> = component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Double {
+                    double(a: &i32) -> [b] = {
+                        calls_clone.lock().unwrap().push(*a);
+                        ret![*a * 2]
+                    };
+                }
+            }
+        };
+        component.set_undo_limit(UndoLimit::Limited(0));
+
+        // Run both generations after the second one has replaced the first
+        let executor = QueueExecutor::default();
+        component.edit("a", 1).unwrap();
+        component.par_solve(&executor).unwrap();
+        component.edit("a", 2).unwrap();
+        component.par_solve(&executor).unwrap();
+        executor.run_all();
+
+        assert_eq!(*calls.lock().unwrap(), vec![2]);
+        assert_eq!(component.value("b"), Ok(Activation::from(4)));
+    }
+
+    /// A component with a method that runs until it is cancelled,
+    /// and reports whether it was when it stops.
+    fn never_finishes(cancelled: mpsc::Sender<bool>) -> Component<i32> {
+        let cancelled = Mutex::new(cancelled);
+        component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Forever {
+                    forever(a: &i32) -> [b] = |token| {
+                        while !token.is_cancelled() {
+                            std::thread::yield_now();
+                        }
+                        cancelled.lock().unwrap().send(token.is_cancelled()).unwrap();
+                        ret![*a]
+                    };
+                }
+            }
+        }
+    }
+
+    /// Solves the component with the executor, and returns the reasons for why `b` failed.
+    fn reasons_for_failure<E: MethodExecutor>(
+        component: &mut Component<i32>,
+        executor: &E,
+    ) -> Vec<Reason>
+    where
+        E::ExecError: std::fmt::Debug,
+    {
+        let (errors, wait_for_errors) = mpsc::channel();
+        let errors = Mutex::new(errors);
+        component
+            .subscribe("b", move |event| {
+                if let Event::Error(errors_) = event {
+                    let reasons = errors_.iter().map(|e| e.reason().clone()).collect();
+                    let _ = errors.lock().unwrap().send(reasons);
+                }
+            })
+            .unwrap();
+        component.edit("a", 1).unwrap();
+        component.par_solve(executor).unwrap();
+        wait_for_errors
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap()
+    }
+
+    #[test]
+    fn slow_methods_time_out() {
+        let (cancelled, wait_for_cancel) = mpsc::channel();
+        let mut component = never_finishes(cancelled);
+        component.set_method_timeout(Some(Duration::from_millis(20)));
+        assert_eq!(
+            reasons_for_failure(&mut component, &ThreadExecutor),
+            vec![Reason::Timeout]
+        );
+        // The method was asked to stop
+        let timeout = Duration::from_secs(10);
+        assert_eq!(wait_for_cancel.recv_timeout(timeout), Ok(true));
+    }
+
+    #[test]
+    fn method_timeouts_override_component_timeouts() {
+        let (cancelled, wait_for_cancel) = mpsc::channel();
+        let mut component = never_finishes(cancelled);
+        component.set_method_timeout(Some(Duration::from_secs(3600)));
+        component.constraints[0].methods_mut()[0].set_timeout(Some(Duration::from_millis(20)));
+        assert_eq!(
+            reasons_for_failure(&mut component, &ThreadExecutor),
+            vec![Reason::Timeout]
+        );
+        let timeout = Duration::from_secs(10);
+        assert_eq!(wait_for_cancel.recv_timeout(timeout), Ok(true));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn slow_methods_time_out_on_thread_pool() {
+        let (cancelled, wait_for_cancel) = mpsc::channel();
+        let mut component = never_finishes(cancelled);
+        component.set_method_timeout(Some(Duration::from_millis(20)));
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        assert_eq!(
+            reasons_for_failure(&mut component, &pool),
+            vec![Reason::Timeout]
+        );
+        let timeout = Duration::from_secs(10);
+        assert_eq!(wait_for_cancel.recv_timeout(timeout), Ok(true));
+    }
+
+    /// Records the values of ready events, and the name of the variable if there is one.
+    type Recorded = Arc<Mutex<Vec<(String, i32)>>>;
+
+    fn record(recorded: &Recorded, variable: &str) -> impl Fn(Event<'_, i32, SolveError>) {
+        let recorded = recorded.clone();
+        let variable = variable.to_owned();
+        move |event| {
+            if let Event::Ready(Ready::Changed(&value)) = event {
+                recorded.lock().unwrap().push((variable.clone(), value));
+            }
+        }
+    }
+
+    #[test]
+    fn variables_have_multiple_subscribers() {
+        let mut component: Component<i32> = sum();
+        let recorded = Recorded::default();
+        let first = component
+            .subscribe_guarded("c", record(&recorded, "first"))
+            .unwrap();
+        component
+            .subscribe("c", record(&recorded, "second"))
+            .unwrap();
+        component.edit("a", 1).unwrap();
+        component.solve().unwrap();
+
+        // Dropping one subscription leaves the other
+        drop(first);
+        component.edit("a", 2).unwrap();
+        component.solve().unwrap();
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![
+                ("first".to_owned(), 0),
+                ("second".to_owned(), 0),
+                ("first".to_owned(), 1),
+                ("second".to_owned(), 1),
+                ("second".to_owned(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn component_subscribers_get_variable_names() {
+        let mut component: Component<i32> = sum();
+        let recorded = Recorded::default();
+        let recorded_clone = recorded.clone();
+        let subscription = component.subscribe_all(move |variable, event| {
+            record(&recorded_clone, variable)(event);
+        });
+        component.add_variable("d", 4).unwrap();
+        // Removing the subscribers of a variable keeps the ones of the component
+        component.unsubscribe("a").unwrap();
+        component.edit("a", 1).unwrap();
+        component.edit("d", 5).unwrap();
+        drop(subscription);
+        component.edit("d", 6).unwrap();
+        let expected = vec![("a", 0), ("b", 0), ("c", 0), ("a", 1), ("d", 5)];
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            expected
+                .into_iter()
+                .map(|(variable, value)| (variable.to_owned(), value))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn event_streams_skip_old_generations() {
+        let mut component: Component<i32> = component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Double {
+                    double(a: &i32) -> [b] = ret![*a * 2];
+                }
+            }
+        };
+        component.set_undo_limit(UndoLimit::Limited(0));
+        let mut events = component.events();
+
+        // The first solve is replaced by the second one before it runs
+        let executor = QueueExecutor::default();
+        component.edit("a", 1).unwrap();
+        component.par_solve(&executor).unwrap();
+        component.edit("a", 2).unwrap();
+        component.par_solve(&executor).unwrap();
+        executor.run_all();
+
+        let mut settled = Vec::new();
+        while let Some(Some((name, variable, generation, event))) = events.next().now_or_never() {
+            assert_eq!(name, "A");
+            if variable == "b" && event.is_settled() {
+                settled.push((generation.current_generation(), event));
+            }
+        }
+        assert_eq!(
+            settled,
+            vec![
+                (0, OwnedEvent::Ready(OwnedReady::Changed(0))),
+                (2, OwnedEvent::Ready(OwnedReady::Changed(4)))
+            ]
+        );
+    }
+
+    #[test]
+    fn solve_handles_resolve_once_outputs_are_ready() {
+        let mut component: Component<i32> = sum();
+        let executor = QueueExecutor::default();
+        component.edit("a", 3).unwrap();
+        let mut handle = component.par_solve(&executor).unwrap();
+        assert!(!handle.is_finished());
+        assert_eq!((&mut handle).now_or_never(), None);
+        executor.run_all();
+        assert!(handle.is_finished());
+        assert_eq!(handle.now_or_never(), Some(Ok(())));
+        assert_eq!(
+            component.value("c").unwrap().now_or_never(),
+            Some(Ok(Arc::new(3)))
+        );
+    }
+
+    #[test]
+    fn removed_variables_do_not_receive_events_of_pending_methods() {
+        let mut component: Component<i32> = component! {
+            component Comp {
+                let a: i32 = 0, b: i32 = 0, c: i32 = 0;
+                constraint Split {
+                    split(a: &i32) -> [b, c] = ret![*a * 10, *a * 20];
+                }
+            }
+        };
+        let recorded = Recorded::default();
+        component.subscribe("c", record(&recorded, "c")).unwrap();
+        let executor = QueueExecutor::default();
+        component.edit("a", 1).unwrap();
+        component.par_solve(&executor).unwrap();
+
+        // `c` takes the index of `b` while `split` is pending
+        component.remove_variable_and_constraints("b").unwrap();
+        executor.run_all();
+        assert_eq!(*recorded.lock().unwrap(), vec![("c".to_owned(), 0)]);
+
+        // Later changes are still reported
+        component.edit("c", 5).unwrap();
+        component.solve().unwrap();
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![("c".to_owned(), 0), ("c".to_owned(), 5)]
+        );
+    }
+
+    #[test]
+    fn solve_handles_summarize_errors() {
+        let (cancelled, _wait_for_cancel) = mpsc::channel();
+        let mut component = never_finishes(cancelled);
+        component.set_method_timeout(Some(Duration::from_millis(20)));
+        component.edit("a", 1).unwrap();
+        let handle = component.par_solve(&ThreadExecutor).unwrap();
+        let errors = futures::executor::block_on(handle).unwrap_err();
+        let reasons: Vec<Reason> = errors.iter().map(|e| e.reason().clone()).collect();
+        assert_eq!(reasons, vec![Reason::Timeout]);
+    }
+
+    #[test]
+    fn cancelled_solve_handles_abort_methods() {
+        let mut component: Component<i32> = sum();
+        let executor = QueueExecutor::default();
+        component.edit("a", 3).unwrap();
+        let handle = component.par_solve(&executor).unwrap();
+        handle.cancel();
+        assert!(handle.is_finished());
+        let errors = handle.now_or_never().unwrap().unwrap_err();
+        assert!(!errors.is_empty());
+        assert!(errors.iter().all(|e| e.reason() == &Reason::Cancelled));
+
+        // The methods are skipped, and the old values are kept
+        executor.run_all();
+        let c = component.value("c").unwrap();
+        assert!(matches!(c.inner().read().unwrap().state(), State::Error(_)));
+        assert_eq!(c.now_or_never(), Some(Ok(Arc::new(0))));
+    }
+
+    /// The name of a span, and the id of its parent.
+    #[cfg(feature = "tracing")]
+    type RecordedSpan = (&'static str, Option<u64>);
+
+    /// Records the name and parent of each span, and which threads they were entered on.
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+        entered_on: Arc<Mutex<Vec<(u64, std::thread::ThreadId)>>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    thread_local! {
+        static ENTERED: std::cell::RefCell<Vec<u64>> = std::cell::RefCell::new(Vec::new());
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for SpanRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let parent = match span.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if span.is_contextual() => ENTERED.with(|e| e.borrow().last().copied()),
+                None => None,
+            };
+            let mut spans = self.spans.lock().unwrap();
+            spans.push((span.metadata().name(), parent));
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+        fn event(&self, _: &tracing::Event<'_>) {}
+        fn enter(&self, span: &tracing::span::Id) {
+            ENTERED.with(|e| e.borrow_mut().push(span.into_u64()));
+            let thread = std::thread::current().id();
+            self.entered_on
+                .lock()
+                .unwrap()
+                .push((span.into_u64(), thread));
+        }
+        fn exit(&self, _: &tracing::span::Id) {
+            ENTERED.with(|e| e.borrow_mut().pop());
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn method_spans_are_children_of_solve_spans_on_other_threads() {
+        let recorder = SpanRecorder::default();
+        let mut component: Component<i32> = sum();
+        component.edit("a", 3).unwrap();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let handle = component.par_solve(&ThreadExecutor).unwrap();
+            assert_eq!(futures::executor::block_on(handle), Ok(()));
+        });
+
+        let spans = recorder.spans.lock().unwrap().clone();
+        assert_eq!(spans[0], ("solve", None));
+        assert_eq!(spans[1], ("plan", Some(1)));
+        let methods: Vec<u64> = (1..=spans.len() as u64)
+            .filter(|&id| spans[id as usize - 1].0 == "method")
+            .collect();
+        assert!(!methods.is_empty());
+        for &id in &methods {
+            assert_eq!(spans[id as usize - 1].1, Some(1));
+        }
+
+        // The methods ran on threads of the executor
+        let main = std::thread::current().id();
+        let entered_on = recorder.entered_on.lock().unwrap();
+        for id in methods {
+            assert!(!entered_on.contains(&(id, main)));
+            assert!(entered_on.iter().any(|&(entered, _)| entered == id));
+        }
+    }
+
+    fn double() -> Component<i32> {
+        component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Double {
+                    double(a: &i32) -> [b] = ret![*a * 2];
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stats_count_method_calls_and_plans() {
+        let mut component = double();
+        assert_eq!(component.stats(), None);
+        component.enable_stats();
+        for a in 1..=3 {
+            component.edit("a", a).unwrap();
+            component.solve().unwrap();
+        }
+        let stats = component.stats().unwrap();
+        assert_eq!(stats.solves(), 3);
+        let double = stats.method("A", "Double", "double").unwrap();
+        assert_eq!(double.invocations(), 3);
+        assert_eq!(double.errors(), 0);
+        assert_eq!(double.cancellations(), 0);
+        assert_eq!(stats.to_table().rows().len(), stats.methods().count());
+
+        component.disable_stats();
+        assert_eq!(component.stats(), None);
+    }
+
+    #[test]
+    fn stats_only_count_plans_that_are_run() {
+        let mut component = double();
+        component.enable_stats();
+        component.enable_plan_cache(1);
+
+        // Inspecting a plan is not a solve, and takes no planning time
+        let plan = component.plan().unwrap();
+        let stats = component.stats().unwrap();
+        assert_eq!(stats.solves(), 0);
+        assert_eq!(stats.planning_time(), Duration::default());
+        component.execute(&plan, &DummyExecutor).unwrap();
+        let stats = component.stats().unwrap();
+        assert_eq!(stats.solves(), 1);
+        assert_eq!(stats.planning_time(), Duration::default());
+
+        // Neither does reusing a cached plan
+        component.solve().unwrap();
+        let stats = component.stats().unwrap();
+        assert_eq!(stats.solves(), 2);
+        assert_eq!(stats.planning_time(), Duration::default());
+    }
+
+    #[test]
+    fn stats_count_cancellations_and_errors() {
+        let mut component = double();
+        component.enable_stats();
+        let executor = QueueExecutor::default();
+        component.edit("a", 1).unwrap();
+        component.par_solve(&executor).unwrap().cancel();
+        executor.run_all();
+        let stats = component.stats().unwrap();
+        let double = stats.method("A", "Double", "double").unwrap();
+        assert_eq!(double.cancellations(), 1);
+        assert_eq!(double.invocations(), 0);
+
+        let (cancelled, _wait_for_cancel) = mpsc::channel();
+        let mut component = never_finishes(cancelled);
+        component.enable_stats();
+        component.set_method_timeout(Some(Duration::from_millis(20)));
+        component.edit("a", 1).unwrap();
+        let handle = component.par_solve(&ThreadExecutor).unwrap();
+        assert!(futures::executor::block_on(handle).is_err());
+        let stats = component.stats().unwrap();
+        let forever = stats.method("A", "Forever", "forever").unwrap();
+        assert_eq!(forever.errors(), 1);
+        assert_eq!(forever.cancellations(), 0);
+    }
+
+    #[test]
+    fn queries_follow_the_plan_without_solving() {
+        let mut component: Component<i32> = sum();
+        component.edit("a", 3).unwrap();
+        component.solve().unwrap();
+        let ranking = component.ranking();
+        assert_eq!(component.downstream_of("a"), Ok(vec!["c".to_owned()]));
+        assert_eq!(component.downstream_of("c"), Ok(vec![]));
+        assert_eq!(
+            component.upstream_of("c"),
+            Ok(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(component.upstream_of("a"), Ok(vec![]));
+
+        // Editing c would keep a, since it was edited more recently than b
+        assert_eq!(component.affected_by_edit("c"), Ok(vec!["b".to_owned()]));
+        assert_eq!(component.ranking(), ranking);
+        assert_eq!(component.value("b"), Ok(Activation::from(0)));
+        assert_eq!(
+            component.affected_by_edit("d"),
+            Err(QueryError::NoSuchVariable("d".to_owned()))
+        );
+    }
+
+    #[test]
+    fn plans_can_be_changed_before_they_are_executed() {
+        let mut component: Component<i32> = component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0, c: i32 = 0;
+                constraint AB { ab(a: &i32) -> [b] = ret![*a]; }
+                constraint BC { bc(b: &i32) -> [c] = ret![*b]; }
+            }
+        };
+        component.edit("a", 1).unwrap();
+        let mut plan = component.plan().unwrap();
+        assert_eq!(plan, component.plan().unwrap());
+        assert_eq!(component.value("b"), Ok(Activation::from(0)));
+
+        plan.disable("BC").unwrap();
+        assert!(plan.is_enabled("AB"));
+        assert!(!plan.is_enabled("BC"));
+        assert_eq!(plan.disable("AD"), Err(NoSuchConstraint("AD")));
+        assert_eq!(plan.written(), vec!["b"]);
+        assert_eq!(
+            plan.to_string(),
+            "AB.ab(a) -> [b]\nBC.bc(b) -> [c] (disabled)\n"
+        );
+        component.execute(&plan, &DummyExecutor).unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(1)));
+        assert_eq!(component.value("c"), Ok(Activation::from(0)));
+
+        // Plans can not be used after the variables change
+        component.add_variable("d", 0).unwrap();
+        assert_eq!(
+            component.execute(&plan, &DummyExecutor).err(),
+            Some(PlanMismatch("A".to_owned()))
+        );
+    }
+
+    #[test]
+    fn plans_can_not_be_executed_after_constraints_change() {
+        let mut component: Component<i32> = component! {
+            component A {
+                let a: i32 = 0, b: i32 = 0, c: i32 = 0;
+                constraint AB { ab(a: &i32) -> [b] = ret![*a]; }
+                constraint BC { bc(b: &i32) -> [c] = ret![*b]; }
+            }
+        };
+        let mismatch = Some(PlanMismatch("A".to_owned()));
+
+        let plan = component.plan().unwrap();
+        component.constraint_mut("BC").unwrap().set_active(false);
+        assert_eq!(component.execute(&plan, &DummyExecutor).err(), mismatch);
+
+        let plan = component.plan().unwrap();
+        component.pin("a").unwrap();
+        assert_eq!(component.execute(&plan, &DummyExecutor).err(), mismatch);
+
+        let plan = component.plan().unwrap();
+        component.remove_constraint("AB").unwrap();
+        assert_eq!(component.execute(&plan, &DummyExecutor).err(), mismatch);
+
+        // Looking up a missing constraint changes nothing
+        let plan = component.plan().unwrap();
+        assert!(component.constraint_mut("AB").is_err());
+        let index_mut = std::panic::AssertUnwindSafe(|| component["AB"].set_active(false));
+        assert!(std::panic::catch_unwind(index_mut).is_err());
+        assert!(component.execute(&plan, &DummyExecutor).is_ok());
+
+        // A new plan can be executed
+        let plan = component.plan().unwrap();
+        assert!(component.execute(&plan, &DummyExecutor).is_ok());
+    }
+
+    #[test]
+    fn cached_plans_are_reused_until_constraints_change() {
+        let mut component: Component<i32> = sum();
+        component.add_variable("unrelated", 0).unwrap();
+        component.enable_plan_cache(4);
+        for (variable, value) in [("a", 1), ("b", 2), ("a", 3), ("unrelated", 4), ("b", 5)] {
+            component.edit(variable, value).unwrap();
+            component.solve().unwrap();
+        }
+        assert_eq!(component.value("c"), Ok(Activation::from(8)));
+        let cache = component.plan_cache().unwrap();
+        // Only the first edits of a and b need new plans, since the ranking of other variables does not matter
+        assert_eq!((cache.hits(), cache.misses()), (3, 2));
+        assert_eq!(cache.len(), 2);
+
+        component.pin("a").unwrap();
+        assert!(component.plan_cache().unwrap().is_empty());
+        component.edit("c", 10).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(7)));
+        let cache = component.plan_cache().unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (3, 3));
+    }
+
+    #[test]
+    fn explanations_follow_the_last_solve() {
+        let mut component: Component<i32> = component! {
+            component Chain {
+                let a: i32 = 0, b: i32 = 0, c: i32 = 0;
+                constraint AB {
+                    ab(a: &i32) -> [b] = ret![*a];
+                    ba(b: &i32) -> [a] = ret![*b];
+                }
+                constraint BC {
+                    bc(b: &i32) -> [c] = ret![*b];
+                    cb(c: &i32) -> [b] = ret![*c];
+                }
+            }
+        };
+        component.edit("a", 1).unwrap();
+        component.solve().unwrap();
+        // Editing without solving does not change the explanation
+        component.edit("c", 2).unwrap();
+
+        let explanation = component.explain("c").unwrap();
+        assert_eq!(explanation.constraint(), Some("BC"));
+        assert_eq!(explanation.method(), Some("bc"));
+        assert_eq!(explanation.forced_by(), ["a"]);
+        assert_eq!(explanation.rejected().len(), 1);
+        assert_eq!(explanation.rejected()[0].method(), "cb");
+        assert!(explanation.rejected()[0].overwrites().is_empty());
+        assert_eq!(
+            explanation.to_string(),
+            "c was written by BC.bc to keep a\nBC.cb was rejected since it conflicts with other methods"
+        );
+
+        let explanation = component.explain("a").unwrap();
+        assert_eq!(explanation.method(), None);
+        assert_eq!(explanation.to_string(), "a was not written to");
+        assert_eq!(
+            component.explain("d"),
+            Err(QueryError::NoSuchVariable("d".to_owned()))
+        );
+    }
+
+    #[test]
+    fn explanations_use_the_planner_of_the_component() {
+        /// Plans like the hierarchical planner, but with the ranking reversed.
+        struct Reversed;
+        impl Plan<Component<i32>> for Reversed {
+            fn plan(
+                &self,
+                component: &Component<i32>,
+            ) -> Result<OwnedPlan<Method<i32>>, PlanError> {
+                let mut ranking = component.ranking();
+                ranking.reverse();
+                let mut reversed = component.clone();
+                reversed.set_ranking(&ranking);
+                hierarchical_planner(&reversed)
+            }
+        }
+
+        let mut component: Component<i32> = component! {
+            component Chain {
+                let a: i32 = 0, b: i32 = 0;
+                constraint AB {
+                    ab(a: &i32) -> [b] = ret![*a];
+                    ba(b: &i32) -> [a] = ret![*b];
+                }
+            }
+        };
+        component.set_planner(Reversed);
+        component.edit("a", 1).unwrap();
+        component.solve().unwrap();
+        // The most recent edit is overwritten, which the explanation agrees with
+        assert_eq!(component.value("a"), Ok(Activation::from(0)));
+        assert_eq!(component.explain("a").unwrap().method(), Some("ba"));
+        assert_eq!(component.explain("b").unwrap().method(), None);
+    }
+
+    #[test]
+    fn priority_levels_override_edits() {
+        let mut component: Component<i32> = sum();
+        component.set_priority("c", 1).unwrap();
+        assert_eq!(component.priority("a"), Ok(0));
+        assert_eq!(component.set_priority("d", 1), Err(NoSuchVariable("d")));
+
+        // With fixed priorities, c is kept and a comes before b
+        component.set_ranking_strategy(RankingStrategy::Fixed);
+        component.edit("b", 2).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.ranking_names(), ["c", "a", "b"]);
+        assert_eq!(component.value("b"), Ok(Activation::from(0)));
+
+        // With the hybrid strategy, c is kept and the most recent edit comes next
+        component.set_ranking_strategy(RankingStrategy::Hybrid);
+        component.edit("b", 2).unwrap();
+        component.solve().unwrap();
+        assert_eq!(component.ranking_names(), ["c", "b", "a"]);
+        assert_eq!(component.value("a"), Ok(Activation::from(-2)));
+        assert_eq!(component.affected_by_edit("a"), Ok(vec!["b".to_owned()]));
+
+        component.set_ranking_strategy(RankingStrategy::Recency);
+        component.set_ranking_by_name(&["b", "a"]).unwrap();
+        assert_eq!(component.ranking_names(), ["b", "a", "c"]);
+        assert_eq!(component.affected_by_edit("a"), Ok(vec!["c".to_owned()]));
+    }
+
+    #[test]
+    fn custom_ranking_strategies_are_used() {
+        /// Gives the least recently edited variables the highest priority.
+        struct LeastRecent;
+
+        impl Rank for LeastRecent {
+            fn rank(&self, mut recency: Vec<usize>, _: &[i32]) -> Vec<usize> {
+                recency.reverse();
+                recency
+            }
+        }
+
+        let mut component: Component<i32> = sum();
+        component.set_ranking_strategy(LeastRecent);
+        component.edit("a", 1).unwrap();
+        component.edit("b", 2).unwrap();
+        assert_eq!(component.ranking_names(), ["c", "a", "b"]);
+
+        // The most recent edit is overwritten
+        component.solve().unwrap();
+        assert_eq!(component.value("b"), Ok(Activation::from(-1)));
+    }
 }
@@ -0,0 +1,139 @@
+//! A plan for solving a [`Component`](super::Component) that can be inspected and changed before it is executed.
+
+use super::{errors::NoSuchConstraint, method::Method};
+use crate::planner::{MethodSpec, OwnedEnforcedConstraint, OwnedPlan, Vertex};
+use derivative::Derivative;
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display},
+};
+
+/// A plan made by [`Component::plan`](super::Component::plan), to be run with
+/// [`Component::execute`](super::Component::execute).
+///
+/// It contains the method selected to enforce each constraint, in the order they are run.
+/// Methods can be disabled, so that executing the plan leaves their outputs unchanged.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""), PartialEq(bound = ""))]
+pub struct ComponentPlan<T> {
+    component: String,
+    /// The revision of the component the plan was made for.
+    revision: usize,
+    variable_names: Vec<String>,
+    enforced: OwnedPlan<Method<T>>,
+    /// The names of the constraints whose methods are disabled.
+    disabled: BTreeSet<String>,
+}
+
+impl<T> ComponentPlan<T> {
+    /// Constructs a new [`ComponentPlan`] for the specified revision of the component with the specified variables, by index.
+    pub(crate) fn new(
+        component: String,
+        revision: usize,
+        variable_names: Vec<String>,
+        enforced: OwnedPlan<Method<T>>,
+    ) -> Self {
+        Self {
+            component,
+            revision,
+            variable_names,
+            enforced,
+            disabled: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the name of the component the plan was made for.
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    /// Returns the revision of the component the plan was made for.
+    pub(crate) fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Returns the names of the variables of the component when the plan was made, by index.
+    pub fn variable_names(&self) -> &[String] {
+        &self.variable_names
+    }
+
+    /// Returns the enforced constraints in the order their methods are run, including disabled ones.
+    pub fn enforced(&self) -> &[OwnedEnforcedConstraint<Method<T>>] {
+        &self.enforced
+    }
+
+    /// Returns the method selected to enforce the specified constraint,
+    /// or `None` if the constraint is not enforced.
+    pub fn method(&self, constraint: &str) -> Option<&Method<T>> {
+        self.enforced
+            .iter()
+            .find(|ec| ec.name() == constraint)
+            .map(OwnedEnforcedConstraint::method)
+    }
+
+    /// Returns the names of the variables that are written to by the enabled methods, in the order they are run.
+    pub fn written(&self) -> Vec<&str> {
+        self.enabled()
+            .flat_map(|ec| ec.method().outputs())
+            .map(|&vi| self.variable_names[vi].as_str())
+            .collect()
+    }
+
+    /// Disables the method that enforces the specified constraint,
+    /// so that its outputs are left unchanged when the plan is executed.
+    pub fn disable<'a>(&mut self, constraint: &'a str) -> Result<(), NoSuchConstraint<'a>> {
+        self.method(constraint)
+            .ok_or(NoSuchConstraint(constraint))?;
+        self.disabled.insert(constraint.to_owned());
+        Ok(())
+    }
+
+    /// Enables the method that enforces the specified constraint again.
+    pub fn enable<'a>(&mut self, constraint: &'a str) -> Result<(), NoSuchConstraint<'a>> {
+        self.method(constraint)
+            .ok_or(NoSuchConstraint(constraint))?;
+        self.disabled.remove(constraint);
+        Ok(())
+    }
+
+    /// Returns true if the method that enforces the specified constraint is run when the plan is executed.
+    pub fn is_enabled(&self, constraint: &str) -> bool {
+        self.method(constraint).is_some() && !self.disabled.contains(constraint)
+    }
+
+    /// Returns the enforced constraints whose methods are run when the plan is executed.
+    pub(crate) fn enabled(&self) -> impl Iterator<Item = &OwnedEnforcedConstraint<Method<T>>> {
+        self.enforced
+            .iter()
+            .filter(move |ec| !self.disabled.contains(ec.name()))
+    }
+}
+
+/// Shows one method per line, such as `Sum.abc(a, b) -> [c]`.
+impl<T> Display for ComponentPlan<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |indices: &[usize]| {
+            indices
+                .iter()
+                .map(|&vi| self.variable_names[vi].as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        for ec in &self.enforced {
+            let method = ec.method();
+            write!(
+                f,
+                "{}.{}({}) -> [{}]",
+                ec.name(),
+                method.name().unwrap_or("stay"),
+                names(method.inputs()),
+                names(method.outputs())
+            )?;
+            if self.disabled.contains(ec.name()) {
+                write!(f, " (disabled)")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -3,25 +3,31 @@
 //! A [`Constraint`] contains a number of [`Method`](super::Method)s that read from and write to different variables,
 //! and can be executed in order to enforce the constraint.
 
-use super::method::Method;
+use super::method::{Conditions, Method};
 use crate::{
-    macros::raw_constraint::Assert,
-    planner::{ConstraintSpec, MethodSpec, Vertex},
+    macros::raw_constraint::{Assert, Condition},
+    planner::{ConstraintSpec, MethodSpec, Strength, Vertex},
 };
-use std::{collections::HashSet, ops::Index};
+use std::{collections::HashSet, ops::Index, sync::Arc};
 
 /// Represents a constraint in a multiway dataflow constraint system.
 /// It has a name, a set of variables it references, a set of [`Method`]s to enforce it,
-/// and an optional assertion to run to ensure that it is actually enforced upon running a method.
+/// an optional assertion that documents it,
+/// and optional conditions to check before and after running a method to enforce it.
 #[derive(derivative::Derivative)]
-#[derivative(Clone(bound = ""), Debug)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
 pub struct Constraint<T> {
     name: String,
     variables: Vec<usize>,
     methods: Vec<Method<T>>,
     #[derivative(Debug = "ignore")]
     assert: Option<Assert<T>>,
+    #[derivative(Debug = "ignore")]
+    precondition: Option<Condition<T>>,
+    #[derivative(Debug = "ignore")]
+    postcondition: Option<Condition<T>>,
     active: bool,
+    strength: Strength,
 }
 
 impl<T> ConstraintSpec for Constraint<T> {
@@ -37,7 +43,10 @@ impl<T> ConstraintSpec for Constraint<T> {
             variables: vec![index],
             methods: vec![Method::stay(index)],
             assert: None,
+            precondition: None,
+            postcondition: None,
             active: true,
+            strength: Strength::Required,
         }
     }
 
@@ -55,9 +64,8 @@ impl<T> ConstraintSpec for Constraint<T> {
 
     fn add_method(&mut self, m: Method<T>) {
         self.methods.push(m);
-        let mut set = HashSet::with_capacity(self.variables.len());
-        Self::fill_variables(&mut set, &self.methods);
-        self.variables = set.into_iter().collect();
+        self.variables = Self::collect_variables(&self.methods);
+        self.attach_conditions();
     }
 
     /// Remove a method from the constraint system.
@@ -76,9 +84,8 @@ impl<T> ConstraintSpec for Constraint<T> {
             _ => panic!("Ambiguous method name {}", name),
         }
         // TODO: Update instead of clearing and refilling somehow?
-        let mut set = HashSet::with_capacity(self.variables.len());
-        Self::fill_variables(&mut set, &self.methods);
-        self.variables = set.into_iter().collect();
+        self.variables = Self::collect_variables(&self.methods);
+        self.attach_conditions();
     }
 
     fn variables(&self) -> &[usize] {
@@ -88,17 +95,38 @@ impl<T> ConstraintSpec for Constraint<T> {
     fn is_active(&self) -> bool {
         self.active
     }
+
+    fn strength(&self) -> Strength {
+        self.strength
+    }
 }
 
 impl<T> Constraint<T> {
-    fn fill_variables(variables: &mut HashSet<usize>, methods: &[Method<T>]) {
-        for m in methods {
-            for i in m.inputs() {
-                variables.insert(*i);
-            }
-            for o in m.outputs() {
-                variables.insert(*o);
-            }
+    /// Returns the variables referenced by the methods, in the order they are first referenced.
+    fn collect_variables(methods: &[Method<T>]) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        methods
+            .iter()
+            .flat_map(|m| m.inputs().iter().chain(m.outputs()))
+            .copied()
+            .filter(|&vi| seen.insert(vi))
+            .collect()
+    }
+
+    /// Gives each method the conditions of the constraint,
+    /// so that they can be checked when the method is run.
+    fn attach_conditions(&mut self) {
+        let conditions = if self.precondition.is_some() || self.postcondition.is_some() {
+            Some(Arc::new(Conditions::new(
+                self.precondition.clone(),
+                self.postcondition.clone(),
+                self.variables.clone(),
+            )))
+        } else {
+            None
+        };
+        for m in &mut self.methods {
+            m.set_conditions(conditions.clone());
         }
     }
 
@@ -112,21 +140,41 @@ impl<T> Constraint<T> {
         Self::new_with_name_and_assert(name, methods, None)
     }
 
-    /// Constructs a new [`Component`](super::Component) with the specified name and assertion.
+    /// Constructs a new [`Constraint`] with the specified name and assertion.
+    /// The assertion is not checked when solving, unlike the conditions of
+    /// [`new_with_name_and_conditions`](Self::new_with_name_and_conditions).
     pub fn new_with_name_and_assert(
         name: String,
         methods: Vec<Method<T>>,
         assert: Option<Assert<T>>,
     ) -> Self {
-        let mut variables: HashSet<usize> = HashSet::new();
-        Self::fill_variables(&mut variables, &methods);
-        Self {
+        let mut constraint = Self::new_with_name_and_conditions(name, methods, None, None);
+        constraint.assert = assert;
+        constraint
+    }
+
+    /// Constructs a new [`Constraint`] with the specified name, precondition and postcondition.
+    ///
+    /// The precondition must hold before a method of the constraint is run,
+    /// and the postcondition must hold after it.
+    pub fn new_with_name_and_conditions(
+        name: String,
+        methods: Vec<Method<T>>,
+        precondition: Option<Condition<T>>,
+        postcondition: Option<Condition<T>>,
+    ) -> Self {
+        let mut constraint = Self {
             name,
-            variables: variables.into_iter().collect(),
+            variables: Self::collect_variables(&methods),
             methods,
-            assert,
+            assert: None,
+            precondition,
+            postcondition,
             active: true,
-        }
+            strength: Strength::Required,
+        };
+        constraint.attach_conditions();
+        constraint
     }
 
     /// Enables or disables the constraint.
@@ -135,11 +183,38 @@ impl<T> Constraint<T> {
     pub fn set_active(&mut self, active: bool) {
         self.active = active;
     }
+
+    /// Sets the assertion of the constraint, which is not checked when solving.
+    pub(crate) fn set_assert(&mut self, assert: Option<Assert<T>>) {
+        self.assert = assert;
+    }
+
+    /// Sets the strength of the constraint.
+    ///
+    /// If constraints conflict, the weakest ones are left unenforced.
+    pub fn set_strength(&mut self, strength: Strength) {
+        self.strength = strength;
+    }
+
+    /// Returns a copy of the constraint with a new name,
+    /// where each variable index `i` is replaced by `f(i)`.
+    pub(crate) fn reindexed(&self, name: String, f: impl Fn(usize) -> usize) -> Self {
+        Self {
+            name,
+            variables: self.variables.iter().map(|&vi| f(vi)).collect(),
+            methods: self.methods.iter().map(|m| m.reindexed(&f)).collect(),
+            assert: self.assert.clone(),
+            precondition: self.precondition.clone(),
+            postcondition: self.postcondition.clone(),
+            active: self.active,
+            strength: self.strength,
+        }
+    }
 }
 
 impl<T> PartialEq for Constraint<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.methods == other.methods
+        self.name == other.name && self.methods == other.methods && self.strength == other.strength
     }
 }
 
@@ -5,32 +5,185 @@
 use super::{
     activation::Activation,
     component::Component,
-    errors::{NoSuchComponent, NoSuchItem},
+    constraint::Constraint,
+    errors::{
+        NoSuchComponent, NoSuchConstraint, NoSuchItem, NoSuchQualifiedVariable, QueryError,
+        TransactionError,
+    },
+    event_stream::EventStream,
+    generation_id::GenerationId,
+    inter_constraint::InterConstraint,
+    method::Method,
+    solve_handle::SolveHandle,
+    subscription::{Subscription, SubscriptionId, SystemEventCallback},
+    transaction::{last_edits, SystemTransaction},
     undo::{NoMoreRedo, NoMoreUndo, UndoLimit},
     variable::Variable,
+    variables::Variables,
 };
 use crate::{
-    event::Event,
+    event::{Event, EventWithLocation},
     executor::{DummyExecutor, MethodExecutor},
-    planner::PlanError,
-    solver::SolveError,
+    macros::RawConstraint,
+    planner::{
+        dependencies::{self, Follow},
+        priority_adjuster::adjust_priorities,
+        ComponentSpec, ConstraintSpec, OwnedPlan, Plan, PlanError, Strength, Vertex,
+    },
+    solver::{self, SolveError},
+    stats::{Instant, SharedStats, Stats},
+    variable_ranking::{SortRanker, VariableRanker},
 };
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Subscribes a callback to every variable of a component.
+type SubscribeTo<T> = Arc<dyn Fn(&mut Component<T>) -> Subscription + Send + Sync>;
+
+/// Gives a component its own copy of a planner.
+type SetPlanner<T> = Arc<dyn Fn(&mut Component<T>) + Send + Sync>;
+
+/// A callback subscribed to every variable of a [`ConstraintSystem`],
+/// along with its subscriptions to each component by name.
+struct SystemCallback<T> {
+    id: SubscriptionId,
+    subscribe_to: SubscribeTo<T>,
+    subscriptions: Vec<(String, Subscription)>,
+}
+
+/// The callbacks subscribed to every variable of a [`ConstraintSystem`].
+/// Clones share the same callbacks.
+struct SystemCallbacks<T>(Arc<Mutex<Vec<SystemCallback<T>>>>);
+
+impl<T> Clone for SystemCallbacks<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Default for SystemCallbacks<T> {
+    fn default() -> Self {
+        Self(Arc::default())
+    }
+}
+
+impl<T> Debug for SystemCallbacks<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ids: Vec<SubscriptionId> = self.0.lock().unwrap().iter().map(|sc| sc.id).collect();
+        f.debug_tuple("SystemCallbacks").field(&ids).finish()
+    }
+}
+
+/// Callbacks are not part of the state of a constraint system.
+impl<T> PartialEq for SystemCallbacks<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// Statistics shared with every component, if they are collected.
+#[derive(Clone, Debug, Default)]
+struct SystemStats(Option<SharedStats>);
+
+/// Statistics are not part of the state of a constraint system.
+impl PartialEq for SystemStats {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// The planner set with [`ConstraintSystem::set_planner`], which new components are given as well.
+struct SystemPlanner<T>(Option<SetPlanner<T>>);
+
+impl<T> Clone for SystemPlanner<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Default for SystemPlanner<T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<T> Debug for SystemPlanner<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SystemPlanner")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+/// The planner is not part of the state of a constraint system.
+impl<T> PartialEq for SystemPlanner<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// Linked components merged into one, see [`ConstraintSystem::merge_group`].
+struct MergedGroup<T> {
+    /// The merged component, where variables are named `component.variable`
+    /// and the constraints of each component are named `component.constraint`.
+    component: Component<T>,
+    /// The index of the component of each variable, and its index in that component.
+    owners: Vec<(usize, usize)>,
+    /// The name of each variable in its component.
+    variable_names: Vec<String>,
+    /// The component and name of each constraint, by its name in the merged component.
+    locations: HashMap<String, (String, String)>,
+    /// The method timeouts of the components, by the name of the constraint in the merged component.
+    timeouts: HashMap<String, Duration>,
+    /// The variables by priority, where the first one has the highest priority.
+    ranking: Vec<usize>,
+}
 
 /// A container for [`Component`]s.
+///
+/// Constraints between variables in different components can be added with
+/// [`add_constraint`](Self::add_constraint).
+/// Components that are connected by such constraints are planned and solved as one graph.
 #[derive(Clone, Debug, PartialEq)]
 pub struct ConstraintSystem<T> {
     components: HashMap<String, Component<T>>,
-    undo_stack: Vec<String>,
-    redo_stack: Vec<String>,
+    /// Constraints between variables in different components.
+    constraints: Vec<InterConstraint<T>>,
+    /// The location and name of the constraints between components that were left unenforced in the last solve.
+    unenforced: Vec<(String, String)>,
+    /// When each variable of linked components was last edited or solved, by component and variable name.
+    priorities: HashMap<(String, String), usize>,
+    clock: usize,
+    /// The names of the components changed by each step.
+    undo_stack: Vec<Vec<String>>,
+    redo_stack: Vec<Vec<String>>,
+    /// Whether changes are still being added to the last step of the undo stack.
+    step_open: bool,
+    /// Callbacks subscribed to every variable, which new components are subscribed to as well.
+    callbacks: SystemCallbacks<T>,
+    stats: SystemStats,
+    planner: SystemPlanner<T>,
 }
 
 impl<T> Default for ConstraintSystem<T> {
     fn default() -> Self {
         Self {
             components: HashMap::new(),
+            constraints: Vec::new(),
+            unenforced: Vec::new(),
+            priorities: HashMap::new(),
+            clock: 0,
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            step_open: false,
+            callbacks: SystemCallbacks::default(),
+            stats: SystemStats::default(),
+            planner: SystemPlanner::default(),
         }
     }
 }
@@ -42,16 +195,158 @@ impl<T> ConstraintSystem<T> {
     }
 
     /// Adds a component to the constraint system.
-    pub fn add_component(&mut self, component: Component<T>) {
-        self.components
-            .insert(component.name().to_owned(), component);
+    ///
+    /// Callbacks added with [`subscribe_all`](Self::subscribe_all) are subscribed to its variables,
+    /// and it is given the planner set with [`set_planner`](Self::set_planner), if any.
+    pub fn add_component(&mut self, mut component: Component<T>) {
+        let name = component.name().to_owned();
+        for sc in self.callbacks.0.lock().unwrap().iter_mut() {
+            // Replace subscriptions to a component with the same name
+            sc.subscriptions.retain(|(component, _)| component != &name);
+            let subscription = (sc.subscribe_to)(&mut component);
+            sc.subscriptions.push((name.clone(), subscription));
+        }
+        if let Some(stats) = &self.stats.0 {
+            component.set_shared_stats(Some(stats.clone()));
+        }
+        if let Some(set_planner) = &self.planner.0 {
+            set_planner(&mut component);
+        }
+        self.components.insert(name, component);
     }
 
-    /// Removes a component from the constraint system.
+    /// Removes a component from the constraint system,
+    /// along with the constraints between it and other components.
+    ///
+    /// Callbacks added with [`subscribe_all`](Self::subscribe_all) are unsubscribed from it.
     pub fn remove_component(&mut self, name: &str) -> Option<Component<T>> {
+        let links: Vec<InterConstraint<T>> = Self::links_of(&self.constraints, &[name.to_owned()])
+            .cloned()
+            .collect();
+        self.constraints
+            .retain(|c| !c.components().iter().any(|component| component == name));
+        for link in &links {
+            self.mark_modified(link);
+        }
+        for sc in self.callbacks.0.lock().unwrap().iter_mut() {
+            sc.subscriptions.retain(|(component, _)| component != name);
+        }
+        self.priorities
+            .retain(|(component, _), _| component != name);
         self.components.remove(name)
     }
 
+    /// Starts collecting statistics about the calls to each method and the time spent planning,
+    /// for every component in the system.
+    ///
+    /// The statistics of the components are combined, and ones collected earlier by the components are discarded.
+    pub fn enable_stats(&mut self) {
+        if self.stats.0.is_none() {
+            let stats = SharedStats::default();
+            for component in self.components.values_mut() {
+                component.set_shared_stats(Some(stats.clone()));
+            }
+            self.stats.0 = Some(stats);
+        }
+    }
+
+    /// Stops collecting statistics, and discards the ones collected so far.
+    pub fn disable_stats(&mut self) {
+        for component in self.components.values_mut() {
+            component.set_shared_stats(None);
+        }
+        self.stats.0 = None;
+    }
+
+    /// Returns the statistics collected so far, or `None` if they are not being collected.
+    pub fn stats(&self) -> Option<Stats> {
+        self.stats
+            .0
+            .as_ref()
+            .map(|stats| stats.lock().unwrap().clone())
+    }
+
+    /// Adds a constraint between variables in different components.
+    ///
+    /// Variables are named as `component.variable`, and the components must already be in the system.
+    /// Components that are connected by constraints are planned and solved as one graph,
+    /// using the planner of the first of them by name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{component, ret, macros::{RawConstraint, RawMethod}, model::{Activation, ConstraintSystem}};
+    /// # use std::sync::Arc;
+    /// let mut cs: ConstraintSystem<i32> = ConstraintSystem::new();
+    /// cs.add_component(component! { component Parent { let width: i32 = 0; } });
+    /// cs.add_component(component! { component Child { let width: i32 = 0; } });
+    /// cs.add_constraint(RawConstraint::new(
+    ///     "SameWidth",
+    ///     vec![
+    ///         RawMethod::new("down", vec!["Parent.width"], vec!["Child.width"], Arc::new(Ok)),
+    ///         RawMethod::new("up", vec!["Child.width"], vec!["Parent.width"], Arc::new(Ok)),
+    ///     ],
+    /// ))
+    /// .unwrap();
+    ///
+    /// cs.edit("Parent", "width", 3).unwrap();
+    /// cs.solve().unwrap();
+    /// assert_eq!(cs.value("Child", "width"), Ok(Activation::from(3)));
+    /// ```
+    pub fn add_constraint(
+        &mut self,
+        constraint: RawConstraint<T>,
+    ) -> Result<(), NoSuchQualifiedVariable>
+    where
+        T: Clone,
+    {
+        let mut name_to_index = HashMap::new();
+        let mut variables = Vec::new();
+        for name in constraint.variables() {
+            if name_to_index.contains_key(name) {
+                continue;
+            }
+            let variable = name
+                .split_once('.')
+                .filter(|(component, variable)| {
+                    self.components
+                        .get(*component)
+                        .map_or(false, |c| c.variable_index(variable).is_ok())
+                })
+                .ok_or_else(|| NoSuchQualifiedVariable(name.to_owned()))?;
+            name_to_index.insert(name.to_owned(), variables.len());
+            variables.push((variable.0.to_owned(), variable.1.to_owned()));
+        }
+        log::trace!("Adding constraint {} between components", constraint.name());
+        let constraint = constraint.into_constraint(&name_to_index);
+        let constraint = InterConstraint::new(constraint, variables);
+        self.mark_modified(&constraint);
+        self.constraints.push(constraint);
+        Ok(())
+    }
+
+    /// Removes a constraint between components that was added with [`add_constraint`](Self::add_constraint).
+    pub fn remove_constraint<'a>(&mut self, name: &'a str) -> Result<(), NoSuchConstraint<'a>> {
+        let position = self
+            .constraints
+            .iter()
+            .position(|c| c.name() == name)
+            .ok_or(NoSuchConstraint(name))?;
+        let constraint = self.constraints.remove(position);
+        self.mark_modified(&constraint);
+        Ok(())
+    }
+
+    /// Marks the components of a constraint between components as modified,
+    /// so that they are solved again after it was added or removed.
+    fn mark_modified(&mut self, constraint: &InterConstraint<T>) {
+        for name in constraint.components() {
+            if let Some(component) = self.components.get_mut(name) {
+                component.set_modified(true);
+            }
+        }
+    }
+
     /// Get a reference to the selected component.
     pub fn component<'s>(&self, name: &'s str) -> Result<&Component<T>, NoSuchComponent<'s>> {
         self.components.get(name).ok_or(NoSuchComponent(name))
@@ -70,6 +365,23 @@ impl<T> ConstraintSystem<T> {
         self.components.values()
     }
 
+    /// Sets the planner to use when solving each component, including ones added later.
+    ///
+    /// Each component gets its own clone of the planner,
+    /// which can be replaced with [`Component::set_planner`].
+    /// Components connected by constraints are planned with the planner of the first of them by name.
+    pub fn set_planner<P>(&mut self, planner: P)
+    where
+        P: Plan<Component<T>> + Clone + Send + Sync + 'static,
+    {
+        let set_planner: SetPlanner<T> =
+            Arc::new(move |component| component.set_planner(planner.clone()));
+        for component in self.components.values_mut() {
+            set_planner(component);
+        }
+        self.planner.0 = Some(set_planner);
+    }
+
     /// Edits the specified variable's value.
     pub fn edit<'s>(
         &mut self,
@@ -77,13 +389,95 @@ impl<T> ConstraintSystem<T> {
         variable: &'s str,
         value: impl Into<T>,
     ) -> Result<(), NoSuchItem<'s>> {
-        self.undo_stack.push(component.to_string());
-        self.redo_stack.clear();
-        let component = self.component_mut(component)?;
-        component.edit(variable, value)?;
+        self.component_mut(component)?.edit(variable, value)?;
+        self.clock += 1;
+        self.priorities
+            .insert((component.to_owned(), variable.to_owned()), self.clock);
+        self.record_change(component);
         Ok(())
     }
 
+    /// Edits variables in multiple components as one change.
+    ///
+    /// The edits are only applied if all of them succeed, and they are undone together in a single step.
+    /// If [`SystemTransaction::solve`] is called, the constraint system is solved afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hotdrink_rs::{component, ret, model::{Activation, ConstraintSystem}};
+    /// let mut cs = ConstraintSystem::new();
+    /// cs.add_component(component! {
+    ///     component Point {
+    ///         let x: i32 = 0, y: i32 = 0;
+    ///     }
+    /// });
+    /// cs.transaction(|tx| {
+    ///     tx.edit("Point", "x", 3);
+    ///     tx.edit("Point", "y", 4);
+    ///     tx.solve();
+    /// })
+    /// .unwrap();
+    /// cs.undo().unwrap();
+    /// assert_eq!(cs.value("Point", "x"), Ok(Activation::from(0)));
+    /// assert_eq!(cs.value("Point", "y"), Ok(Activation::from(0)));
+    /// ```
+    pub fn transaction(
+        &mut self,
+        f: impl FnOnce(&mut SystemTransaction<T>),
+    ) -> Result<(), TransactionError>
+    where
+        T: Send + Sync + 'static + Debug,
+    {
+        let mut transaction = SystemTransaction::new();
+        f(&mut transaction);
+        let (edits, solve) = transaction.finish()?;
+
+        // Find all variables before applying anything
+        let edits = edits
+            .into_iter()
+            .map(|(component, variable, value)| {
+                let index = self
+                    .components
+                    .get(&component)
+                    .ok_or_else(|| TransactionError::NoSuchComponent(component.clone()))?
+                    .variable_index(&variable)
+                    .map_err(|_| {
+                        TransactionError::NoSuchVariable(format!("{}.{}", component, variable))
+                    })?;
+                Ok(((component, variable), (index, value)))
+            })
+            .collect::<Result<Vec<_>, TransactionError>>()?;
+        for ((component, variable), (index, value)) in last_edits(edits) {
+            self.components
+                .get_mut(&component)
+                .expect("component was found above")
+                .edit_at(index, value);
+            self.clock += 1;
+            self.priorities
+                .insert((component.clone(), variable), self.clock);
+            self.record_change(&component);
+        }
+
+        if solve {
+            self.solve()?;
+        }
+        Ok(())
+    }
+
+    /// Adds a component to the current undo step, starting a new step if needed.
+    fn record_change(&mut self, component: &str) {
+        if !self.step_open {
+            self.undo_stack.push(Vec::new());
+            self.step_open = true;
+        }
+        self.redo_stack.clear();
+        let step = self.undo_stack.last_mut().expect("a step was just opened");
+        if !step.iter().any(|c| c == component) {
+            step.push(component.to_owned());
+        }
+    }
+
     /// Returns the current value of the variable with name `variable` in `component`, if one exists.
     pub fn variable<'a>(
         &self,
@@ -114,37 +508,391 @@ impl<T> ConstraintSystem<T> {
         T: Send + Sync + 'static + Debug,
     {
         log::trace!("update");
-        self.par_solve(&DummyExecutor)
+        self.par_solve(&DummyExecutor).map(drop)
     }
 
     /// Attempts to enforces all constraints in every component that is modified.
     /// If no plan could be found, it will return a [`PlanError`].
-    /// This variant lets you specify a thread pool to run methods on.
-    pub fn par_solve(&mut self, spawn: &impl MethodExecutor) -> Result<(), PlanError>
+    /// This variant lets you specify a thread pool to run methods on,
+    /// and returns a [`SolveHandle`] to wait for or cancel the methods of every component.
+    pub fn par_solve(&mut self, spawn: &impl MethodExecutor) -> Result<SolveHandle<T>, PlanError>
     where
         T: Send + Sync + 'static + Debug,
     {
         log::trace!("par_update");
-        for component in self.components.values_mut() {
-            if component.is_modified() {
-                component.par_solve(spawn)?;
+        self.solve_components(spawn, false)
+    }
+
+    /// Attempts to enforces all constraints in every component, even if they have not been modified.
+    /// If no plan could be found, it will return a [`PlanError`].
+    pub fn par_update_always(
+        &mut self,
+        spawn: &mut impl MethodExecutor,
+    ) -> Result<SolveHandle<T>, PlanError>
+    where
+        T: Send + Sync + 'static + Debug,
+    {
+        self.solve_components(spawn, true)
+    }
+
+    /// Solves the components that are not connected to others one by one,
+    /// and the connected ones together.
+    /// Everything is planned before any methods are run, so that nothing changes if planning fails.
+    /// The changed components are recorded as one step that can be undone.
+    fn solve_components(
+        &mut self,
+        spawn: &impl MethodExecutor,
+        always: bool,
+    ) -> Result<SolveHandle<T>, PlanError>
+    where
+        T: Send + Sync + 'static + Debug,
+    {
+        let groups = self.linked_groups();
+        let linked: HashSet<&String> = groups.iter().flatten().collect();
+        let is_modified = |name: &String| always || self.components[name].is_modified();
+
+        let mut plans = Vec::new();
+        for name in self.components.keys() {
+            if !linked.contains(name) && is_modified(name) {
+                plans.push((name.clone(), self.components[name].plan_to_solve()?));
+            }
+        }
+        let mut group_plans = Vec::new();
+        for group in &groups {
+            if group.iter().any(is_modified) {
+                let merged = self.merge_group(group);
+                let planning_started = Instant::now();
+                let plan = self.plan_group(group, &merged.component, false);
+                if let Some(stats) = &self.stats.0 {
+                    stats
+                        .lock()
+                        .unwrap()
+                        .record_planning(planning_started.elapsed());
+                }
+                group_plans.push((group.clone(), merged, plan?));
             }
         }
 
-        Ok(())
+        let mut outputs = Vec::new();
+        for (name, plan) in plans {
+            let component = self.components.get_mut(&name).expect("name is a key");
+            outputs.extend(component.execute_uncommitted(&plan, spawn));
+            if component.has_uncommitted_changes() {
+                component.commit();
+                self.record_change(&name);
+            }
+        }
+        for (group, merged, plan) in group_plans {
+            outputs.extend(self.solve_group(&group, merged, plan, spawn));
+        }
+
+        // Later edits belong to a new step
+        self.step_open = false;
+        Ok(SolveHandle::new(outputs))
     }
 
-    /// Attempts to enforces all constraints in every component, even if they have not been modified.
-    /// If no plan could be found, it will return a [`PlanError`].
-    pub fn par_update_always(&mut self, spawn: &mut impl MethodExecutor) -> Result<(), PlanError>
+    /// Returns the names of the components that are connected by constraints between components,
+    /// grouped by which ones are connected. Each group is sorted by name.
+    fn linked_groups(&self) -> Vec<Vec<String>> {
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        for constraint in &self.constraints {
+            let mut group = constraint.components().to_vec();
+            // Merge the groups that share a component with the constraint
+            let mut i = 0;
+            while i < groups.len() {
+                if groups[i].iter().any(|c| group.contains(c)) {
+                    group.extend(groups.swap_remove(i));
+                } else {
+                    i += 1;
+                }
+            }
+            group.sort();
+            group.dedup();
+            groups.push(group);
+        }
+        groups.retain(|group| !group.is_empty());
+        groups.sort();
+        groups
+    }
+
+    /// Returns the constraints between components that involve any of the specified components.
+    fn links_of<'a>(
+        constraints: &'a [InterConstraint<T>],
+        names: &'a [String],
+    ) -> impl Iterator<Item = &'a InterConstraint<T>> {
+        constraints
+            .iter()
+            .filter(move |c| c.components().iter().any(|n| names.contains(n)))
+    }
+
+    /// Merges the specified components, and the constraints between them, into one component.
+    fn merge_group(&self, names: &[String]) -> MergedGroup<T> {
+        // Give the variables of each component indices after those of the previous ones,
+        // and qualify the names of their constraints with the component name.
+        let mut offsets: HashMap<&str, usize> = HashMap::with_capacity(names.len());
+        let mut owners: Vec<(usize, usize)> = Vec::new();
+        let mut variable_names: Vec<String> = Vec::new();
+        let mut activations: Vec<Activation<T>> = Vec::new();
+        let mut constraints: Vec<Constraint<T>> = Vec::new();
+        let mut locations: HashMap<String, (String, String)> = HashMap::new();
+        let mut timeouts: HashMap<String, Duration> = HashMap::new();
+        for (ci, name) in names.iter().enumerate() {
+            let component = &self.components[name];
+            let offset = owners.len();
+            offsets.insert(name, offset);
+            let mut local_names = vec![String::new(); component.n_variables()];
+            for (variable, &vi) in component.name_to_index() {
+                local_names[vi] = variable.clone();
+            }
+            variable_names.extend(local_names);
+            owners.extend((0..component.n_variables()).map(|vi| (ci, vi)));
+            activations.extend(component.variables().iter().map(|v| v.get().clone()));
+            for constraint in component.constraints() {
+                let qualified = format!("{}.{}", name, constraint.name());
+                locations.insert(
+                    qualified.clone(),
+                    (name.clone(), constraint.name().to_owned()),
+                );
+                if let Some(timeout) = component.method_timeout() {
+                    timeouts.insert(qualified.clone(), timeout);
+                }
+                constraints.push(constraint.reindexed(qualified, |vi| offset + vi));
+            }
+        }
+        for link in Self::links_of(&self.constraints, names) {
+            // Skip links to variables that have since been removed from their component
+            let indices: Option<Vec<usize>> = link
+                .variables()
+                .iter()
+                .map(|(component, variable)| {
+                    let index = self.components[component].variable_index(variable).ok()?;
+                    Some(offsets[component.as_str()] + index)
+                })
+                .collect();
+            let indices = match indices {
+                Some(indices) => indices,
+                None => continue,
+            };
+            locations.insert(
+                link.name().to_owned(),
+                (link.location(), link.name().to_owned()),
+            );
+            constraints.push(
+                link.constraint()
+                    .reindexed(link.name().to_owned(), |vi| indices[vi]),
+            );
+        }
+
+        // Rank variables by when they were last edited or solved, then by the ranking in their component
+        let mut ranked: Vec<(Reverse<usize>, usize, usize)> = Vec::with_capacity(owners.len());
+        for name in names {
+            let offset = offsets[name.as_str()];
+            for (position, vi) in self.components[name].ranking().into_iter().enumerate() {
+                let key = (name.clone(), variable_names[offset + vi].clone());
+                let priority = self.priorities.get(&key).copied().unwrap_or_default();
+                ranked.push((Reverse(priority), position, offset + vi));
+            }
+        }
+        ranked.sort_unstable();
+        let ranking: Vec<usize> = ranked.into_iter().map(|(_, _, vi)| vi).collect();
+
+        let name_to_index = variable_names
+            .iter()
+            .zip(&owners)
+            .enumerate()
+            .map(|(vi, (variable, &(ci, _)))| (format!("{}.{}", names[ci], variable), vi))
+            .collect();
+        let component = Component::from_activations(
+            names.join("+"),
+            name_to_index,
+            activations,
+            constraints,
+            &ranking,
+        );
+        MergedGroup {
+            component,
+            owners,
+            variable_names,
+            locations,
+            timeouts,
+            ranking,
+        }
+    }
+
+    /// Plans the merged component of the specified components with the planner of the first one.
+    /// If the plan is only inspected, the planner is left unchanged, see [`Plan::plan_uncached`].
+    fn plan_group(
+        &self,
+        names: &[String],
+        merged: &Component<T>,
+        inspect: bool,
+    ) -> Result<OwnedPlan<Method<T>>, PlanError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("plan", component = %merged.name()).entered();
+        let planner = self.components[&names[0]].planner();
+        let plan = match inspect {
+            true => planner.plan_uncached(merged),
+            false => planner.plan(merged),
+        };
+        plan.map_err(|e| match e {
+            PlanError::Overconstrained(mut conflict) => {
+                conflict.set_component(merged.name());
+                PlanError::Overconstrained(conflict)
+            }
+        })
+    }
+
+    /// Solves the specified components, and the constraints between them, as one graph,
+    /// using their merged component and the plan made for it by [`plan_group`](Self::plan_group).
+    fn solve_group(
+        &mut self,
+        names: &[String],
+        merged: MergedGroup<T>,
+        plan: OwnedPlan<Method<T>>,
+        spawn: &impl MethodExecutor,
+    ) -> Vec<Activation<T>>
     where
         T: Send + Sync + 'static + Debug,
     {
-        for component in self.components.values_mut() {
-            component.par_solve(spawn)?;
+        let location = names.join("+");
+        log::trace!("Solving linked components {}", location);
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "solve",
+            component = %location,
+            generation = tracing::field::Empty,
+        )
+        .entered();
+
+        let MergedGroup {
+            component: merged,
+            owners,
+            variable_names,
+            locations,
+            timeouts,
+            ranking,
+        } = merged;
+        if let Some(stats) = &self.stats.0 {
+            stats.lock().unwrap().record_solve();
         }
+        let links: Vec<&InterConstraint<T>> = Self::links_of(&self.constraints, names).collect();
+        let activations: Vec<Activation<T>> = merged.values().into_iter().cloned().collect();
 
-        Ok(())
+        // Update the ranking in each component, and when each variable was last solved
+        let mut ranker = SortRanker::of_size(owners.len());
+        for &vi in ranking.iter().rev() {
+            ranker.touch(vi);
+        }
+        let ranking = adjust_priorities(&plan, &ranker).ranking();
+        for &vi in ranking.iter().rev() {
+            self.clock += 1;
+            let (ci, _) = owners[vi];
+            let key = (names[ci].clone(), variable_names[vi].clone());
+            self.priorities.insert(key, self.clock);
+        }
+        for (ci, name) in names.iter().enumerate() {
+            let local: Vec<usize> = ranking
+                .iter()
+                .map(|&vi| owners[vi])
+                .filter(|&(owner, _)| owner == ci)
+                .map(|(_, local)| local)
+                .collect();
+            let component = self.components.get_mut(name).expect("name is a key");
+            component.set_ranking(&local);
+        }
+
+        // Store the constraints that were dropped due to conflicts with stronger ones
+        let enforced: HashSet<&str> = plan.iter().map(|ec| ec.name()).collect();
+        let is_unenforced = |c: &Constraint<T>, name: &str| {
+            c.is_active() && c.strength() != Strength::Required && !enforced.contains(name)
+        };
+        for name in names {
+            let component = self.components.get_mut(name).expect("name is a key");
+            let unenforced = component
+                .constraints()
+                .iter()
+                .filter(|c| is_unenforced(c, &format!("{}.{}", name, c.name())))
+                .map(|c| c.name().to_owned())
+                .collect();
+            component.set_unenforced(unenforced);
+        }
+        self.unenforced
+            .retain(|(_, name)| !links.iter().any(|link| link.name() == name));
+        for link in &links {
+            if is_unenforced(link.constraint(), link.name()) {
+                self.unenforced
+                    .push((link.location(), link.name().to_owned()));
+            }
+        }
+
+        // Start a new generation in each component, and only accept events from it for written variables
+        let generations: Vec<GenerationId> = names
+            .iter()
+            .map(|name| {
+                let component = self.components.get_mut(name).expect("name is a key");
+                component.begin_generation()
+            })
+            .collect();
+        #[cfg(feature = "tracing")]
+        span.record("generation", &tracing::field::debug(&generations));
+        let targets: Vec<_> = owners
+            .iter()
+            .map(|&(ci, vi)| {
+                let callbacks = self.components[&names[ci]].callbacks().clone();
+                (callbacks, vi, generations[ci])
+            })
+            .collect();
+        for p in &plan {
+            for &o in p.method().outputs() {
+                let (callbacks, vi, generation) = &targets[o];
+                callbacks.lock().unwrap()[*vi].set_target(*generation);
+            }
+        }
+        let targets = Arc::new(targets);
+
+        // Solve based on the plan, and send events to the component each variable belongs to.
+        // The generation passed to the solver is replaced by the one of that component.
+        let mut values = Variables::new(activations);
+        solver::solve_with_locations(
+            &plan,
+            &mut values,
+            |constraint| {
+                locations
+                    .get(constraint)
+                    .cloned()
+                    .unwrap_or_else(|| (location.clone(), constraint.to_owned()))
+            },
+            |constraint| timeouts.get(constraint).copied(),
+            generations[0],
+            self.stats.0.as_ref(),
+            spawn,
+            move |ge| {
+                let (callbacks, vi, generation) = &targets[ge.variable()];
+                let lock = callbacks.lock().unwrap();
+                lock[*vi].call(EventWithLocation::new(*vi, *generation, ge.event()));
+            },
+        );
+
+        // Move the new values to their components, and commit them
+        let mut outputs = Vec::new();
+        for p in &plan {
+            for &o in p.method().outputs() {
+                let (ci, vi) = owners[o];
+                let component = self.components.get_mut(&names[ci]).expect("name is a key");
+                component.set_solved(vi, values[o].clone());
+                outputs.push(values[o].clone());
+            }
+        }
+        for name in names {
+            let component = self.components.get_mut(name).expect("name is a key");
+            component.set_modified(false);
+            if component.has_uncommitted_changes() {
+                component.commit();
+                self.record_change(name);
+            }
+        }
+
+        outputs
     }
 
     /// Attaches a callback to a variable in a component, to be called when its status changes.
@@ -174,23 +922,125 @@ impl<T> ConstraintSystem<T> {
     ///     Event::Error(errors) => panic!("{:?}", errors),
     /// });
     /// ```
+    ///
+    /// The callback stays subscribed until the variable is unsubscribed from, see [`Component::subscribe`].
     pub fn subscribe<'a>(
         &mut self,
         component: &'a str,
         variable: &'a str,
         callback: impl for<'e> Fn(Event<'e, T, SolveError>) + Send + Sync + 'static,
     ) -> Result<(), NoSuchItem<'a>>
+    where
+        T: 'static,
+    {
+        let subscription = self.subscribe_guarded(component, variable, callback)?;
+        subscription.detach();
+        Ok(())
+    }
+
+    /// Like [`subscribe`](Self::subscribe), but only this callback is unsubscribed
+    /// when the returned [`Subscription`] is dropped, see [`Component::subscribe_guarded`].
+    pub fn subscribe_guarded<'a>(
+        &mut self,
+        component: &'a str,
+        variable: &'a str,
+        callback: impl for<'e> Fn(Event<'e, T, SolveError>) + Send + Sync + 'static,
+    ) -> Result<Subscription, NoSuchItem<'a>>
     where
         T: 'static,
     {
         log::trace!("Subscribing to {}.{}", component, variable);
         let component = self.component_mut(component)?;
         component
-            .subscribe(variable, callback)
+            .subscribe_guarded(variable, callback)
             .map_err(NoSuchItem::Variable)
     }
 
-    /// Unsubscribe from a variable in the specified component to avoid receiving further events.
+    /// Add a callback to be called when any variable in the constraint system is updated,
+    /// along with the names of the component and the variable.
+    ///
+    /// This includes components that are added later.
+    /// The callback is unsubscribed when the returned [`Subscription`] is dropped,
+    /// see [`Component::subscribe_all`].
+    pub fn subscribe_all(
+        &mut self,
+        callback: impl Fn(&str, &str, Event<'_, T, SolveError>) + Send + Sync + 'static,
+    ) -> Subscription
+    where
+        T: 'static,
+    {
+        self.subscribe_all_with_generation(move |component, variable, _, event| {
+            callback(component, variable, event)
+        })
+    }
+
+    /// Returns a [`Stream`](futures::Stream) of the events from every variable in the constraint system,
+    /// including components that are added later. See [`Component::events`].
+    pub fn events(&mut self) -> EventStream<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        EventStream::new(|sender| {
+            self.subscribe_all_with_generation(move |component, variable, generation, event| {
+                let item = (
+                    component.to_owned(),
+                    variable.to_owned(),
+                    generation,
+                    event.into(),
+                );
+                // The stream may have been dropped
+                let _ = sender.unbounded_send(item);
+            })
+        })
+    }
+
+    /// Like [`subscribe_all`](Self::subscribe_all), but also passes the generation each event is from.
+    fn subscribe_all_with_generation(
+        &mut self,
+        callback: impl Fn(&str, &str, GenerationId, Event<'_, T, SolveError>) + Send + Sync + 'static,
+    ) -> Subscription
+    where
+        T: 'static,
+    {
+        let callback: SystemEventCallback<T> = Arc::new(callback);
+        let subscribe_to: SubscribeTo<T> = Arc::new(move |component| {
+            let callback = callback.clone();
+            let name = component.name().to_owned();
+            component.subscribe_all_with_generation(move |variable, generation, event| {
+                callback(&name, variable, generation, event)
+            })
+        });
+        let id = SubscriptionId::next();
+        let mut names: Vec<String> = self.components.keys().cloned().collect();
+        names.sort();
+        let subscriptions = names
+            .into_iter()
+            .map(|name| {
+                let component = self.components.get_mut(&name).expect("name is a key");
+                let subscription = subscribe_to(component);
+                (name, subscription)
+            })
+            .collect();
+        self.callbacks.0.lock().unwrap().push(SystemCallback {
+            id,
+            subscribe_to,
+            subscriptions,
+        });
+        // Dropping the system callback drops its subscriptions to each component
+        let callbacks = Arc::downgrade(&self.callbacks.0);
+        Subscription::new(id, move || {
+            if let Some(callbacks) = callbacks.upgrade() {
+                let removed: Vec<SystemCallback<T>> = callbacks
+                    .lock()
+                    .unwrap()
+                    .drain_filter(|sc| sc.id == id)
+                    .collect();
+                drop(removed);
+            }
+        })
+    }
+
+    /// Unsubscribe all callbacks from a variable in the specified component to avoid receiving further events.
     pub fn unsubscribe<'a>(
         &mut self,
         component: &'a str,
@@ -229,30 +1079,61 @@ impl<T> ConstraintSystem<T> {
         Ok(())
     }
 
-    /// Undo the last change of the last modified component.
+    /// Undo the last change.
+    ///
+    /// A change made by solving components that are connected by constraints
+    /// is undone in all of them, or in none of them if one of them can not undo it.
     pub fn undo(&mut self) -> Result<(), NoMoreUndo> {
-        let last_undone = self.undo_stack.pop().ok_or(NoMoreUndo)?;
-        let component = self
-            .component_mut(&last_undone)
-            .expect("Component was removed");
-        log::trace!("Undoing last change in {}", component.name());
-        let _ = component.undo()?;
+        self.step_open = false;
+        // Components that have been removed since are skipped
+        let last_step = self.undo_stack.last().ok_or(NoMoreUndo)?;
+        if !self.existing_components(last_step).all(|c| c.can_undo()) {
+            return Err(NoMoreUndo);
+        }
+        let last_undone = self.undo_stack.pop().expect("step was just checked");
+        for name in &last_undone {
+            if let Some(component) = self.components.get_mut(name) {
+                log::trace!("Undoing last change in {}", name);
+                component
+                    .undo()
+                    .expect("component was checked to be undoable");
+            }
+        }
         self.redo_stack.push(last_undone);
         Ok(())
     }
 
-    /// Redo the last change of the last modified component.
+    /// Redo the last undone change.
+    ///
+    /// Like [`undo`](#method.undo), this happens in all the components of the change or in none of them.
     pub fn redo(&mut self) -> Result<(), NoMoreRedo> {
-        let last_redone = self.redo_stack.pop().ok_or(NoMoreRedo)?;
-        let component = self
-            .component_mut(&last_redone)
-            .expect("Component was removed");
-        log::trace!("Redoing last change in {}", component.name());
-        let _ = component.redo()?;
+        let last_step = self.redo_stack.last().ok_or(NoMoreRedo)?;
+        if !self.existing_components(last_step).all(|c| c.can_redo()) {
+            return Err(NoMoreRedo);
+        }
+        let last_redone = self.redo_stack.pop().expect("step was just checked");
+        for name in &last_redone {
+            if let Some(component) = self.components.get_mut(name) {
+                log::trace!("Redoing last change in {}", name);
+                component
+                    .redo()
+                    .expect("component was checked to be redoable");
+            }
+        }
         self.undo_stack.push(last_redone);
         Ok(())
     }
 
+    /// Returns the components of an undo step that still exist.
+    fn existing_components<'a>(
+        &'a self,
+        names: &'a [String],
+    ) -> impl Iterator<Item = &'a Component<T>> + 'a {
+        names
+            .iter()
+            .filter_map(move |name| self.components.get(name))
+    }
+
     /// Sets the undo-limit per component in the system.
     pub fn set_undo_limit(&mut self, limit: UndoLimit) {
         for component in self.components.values_mut() {
@@ -281,6 +1162,116 @@ impl<T> ConstraintSystem<T> {
         component.disable_constraint(constraint)?;
         Ok(())
     }
+
+    /// Sets the strength of the specified constraint.
+    pub fn set_constraint_strength<'a>(
+        &mut self,
+        component: &'a str,
+        constraint: &'a str,
+        strength: Strength,
+    ) -> Result<(), NoSuchItem<'a>> {
+        let component = self.component_mut(component)?;
+        component.set_constraint_strength(constraint, strength)?;
+        Ok(())
+    }
+
+    /// Returns the component and constraint names of the constraints
+    /// that were left unenforced in the last solve of each component.
+    ///
+    /// Constraints between components are reported with the names of their components joined by `+`.
+    pub fn unenforced_constraints(&self) -> Vec<(&str, &str)> {
+        self.components
+            .values()
+            .flat_map(|c| {
+                c.unenforced_constraints()
+                    .iter()
+                    .map(move |name| (c.name(), name.as_str()))
+            })
+            .chain(
+                self.unenforced
+                    .iter()
+                    .map(|(location, name)| (location.as_str(), name.as_str())),
+            )
+            .collect()
+    }
+
+    /// Returns the component and variable names of the variables that are recomputed from the specified variable
+    /// by the plan for the current ranking.
+    /// This includes variables in other components that are linked to it by constraints between components.
+    ///
+    /// Editing the variable may select other methods.
+    /// Use [`ConstraintSystem::affected_by_edit`] to get the variables that an edit would change.
+    pub fn downstream_of(
+        &self,
+        component: &str,
+        variable: &str,
+    ) -> Result<Vec<(String, String)>, QueryError> {
+        self.query(component, variable, false, dependencies::downstream)
+    }
+
+    /// Returns the component and variable names of the variables that the value of the specified variable
+    /// is computed from by the plan for the current ranking.
+    pub fn upstream_of(
+        &self,
+        component: &str,
+        variable: &str,
+    ) -> Result<Vec<(String, String)>, QueryError> {
+        self.query(component, variable, false, dependencies::upstream)
+    }
+
+    /// Returns the component and variable names of the variables that would be recomputed
+    /// if the specified variable was edited.
+    ///
+    /// This plans as if the variable had been edited, but does not run any methods or change the constraint system.
+    pub fn affected_by_edit(
+        &self,
+        component: &str,
+        variable: &str,
+    ) -> Result<Vec<(String, String)>, QueryError> {
+        self.query(component, variable, true, dependencies::downstream)
+    }
+
+    /// Plans the components linked to the specified variable, as if it had been edited if `edit` is true,
+    /// and returns the variables that `follow` finds from it in the plan.
+    fn query(
+        &self,
+        component: &str,
+        variable: &str,
+        edit: bool,
+        follow: Follow<Method<T>>,
+    ) -> Result<Vec<(String, String)>, QueryError> {
+        let local = self
+            .components
+            .get(component)
+            .ok_or_else(|| QueryError::NoSuchComponent(component.to_owned()))?
+            .variable_index(variable)
+            .map_err(|_| QueryError::NoSuchVariable(format!("{}.{}", component, variable)))?;
+        let names = self
+            .linked_groups()
+            .into_iter()
+            .find(|group| group.iter().any(|name| name == component))
+            .unwrap_or_else(|| vec![component.to_owned()]);
+        let mut merged = self.merge_group(&names);
+        let index = merged
+            .owners
+            .iter()
+            .position(|&(ci, vi)| names[ci] == component && vi == local)
+            .expect("variable is in a merged component");
+        if edit {
+            let mut ranking = merged.ranking.clone();
+            ranking.retain(|&vi| vi != index);
+            ranking.insert(0, index);
+            merged.component.set_ranking(&ranking);
+        }
+        let plan = self.plan_group(&names, &merged.component, true)?;
+        Ok(follow(&plan, index)
+            .into_iter()
+            .map(|vi| {
+                let (ci, _) = merged.owners[vi];
+                (names[ci].clone(), merged.variable_names[vi].clone())
+            })
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -288,9 +1279,269 @@ mod tests {
     use super::ConstraintSystem;
     use crate::{
         component,
-        event::{Event, Ready},
+        event::{Event, OwnedEvent, OwnedReady, Ready},
+        executor::ThreadExecutor,
+        macros::{RawConstraint, RawMethod},
+        model::{
+            activation::State,
+            errors::{NoSuchConstraint, NoSuchQualifiedVariable, QueryError, TransactionError},
+            undo::{NoMoreRedo, NoMoreUndo, UndoLimit},
+            Activation, Component, Method,
+        },
+        planner::{Conflict, OwnedPlan, Plan, PlanError},
         ret,
     };
+    use std::sync::{Arc, Mutex};
+
+    /// A parent and child component, where the width of the child must equal that of the parent.
+    fn parent_and_child() -> ConstraintSystem<i32> {
+        let mut cs = ConstraintSystem::new();
+        cs.add_component(component! {
+            component Parent {
+                let total: i32 = 0, width: i32 = 0;
+                constraint Double {
+                    to_total(width: &i32) -> [total] = ret![*width * 2];
+                    to_width(total: &i32) -> [width] = ret![*total / 2];
+                }
+            }
+        });
+        cs.add_component(component! {
+            component Child {
+                let width: i32 = 0;
+            }
+        });
+        cs.add_constraint(same_width("SameWidth", true)).unwrap();
+        cs
+    }
+
+    /// A constraint that copies the width of the parent to the child, and optionally back.
+    fn same_width(name: &str, two_way: bool) -> RawConstraint<i32> {
+        let mut methods = vec![RawMethod::new(
+            "down",
+            vec!["Parent.width"],
+            vec!["Child.width"],
+            Arc::new(Ok),
+        )];
+        if two_way {
+            methods.push(RawMethod::new(
+                "up",
+                vec!["Child.width"],
+                vec!["Parent.width"],
+                Arc::new(Ok),
+            ));
+        }
+        RawConstraint::new(name, methods)
+    }
+
+    fn values(cs: &ConstraintSystem<i32>) -> [Activation<i32>; 3] {
+        [
+            cs.value("Parent", "total").unwrap(),
+            cs.value("Parent", "width").unwrap(),
+            cs.value("Child", "width").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn constraints_between_components_are_enforced() {
+        let mut cs = parent_and_child();
+        cs.edit("Child", "width", 4).unwrap();
+        assert_eq!(cs.solve(), Ok(()));
+        assert_eq!(values(&cs), [8.into(), 4.into(), 4.into()]);
+
+        cs.edit("Parent", "total", 10).unwrap();
+        assert_eq!(cs.solve(), Ok(()));
+        assert_eq!(values(&cs), [10.into(), 5.into(), 5.into()]);
+    }
+
+    #[test]
+    fn undo_and_redo_span_linked_components() {
+        let mut cs = parent_and_child();
+        cs.edit("Parent", "total", 10).unwrap();
+        cs.solve().unwrap();
+        cs.edit("Child", "width", 1).unwrap();
+        cs.solve().unwrap();
+        assert_eq!(values(&cs), [2.into(), 1.into(), 1.into()]);
+
+        assert_eq!(cs.undo(), Ok(()));
+        assert_eq!(values(&cs), [10.into(), 5.into(), 5.into()]);
+        assert_eq!(cs.undo(), Ok(()));
+        assert_eq!(values(&cs), [0.into(), 0.into(), 0.into()]);
+        assert_eq!(cs.redo(), Ok(()));
+        assert_eq!(values(&cs), [10.into(), 5.into(), 5.into()]);
+    }
+
+    #[test]
+    fn undo_is_all_or_nothing_across_linked_components() {
+        let mut cs = parent_and_child();
+        cs.edit("Parent", "total", 10).unwrap();
+        cs.solve().unwrap();
+        // The child can no longer undo its part of the change
+        cs.component_mut("Child")
+            .unwrap()
+            .set_undo_limit(UndoLimit::Limited(0));
+
+        assert_eq!(cs.undo(), Err(NoMoreUndo));
+        assert_eq!(values(&cs), [10.into(), 5.into(), 5.into()]);
+        assert_eq!(cs.redo(), Err(NoMoreRedo));
+    }
+
+    #[test]
+    fn redo_is_all_or_nothing_across_linked_components() {
+        let mut cs = parent_and_child();
+        cs.edit("Parent", "total", 10).unwrap();
+        cs.solve().unwrap();
+        assert_eq!(cs.undo(), Ok(()));
+        // The child redoes its part of the change on its own
+        cs.component_mut("Child").unwrap().redo().unwrap();
+
+        assert_eq!(cs.redo(), Err(NoMoreRedo));
+        assert_eq!(values(&cs), [0.into(), 0.into(), 5.into()]);
+        // The step is kept, so it can be redone once the child is back
+        cs.component_mut("Child").unwrap().undo().unwrap();
+        assert_eq!(cs.redo(), Ok(()));
+        assert_eq!(values(&cs), [10.into(), 5.into(), 5.into()]);
+    }
+
+    #[test]
+    fn subscribers_of_linked_components_are_notified() {
+        let mut cs = parent_and_child();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        cs.subscribe("Child", "width", move |e| {
+            if let Event::Ready(Ready::Changed(&v)) = e {
+                events_clone.lock().unwrap().push(v);
+            }
+        })
+        .unwrap();
+        cs.edit("Parent", "total", 6).unwrap();
+        cs.solve().unwrap();
+        assert_eq!(*events.lock().unwrap(), vec![0, 3]);
+    }
+
+    #[test]
+    fn conflicts_between_components_are_reported() {
+        let mut cs = parent_and_child();
+        cs.add_constraint(same_width("A", false)).unwrap();
+        cs.add_constraint(same_width("B", false)).unwrap();
+        let mut conflict = Conflict::new(
+            vec!["A".to_owned(), "B".to_owned()],
+            vec!["Child.width".to_owned(), "Parent.width".to_owned()],
+        );
+        conflict.set_component("Child+Parent");
+        assert_eq!(cs.solve(), Err(PlanError::Overconstrained(conflict)));
+
+        cs.remove_constraint("A").unwrap();
+        cs.remove_constraint("B").unwrap();
+        assert_eq!(cs.solve(), Ok(()));
+        assert_eq!(cs.remove_constraint("B"), Err(NoSuchConstraint("B")));
+    }
+
+    #[test]
+    fn failed_planning_leaves_every_component_unsolved() {
+        let mut cs = parent_and_child();
+        cs.add_component(component! {
+            component Other {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Same { ab(a: &i32) -> [b] = ret![*a]; }
+            }
+        });
+        cs.edit("Other", "a", 3).unwrap();
+        cs.edit("Parent", "total", 10).unwrap();
+        cs.add_constraint(same_width("A", false)).unwrap();
+        cs.add_constraint(same_width("B", false)).unwrap();
+        assert!(matches!(cs.solve(), Err(PlanError::Overconstrained(_))));
+        assert_eq!(cs.value("Other", "b"), Ok(0.into()));
+        assert_eq!(values(&cs), [10.into(), 0.into(), 0.into()]);
+
+        // The edits and the successful solve are undone as one step
+        cs.remove_constraint("A").unwrap();
+        cs.remove_constraint("B").unwrap();
+        assert_eq!(cs.solve(), Ok(()));
+        assert_eq!(cs.value("Other", "b"), Ok(3.into()));
+        assert_eq!(values(&cs), [10.into(), 5.into(), 5.into()]);
+        assert_eq!(cs.undo(), Ok(()));
+        assert_eq!(cs.value("Other", "a"), Ok(0.into()));
+        assert_eq!(values(&cs), [0.into(), 0.into(), 0.into()]);
+    }
+
+    #[test]
+    fn only_modified_components_are_solved() {
+        let mut cs = parent_and_child();
+        cs.edit("Parent", "total", 10).unwrap();
+        cs.solve().unwrap();
+        assert!(cs.components().all(|c| !c.is_modified()));
+
+        // Solving without changes does not add a step, so the undone one can be redone
+        cs.undo().unwrap();
+        cs.solve().unwrap();
+        assert_eq!(cs.redo(), Ok(()));
+        assert_eq!(values(&cs), [10.into(), 5.into(), 5.into()]);
+
+        // Changing the constraints between components modifies them
+        cs.remove_constraint("SameWidth").unwrap();
+        assert!(cs.components().all(|c| c.is_modified()));
+    }
+
+    #[test]
+    fn planner_is_given_to_components_added_later() {
+        #[derive(Clone, Copy, Debug)]
+        struct Failing;
+        impl Plan<Component<i32>> for Failing {
+            fn plan(&self, _: &Component<i32>) -> Result<OwnedPlan<Method<i32>>, PlanError> {
+                Err(PlanError::Overconstrained(Conflict::default()))
+            }
+        }
+
+        let mut cs: ConstraintSystem<i32> = ConstraintSystem::new();
+        cs.set_planner(Failing);
+        cs.add_component(component! {
+            component Added {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Same { ab(a: &i32) -> [b] = ret![*a]; }
+            }
+        });
+        assert!(matches!(cs.solve(), Err(PlanError::Overconstrained(_))));
+    }
+
+    #[test]
+    fn constraints_must_reference_existing_variables() {
+        let mut cs = parent_and_child();
+        let raw = |input: &str| {
+            RawConstraint::new(
+                "Invalid",
+                vec![RawMethod::new(
+                    "m",
+                    vec![input],
+                    vec!["Child.width"],
+                    Arc::new(Ok),
+                )],
+            )
+        };
+        assert_eq!(
+            cs.add_constraint(raw("Parent.height")),
+            Err(NoSuchQualifiedVariable("Parent.height".to_owned()))
+        );
+        assert_eq!(
+            cs.add_constraint(raw("width")),
+            Err(NoSuchQualifiedVariable("width".to_owned()))
+        );
+    }
+
+    #[test]
+    fn removing_a_component_removes_its_constraints() {
+        let mut cs = parent_and_child();
+        cs.edit("Child", "width", 1).unwrap();
+        cs.solve().unwrap();
+        cs.remove_component("Child");
+        cs.edit("Parent", "total", 4).unwrap();
+        assert_eq!(cs.solve(), Ok(()));
+        assert_eq!(cs.value("Parent", "width"), Ok(2.into()));
+        assert!(cs.linked_groups().is_empty());
+        assert!(cs
+            .priorities
+            .keys()
+            .all(|(component, _)| component != "Child"));
+    }
 
     #[test]
     pub fn constraint_system_test() {
@@ -331,4 +1582,195 @@ mod tests {
         })
         .unwrap();
     }
+
+    #[test]
+    fn constraints_follow_variables_edited_at_runtime() {
+        let mut cs = parent_and_child();
+
+        // Moves the width of the parent to index 0
+        let parent = cs.component_mut("Parent").unwrap();
+        assert_eq!(
+            parent.remove_variable_and_constraints("total"),
+            Ok(vec!["Double".to_owned()])
+        );
+        cs.edit("Child", "width", 6).unwrap();
+        assert_eq!(cs.solve(), Ok(()));
+        assert_eq!(cs.value("Parent", "width"), Ok(6.into()));
+
+        // Links to removed variables are no longer enforced
+        cs.component_mut("Child")
+            .unwrap()
+            .remove_variable("width")
+            .unwrap();
+        cs.edit("Parent", "width", 4).unwrap();
+        assert_eq!(cs.solve(), Ok(()));
+        assert_eq!(cs.value("Parent", "width"), Ok(4.into()));
+    }
+
+    #[test]
+    fn transactions_are_one_undo_step() {
+        let mut cs = parent_and_child();
+        cs.transaction(|tx| {
+            tx.edit("Parent", "total", 4);
+            tx.edit("Child", "width", 3);
+            tx.solve();
+        })
+        .unwrap();
+        assert_eq!(values(&cs), [6.into(), 3.into(), 3.into()]);
+        assert_eq!(
+            cs.transaction(|tx| tx.edit("Child", "height", 1)),
+            Err(TransactionError::NoSuchVariable("Child.height".to_owned()))
+        );
+        cs.undo().unwrap();
+        assert_eq!(values(&cs), [0.into(), 0.into(), 0.into()]);
+        assert_eq!(cs.undo(), Err(NoMoreUndo));
+    }
+
+    #[test]
+    fn system_subscribers_get_component_and_variable_names() {
+        let mut cs = parent_and_child();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        let subscription = cs.subscribe_all(move |component, variable, event| {
+            if let Event::Ready(Ready::Changed(&v)) = event {
+                let name = format!("{}.{}", component, variable);
+                events_clone.lock().unwrap().push((name, v));
+            }
+        });
+        // Components that are added later are included
+        cs.add_component(component! { component Other { let x: i32 = 1; } });
+        cs.edit("Other", "x", 2).unwrap();
+        // Removed ones are not
+        let mut child = cs.remove_component("Child").unwrap();
+        child.edit("width", 3).unwrap();
+        drop(subscription);
+        cs.edit("Other", "x", 4).unwrap();
+
+        let expected = vec![
+            ("Child.width", 0),
+            ("Parent.total", 0),
+            ("Parent.width", 0),
+            ("Other.x", 1),
+            ("Other.x", 2),
+        ];
+        assert_eq!(
+            *events.lock().unwrap(),
+            expected
+                .into_iter()
+                .map(|(name, v)| (name.to_owned(), v))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn event_streams_include_all_components() {
+        let mut cs = parent_and_child();
+        let mut events = cs.events();
+        cs.edit("Parent", "total", 6).unwrap();
+        cs.solve().unwrap();
+        futures::executor::block_on(async {
+            let mut settled = Vec::new();
+            for _ in 0..6 {
+                let (component, variable, _, event) = events.next_settled().await.unwrap();
+                settled.push((format!("{}.{}", component, variable), event));
+            }
+            let changed = |name: &str, value| {
+                (
+                    name.to_owned(),
+                    OwnedEvent::Ready(OwnedReady::Changed(value)),
+                )
+            };
+            assert_eq!(
+                settled,
+                vec![
+                    changed("Child.width", 0),
+                    changed("Parent.total", 0),
+                    changed("Parent.width", 0),
+                    changed("Parent.total", 6),
+                    changed("Parent.width", 3),
+                    changed("Child.width", 3),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn solve_handles_wait_for_all_components() {
+        let mut cs = parent_and_child();
+        cs.edit("Parent", "total", 6).unwrap();
+        let handle = cs.par_solve(&ThreadExecutor).unwrap();
+        assert_eq!(futures::executor::block_on(handle), Ok(()));
+        // Everything is ready once the handle resolves
+        for (component, variable, expected) in [("Parent", "width", 3), ("Child", "width", 3)] {
+            let activation = cs.value(component, variable).unwrap();
+            assert_eq!(
+                activation.inner().read().unwrap().state(),
+                &State::Ready(Arc::new(expected))
+            );
+        }
+    }
+
+    #[test]
+    fn stats_include_all_components() {
+        let mut cs = parent_and_child();
+        cs.enable_stats();
+        cs.add_component(component! {
+            component Other {
+                let a: i32 = 0, b: i32 = 0;
+                constraint Copy {
+                    copy(a: &i32) -> [b] = ret![*a];
+                }
+            }
+        });
+        cs.edit("Parent", "total", 6).unwrap();
+        cs.edit("Other", "a", 1).unwrap();
+        cs.solve().unwrap();
+        let stats = cs.stats().unwrap();
+        // The linked components are planned together
+        assert_eq!(stats.solves(), 2);
+        for (component, constraint, method) in [
+            ("Parent", "Double", "to_width"),
+            ("Child+Parent", "SameWidth", "down"),
+            ("Other", "Copy", "copy"),
+        ] {
+            let method_stats = stats.method(component, constraint, method);
+            assert_eq!(method_stats.map(|s| s.invocations()), Some(1), "{}", method);
+        }
+    }
+
+    #[test]
+    fn queries_cross_linked_components() {
+        let mut cs = parent_and_child();
+        cs.edit("Parent", "total", 6).unwrap();
+        cs.solve().unwrap();
+        let pair = |component: &str, variable: &str| (component.to_owned(), variable.to_owned());
+        assert_eq!(
+            cs.downstream_of("Parent", "total"),
+            Ok(vec![pair("Child", "width"), pair("Parent", "width")])
+        );
+        assert_eq!(
+            cs.upstream_of("Child", "width"),
+            Ok(vec![pair("Parent", "total"), pair("Parent", "width")])
+        );
+
+        // Editing the child changes the parent back, without running any methods
+        assert_eq!(
+            cs.affected_by_edit("Child", "width"),
+            Ok(vec![pair("Parent", "total"), pair("Parent", "width")])
+        );
+        assert_eq!(values(&cs)[2], Activation::from(3));
+        assert_eq!(
+            cs.downstream_of("Child", "width"),
+            Ok(vec![]),
+            "the query does not change the ranking"
+        );
+        assert_eq!(
+            cs.affected_by_edit("Child", "height"),
+            Err(QueryError::NoSuchVariable("Child.height".to_owned()))
+        );
+        assert_eq!(
+            cs.upstream_of("Nobody", "width"),
+            Err(QueryError::NoSuchComponent("Nobody".to_owned()))
+        );
+    }
 }
@@ -1,6 +1,7 @@
 //! Errors from the API of [`ConstraintSystem`](crate::model::ConstraintSystem) and [`Component`](crate::model::Component).
 
 use super::undo::{NoMoreRedo, NoMoreUndo};
+use crate::planner::PlanError;
 use std::fmt::Display;
 
 /// An error occured while using the API.
@@ -89,3 +90,145 @@ impl<'a> Display for NoSuchVariable<'a> {
         write!(f, "Variable not found: {}", self.0)
     }
 }
+
+/// The variable, named as `component.variable`, does not exist.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NoSuchQualifiedVariable(pub String);
+
+impl Display for NoSuchQualifiedVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Variable not found: {}", self.0)
+    }
+}
+
+/// A variable with the specified name already exists.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VariableExists<'a>(pub &'a str);
+
+impl<'a> Display for VariableExists<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Variable already exists: {}", self.0)
+    }
+}
+
+/// An error from removing a variable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemoveVariableError<'a> {
+    /// See [`NoSuchVariable`].
+    Variable(NoSuchVariable<'a>),
+    /// The variable is referenced by the listed constraints.
+    InUse(&'a str, Vec<String>),
+}
+
+impl<'a> Display for RemoveVariableError<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemoveVariableError::Variable(e) => e.fmt(f),
+            RemoveVariableError::InUse(variable, constraints) => write!(
+                f,
+                "Variable {} is used by constraints {}",
+                variable,
+                constraints.join(", ")
+            ),
+        }
+    }
+}
+
+impl<'a> From<NoSuchVariable<'a>> for RemoveVariableError<'a> {
+    fn from(nsv: NoSuchVariable<'a>) -> Self {
+        Self::Variable(nsv)
+    }
+}
+
+/// An error from adding a constraint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AddConstraintError {
+    /// A method of the constraint referenced a variable that does not exist.
+    NoSuchVariable(String),
+    /// A constraint with the same name already exists.
+    ConstraintExists(String),
+}
+
+impl Display for AddConstraintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddConstraintError::NoSuchVariable(name) => write!(f, "Variable not found: {}", name),
+            AddConstraintError::ConstraintExists(name) => {
+                write!(f, "Constraint already exists: {}", name)
+            }
+        }
+    }
+}
+
+/// An error from a transaction. If one occurs, none of the edits are applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionError {
+    /// An edited component does not exist.
+    NoSuchComponent(String),
+    /// An edited variable does not exist.
+    NoSuchVariable(String),
+    /// The value of the edited variable could not be converted.
+    Conversion(String),
+    /// The edits were applied, but solving afterwards failed.
+    Plan(PlanError),
+}
+
+impl Display for TransactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionError::NoSuchComponent(name) => write!(f, "Component not found: {}", name),
+            TransactionError::NoSuchVariable(name) => write!(f, "Variable not found: {}", name),
+            TransactionError::Conversion(name) => {
+                write!(f, "Value of variable {} could not be converted", name)
+            }
+            TransactionError::Plan(pe) => pe.fmt(f),
+        }
+    }
+}
+
+impl From<PlanError> for TransactionError {
+    fn from(pe: PlanError) -> Self {
+        Self::Plan(pe)
+    }
+}
+
+/// An error from a query about the dependencies between variables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryError {
+    /// The component does not exist.
+    NoSuchComponent(String),
+    /// The variable does not exist.
+    NoSuchVariable(String),
+    /// Planning failed.
+    Plan(PlanError),
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::NoSuchComponent(name) => write!(f, "Component not found: {}", name),
+            QueryError::NoSuchVariable(name) => write!(f, "Variable not found: {}", name),
+            QueryError::Plan(pe) => pe.fmt(f),
+        }
+    }
+}
+
+impl From<PlanError> for QueryError {
+    fn from(pe: PlanError) -> Self {
+        Self::Plan(pe)
+    }
+}
+
+/// A plan was made for another component, or before the variables or constraints of the component changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlanMismatch(pub String);
+
+impl Display for PlanMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Plan does not match the variables of component {}",
+            self.0
+        )
+    }
+}
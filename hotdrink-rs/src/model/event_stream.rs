@@ -0,0 +1,66 @@
+//! A [`Stream`] of events from the variables of a component or constraint system.
+
+use super::{generation_id::GenerationId, subscription::Subscription};
+use crate::{event::OwnedEvent, solver::SolveError};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Stream, StreamExt,
+};
+use std::{
+    fmt::Debug,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// An event from an [`EventStream`], along with the names of the component and the variable it is from,
+/// and the generation it is from.
+pub type StreamedEvent<T> = (String, String, GenerationId, OwnedEvent<T, SolveError>);
+
+/// A [`Stream`] of events from the variables of a [`Component`](super::Component)
+/// or [`ConstraintSystem`](super::ConstraintSystem).
+///
+/// Events are buffered until they are read, and the stream stops receiving new ones when it is dropped.
+pub struct EventStream<T> {
+    receiver: UnboundedReceiver<StreamedEvent<T>>,
+    _subscription: Subscription,
+}
+
+impl<T> EventStream<T> {
+    /// Constructs a new [`EventStream`] with the events sent by the callback that `subscribe` subscribes.
+    pub(crate) fn new(
+        subscribe: impl FnOnce(UnboundedSender<StreamedEvent<T>>) -> Subscription,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded();
+        Self {
+            receiver,
+            _subscription: subscribe(sender),
+        }
+    }
+
+    /// Returns the next event that is not [`OwnedEvent::Pending`],
+    /// meaning that a value is ready or the computation failed.
+    pub async fn next_settled(&mut self) -> Option<StreamedEvent<T>> {
+        while let Some(item) = self.next().await {
+            if item.3.is_settled() {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = StreamedEvent<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl<T> Debug for EventStream<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("subscription", &self._subscription)
+            .finish()
+    }
+}
@@ -0,0 +1,126 @@
+//! Why a variable of a [`Component`](super::Component) was written to,
+//! see [`Component::explain`](super::Component::explain).
+
+use std::fmt::{self, Display};
+
+/// A method of the constraint that wrote a variable, which was not selected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RejectedMethod {
+    method: String,
+    overwrites: Vec<String>,
+}
+
+impl RejectedMethod {
+    /// Constructs a new [`RejectedMethod`].
+    pub(crate) fn new(method: String, overwrites: Vec<String>) -> Self {
+        Self { method, overwrites }
+    }
+
+    /// Returns the name of the method.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns the higher-priority variables that the method would have written to, from highest to lowest priority.
+    /// If there are none, the method was rejected since it conflicts with the methods selected for other constraints.
+    pub fn overwrites(&self) -> &[String] {
+        &self.overwrites
+    }
+}
+
+/// Why a variable was written to in the last plan, and which methods could have been used instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    variable: String,
+    /// The constraint and method that wrote to the variable.
+    writer: Option<(String, String)>,
+    forced_by: Vec<String>,
+    rejected: Vec<RejectedMethod>,
+}
+
+impl Explanation {
+    /// Constructs a new [`Explanation`] of a variable that was written to.
+    pub(crate) fn written(
+        variable: String,
+        constraint: String,
+        method: String,
+        forced_by: Vec<String>,
+        rejected: Vec<RejectedMethod>,
+    ) -> Self {
+        Self {
+            variable,
+            writer: Some((constraint, method)),
+            forced_by,
+            rejected,
+        }
+    }
+
+    /// Constructs a new [`Explanation`] of a variable that was not written to.
+    pub(crate) fn unwritten(variable: String) -> Self {
+        Self {
+            variable,
+            writer: None,
+            forced_by: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the explained variable.
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+
+    /// Returns the name of the constraint that wrote to the variable, or `None` if it was not written to.
+    pub fn constraint(&self) -> Option<&str> {
+        self.writer
+            .as_ref()
+            .map(|(constraint, _)| constraint.as_str())
+    }
+
+    /// Returns the name of the method that wrote to the variable, or `None` if it was not written to.
+    pub fn method(&self) -> Option<&str> {
+        self.writer.as_ref().map(|(_, method)| method.as_str())
+    }
+
+    /// Returns the higher-priority variables whose stay constraints forced the variable to be written to,
+    /// from highest to lowest priority.
+    /// These are the ones it was computed from, and the ones the rejected methods would have overwritten.
+    pub fn forced_by(&self) -> &[String] {
+        &self.forced_by
+    }
+
+    /// Returns the other methods of the constraint that wrote to the variable.
+    pub fn rejected(&self) -> &[RejectedMethod] {
+        &self.rejected
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (constraint, method) = match &self.writer {
+            Some(writer) => writer,
+            None => return write!(f, "{} was not written to", self.variable),
+        };
+        write!(
+            f,
+            "{} was written by {}.{}",
+            self.variable, constraint, method
+        )?;
+        if !self.forced_by.is_empty() {
+            write!(f, " to keep {}", self.forced_by.join(", "))?;
+        }
+        for rejected in &self.rejected {
+            write!(f, "\n{}.{} was rejected", constraint, rejected.method)?;
+            if rejected.overwrites.is_empty() {
+                write!(f, " since it conflicts with other methods")?;
+            } else {
+                write!(
+                    f,
+                    " since it would overwrite {}",
+                    rejected.overwrites.join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,23 +1,22 @@
 //! Extra information about a variable, such as its status, generation, and callbacks.
 
-use super::{activation::EventCallback, generation_id::GenerationId};
-use crate::event::{Event, EventWithLocation};
+use super::{activation::EventCallback, generation_id::GenerationId, subscription::SubscriptionId};
+use crate::event::EventWithLocation;
 use std::fmt::Debug;
-use std::sync::Arc;
 
 /// Information about a variable.
 ///
-/// More specifically, this struct contains its generation, current status, and a callback if one exists.
+/// More specifically, this struct contains its generation, current status, and the callbacks subscribed to it.
 pub(crate) struct FilteredCallback<T, E> {
     target: GenerationId,
-    callback: Option<EventCallback<T, E>>,
+    callbacks: Vec<(SubscriptionId, EventCallback<T, E>)>,
 }
 
 impl<T, E> Clone for FilteredCallback<T, E> {
     fn clone(&self) -> Self {
         Self {
             target: self.target,
-            callback: self.callback.clone(),
+            callbacks: self.callbacks.clone(),
         }
     }
 }
@@ -26,7 +25,7 @@ impl<T, E> Default for FilteredCallback<T, E> {
     fn default() -> Self {
         Self {
             target: Default::default(),
-            callback: None,
+            callbacks: Vec::new(),
         }
     }
 }
@@ -36,13 +35,17 @@ impl<T, E: Clone> FilteredCallback<T, E> {
     pub fn new() -> Self {
         Self::default()
     }
-    /// Sets the callback of the variable.
-    pub fn subscribe(&mut self, callback: impl Fn(Event<'_, T, E>) + Send + Sync + 'static) {
-        self.callback = Some(Arc::new(callback));
+    /// Adds a callback to the variable.
+    pub fn subscribe(&mut self, id: SubscriptionId, callback: EventCallback<T, E>) {
+        self.callbacks.push((id, callback));
     }
-    /// Removes the callback of the variable.
-    pub fn unsubscribe(&mut self) {
-        self.callback = None;
+    /// Removes the callback with the specified id.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.callbacks.retain(|(other, _)| *other != id);
+    }
+    /// Removes the callbacks whose ids do not satisfy `keep`.
+    pub fn retain(&mut self, keep: impl Fn(SubscriptionId) -> bool) {
+        self.callbacks.retain(|(id, _)| keep(*id));
     }
 
     /// Set the kind of events to respond to.
@@ -50,7 +53,7 @@ impl<T, E: Clone> FilteredCallback<T, E> {
         self.target = target;
     }
 
-    /// Calls the callback of the variable if one exists.
+    /// Calls the callbacks of the variable in the order they were added.
     ///
     /// Old events will be ignored, and new ones will update the current status of the variable.
     pub fn call(&self, ge: EventWithLocation<'_, T, E>) {
@@ -61,9 +64,10 @@ impl<T, E: Clone> FilteredCallback<T, E> {
             return;
         }
 
-        // Call callback
-        if let Some(callback) = &self.callback {
-            callback(ge.event());
+        // Call callbacks
+        let event = ge.event();
+        for (_, callback) in &self.callbacks {
+            callback(generation, event);
         }
     }
 }
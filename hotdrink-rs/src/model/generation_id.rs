@@ -24,4 +24,14 @@ impl GenerationId {
             total_generation,
         }
     }
+
+    /// Returns the generation the component was on.
+    pub fn current_generation(&self) -> usize {
+        self.current_generation
+    }
+
+    /// Returns the total amount of generations the component had had.
+    pub fn total_generation(&self) -> usize {
+        self.total_generation
+    }
 }
@@ -0,0 +1,57 @@
+//! Types for constraints between variables in different [`Component`](super::Component)s
+//! of a [`ConstraintSystem`](super::ConstraintSystem).
+
+use super::constraint::Constraint;
+use crate::planner::ConstraintSpec;
+
+/// A constraint between variables in different components.
+#[derive(derivative::Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""), PartialEq(bound = ""))]
+pub(crate) struct InterConstraint<T> {
+    /// The constraint, where variable `i` refers to `variables[i]`.
+    constraint: Constraint<T>,
+    /// The component name and variable name of each variable the constraint references.
+    /// Names are used rather than indices, since variables may be added to or removed from components.
+    variables: Vec<(String, String)>,
+    /// The names of the components the constraint references, in sorted order.
+    components: Vec<String>,
+}
+
+impl<T> InterConstraint<T> {
+    /// Constructs a new [`InterConstraint`], where variable `i` of `constraint` refers to `variables[i]`.
+    pub fn new(constraint: Constraint<T>, variables: Vec<(String, String)>) -> Self {
+        let mut components: Vec<String> = variables.iter().map(|(c, _)| c.clone()).collect();
+        components.sort();
+        components.dedup();
+        Self {
+            constraint,
+            variables,
+            components,
+        }
+    }
+
+    /// Returns the name of the constraint.
+    pub fn name(&self) -> &str {
+        self.constraint.name()
+    }
+
+    /// Returns the constraint, where variable `i` refers to the `i`th of [`variables`](Self::variables).
+    pub fn constraint(&self) -> &Constraint<T> {
+        &self.constraint
+    }
+
+    /// Returns the component name and variable name of each variable the constraint references.
+    pub fn variables(&self) -> &[(String, String)] {
+        &self.variables
+    }
+
+    /// Returns the names of the components the constraint references, in sorted order.
+    pub fn components(&self) -> &[String] {
+        &self.components
+    }
+
+    /// Returns the name used for the components of the constraint in errors and reports.
+    pub fn location(&self) -> String {
+        self.components.join("+")
+    }
+}
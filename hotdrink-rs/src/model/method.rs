@@ -5,16 +5,25 @@
 use super::generation_id::GenerationId;
 use crate::{
     event::{Event, EventWithLocation, Ready},
-    executor::MethodExecutor,
-    model::activation::{Activation, ActivationInner},
-    planner::{MethodFailure, MethodFunction, MethodResult, MethodSpec, Vertex},
+    executor::{drive, schedule_when_ready, watch, CancellationToken, MethodExecutor},
+    macros::raw_constraint::Condition,
+    model::activation::{Activation, ActivationInner, State},
+    planner::{
+        AsyncMethodFunction, MethodFailure, MethodFunction, MethodFunctionMut, MethodResult,
+        MethodSpec, Vertex,
+    },
     solver::{Reason, SolveError},
+    stats::{Instant, MethodRecorder, SharedStats},
 };
 use core::slice;
 use derivative::Derivative;
 use std::{
     fmt::Debug,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
+    time::Duration,
 };
 
 /// The inner representation of a [`Method`].
@@ -31,6 +40,94 @@ pub enum MethodInner<T> {
         #[derivative(Debug = "ignore", PartialEq = "ignore")]
         apply: MethodFunction<T>,
     },
+    /// A method that mutates some of its arguments in place.
+    InPlace {
+        name: String,
+        /// The variables passed to the function, in order.
+        arguments: Vec<usize>,
+        /// The arguments that are not mutated.
+        inputs: Vec<usize>,
+        /// The declared outputs, followed by the mutated arguments that are not among them.
+        outputs: Vec<usize>,
+        /// The positions of the mutated arguments in `arguments`.
+        mutable: Vec<usize>,
+        #[derivative(Debug = "ignore", PartialEq = "ignore")]
+        apply: MethodFunctionMut<T>,
+    },
+    /// A method whose function returns a future.
+    Async {
+        name: String,
+        inputs: Vec<usize>,
+        outputs: Vec<usize>,
+        #[derivative(Debug = "ignore", PartialEq = "ignore")]
+        apply: AsyncMethodFunction<T>,
+    },
+}
+
+/// The function of a method, as run by [`Method::activate`].
+enum Body<T> {
+    Sync(MethodFunctionMut<T>),
+    Async(AsyncMethodFunction<T>),
+}
+
+/// The precondition and postcondition of the [`Constraint`](super::Constraint) a method belongs to,
+/// along with the variables of the constraint they are checked against.
+pub(crate) struct Conditions<T> {
+    precondition: Option<Condition<T>>,
+    postcondition: Option<Condition<T>>,
+    variables: Vec<usize>,
+}
+
+impl<T> Conditions<T> {
+    /// Constructs new [`Conditions`] that are checked against the values of `variables`.
+    pub fn new(
+        precondition: Option<Condition<T>>,
+        postcondition: Option<Condition<T>>,
+        variables: Vec<usize>,
+    ) -> Self {
+        Self {
+            precondition,
+            postcondition,
+            variables,
+        }
+    }
+
+    /// Returns the variables the conditions are checked against.
+    pub fn variables(&self) -> &[usize] {
+        &self.variables
+    }
+
+    /// Returns true if `condition` is absent or holds for the values of the variables.
+    fn holds(&self, condition: &Option<Condition<T>>, values: &[Arc<T>]) -> bool {
+        let values: Vec<&T> = values.iter().map(|v| &**v).collect();
+        condition
+            .as_ref()
+            .map_or(true, |condition| condition(&values))
+    }
+}
+
+/// Decides whether a new output of a method is equal to the previous value of the variable.
+pub(crate) type OutputEquality<T> = Arc<dyn Fn(&T, &T) -> bool + Send + Sync>;
+
+/// The inputs of the latest call to a pure method, and the outputs it produced.
+///
+/// Inputs are compared by address, and are kept as weak references so that they can be dropped.
+/// This also stops them from being mutated in place without moving them to a new address.
+struct Memo<T> {
+    inputs: Vec<Weak<T>>,
+    outputs: Vec<Arc<T>>,
+}
+
+impl<T> Memo<T> {
+    /// Returns true if the memo was made from exactly these inputs.
+    fn matches(&self, inputs: &[Arc<T>]) -> bool {
+        self.inputs.len() == inputs.len()
+            && self
+                .inputs
+                .iter()
+                .zip(inputs)
+                .all(|(memo, input)| Weak::as_ptr(memo) == Arc::as_ptr(input))
+    }
 }
 
 /// A method for enforcing a [`Constraint`](super::Constraint).
@@ -40,6 +137,15 @@ pub enum MethodInner<T> {
 #[derivative(Clone(bound = ""), PartialEq(bound = ""), Eq)]
 pub struct Method<T> {
     inner: MethodInner<T>,
+    #[derivative(PartialEq = "ignore")]
+    conditions: Option<Arc<Conditions<T>>>,
+    timeout: Option<Duration>,
+    pure: bool,
+    /// Shared between copies of the method, so that it outlives each plan.
+    #[derivative(PartialEq = "ignore")]
+    memo: Arc<Mutex<Option<Memo<T>>>>,
+    #[derivative(PartialEq = "ignore")]
+    output_equality: Option<OutputEquality<T>>,
 }
 
 impl<T> Debug for Method<T> {
@@ -70,19 +176,37 @@ impl<T> MethodSpec for Method<T> {
                 outputs,
                 apply,
             },
+            conditions: None,
+            timeout: None,
+            pure: false,
+            memo: Arc::default(),
+            output_equality: None,
         }
     }
 
     /// Apply the inner function of this method
     fn apply(&self, input: Vec<Arc<T>>) -> MethodResult<Arc<T>> {
         // Verify that all inputs are defined
-        if input.len() != self.n_inputs() {
-            return Err(MethodFailure::WrongInputCount(self.n_inputs(), input.len()));
+        let n_arguments = self.arguments().len();
+        if input.len() != n_arguments {
+            return Err(MethodFailure::WrongInputCount(n_arguments, input.len()));
         }
         // Compute output
         let output = match &self.inner {
             MethodInner::Stay(_) => input,
             MethodInner::Normal { apply, .. } => apply(input)?,
+            MethodInner::InPlace {
+                arguments,
+                outputs,
+                mutable,
+                apply,
+                ..
+            } => {
+                let mut input = input;
+                let returned = apply(&mut input, &CancellationToken::new())?;
+                merge_in_place(arguments, outputs, mutable, &input, returned)
+            }
+            MethodInner::Async { apply, .. } => futures::executor::block_on(apply(input))?,
         };
         // Verify that all outputs are defined
         if output.len() != self.n_outputs() {
@@ -98,11 +222,32 @@ impl<T> MethodSpec for Method<T> {
     fn name(&self) -> Option<&str> {
         match &self.inner {
             MethodInner::Stay(_) => None,
-            MethodInner::Normal { name, .. } => Some(name),
+            MethodInner::Normal { name, .. }
+            | MethodInner::InPlace { name, .. }
+            | MethodInner::Async { name, .. } => Some(name),
         }
     }
 }
 
+/// Appends the mutated arguments that were not returned by the function to its outputs.
+fn merge_in_place<T>(
+    arguments: &[usize],
+    outputs: &[usize],
+    mutable: &[usize],
+    args: &[Arc<T>],
+    mut returned: Vec<Arc<T>>,
+) -> Vec<Arc<T>> {
+    if returned.len() < outputs.len() {
+        for &o in &outputs[returned.len()..] {
+            match mutable.iter().find(|&&p| arguments[p] == o) {
+                Some(&p) => returned.push(args[p].clone()),
+                None => break,
+            }
+        }
+    }
+    returned
+}
+
 fn handle_error<T>(
     output_indices: &[usize],
     shared_states: &[Arc<RwLock<ActivationInner<T>>>],
@@ -115,20 +260,253 @@ fn handle_error<T>(
     }
     for shared_state in shared_states.iter() {
         shared_state.write().unwrap().set_error(errors.clone());
+        ActivationInner::notify_listeners(shared_state);
     }
 }
 
 impl<T> Method<T> {
+    /// Constructs a new [`Method`] that can mutate some of its arguments in place.
+    ///
+    /// The variables in `arguments` are passed to `apply` in order,
+    /// and the ones at the positions in `mutable` may be mutated by it.
+    /// These also become outputs of the method, after the ones in `outputs`.
+    /// The function should return the values of `outputs`.
+    /// If a mutated argument is also in `outputs`, the returned value is used.
+    ///
+    /// Arguments are mutated without copying them if nothing else refers to their values.
+    /// Values kept for undo are never mutated, so disable undo with
+    /// [`Component::set_undo_limit`](super::Component::set_undo_limit) to avoid copies.
+    pub fn new_in_place(
+        name: String,
+        arguments: Vec<usize>,
+        mutable: Vec<usize>,
+        mut outputs: Vec<usize>,
+        apply: MethodFunctionMut<T>,
+    ) -> Self {
+        let inputs = arguments
+            .iter()
+            .enumerate()
+            .filter(|(p, _)| !mutable.contains(p))
+            .map(|(_, &a)| a)
+            .collect();
+        for &p in &mutable {
+            if !outputs.contains(&arguments[p]) {
+                outputs.push(arguments[p]);
+            }
+        }
+        Self {
+            inner: MethodInner::InPlace {
+                name,
+                arguments,
+                inputs,
+                outputs,
+                mutable,
+                apply,
+            },
+            conditions: None,
+            timeout: None,
+            pure: false,
+            memo: Arc::default(),
+            output_equality: None,
+        }
+    }
+
+    /// Constructs a new [`Method`] whose function can see if its result is still needed.
+    ///
+    /// The [`CancellationToken`] passed to `apply` is cancelled once nobody needs
+    /// the values of the outputs anymore, in which case the result of `apply` is discarded.
+    pub fn new_cancellable(
+        name: String,
+        inputs: Vec<usize>,
+        outputs: Vec<usize>,
+        apply: MethodFunctionMut<T>,
+    ) -> Self {
+        Self::new_in_place(name, inputs, Vec::new(), outputs, apply)
+    }
+
+    /// Constructs a new [`Method`] whose function returns a future.
+    ///
+    /// The future is polled on the [`MethodExecutor`] the method is run on,
+    /// and is dropped if the values of all its outputs are cancelled before it completes.
+    pub fn new_async(
+        name: String,
+        inputs: Vec<usize>,
+        outputs: Vec<usize>,
+        apply: AsyncMethodFunction<T>,
+    ) -> Self {
+        Self {
+            inner: MethodInner::Async {
+                name,
+                inputs,
+                outputs,
+                apply,
+            },
+            conditions: None,
+            timeout: None,
+            pure: false,
+            memo: Arc::default(),
+            output_equality: None,
+        }
+    }
+
+    /// Returns the variables whose values are passed to the function of the method.
+    /// Unlike [`Vertex::inputs`], this includes the variables it mutates in place.
+    pub fn arguments(&self) -> &[usize] {
+        match &self.inner {
+            MethodInner::InPlace { arguments, .. } => arguments,
+            _ => self.inputs(),
+        }
+    }
+
+    /// Returns the variables that are mutated in place by the method.
+    pub fn mutated(&self) -> Vec<usize> {
+        match &self.inner {
+            MethodInner::InPlace {
+                arguments, mutable, ..
+            } => mutable.iter().map(|&p| arguments[p]).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns a copy of the method where each variable index `i` is replaced by `f(i)`.
+    pub(crate) fn reindexed(&self, f: impl Fn(usize) -> usize) -> Self {
+        let inner = match &self.inner {
+            MethodInner::Stay(index) => MethodInner::Stay(f(*index)),
+            MethodInner::Normal {
+                name,
+                inputs,
+                outputs,
+                apply,
+            } => MethodInner::Normal {
+                name: name.clone(),
+                inputs: inputs.iter().map(|&i| f(i)).collect(),
+                outputs: outputs.iter().map(|&o| f(o)).collect(),
+                apply: apply.clone(),
+            },
+            MethodInner::InPlace {
+                name,
+                arguments,
+                inputs,
+                outputs,
+                mutable,
+                apply,
+            } => MethodInner::InPlace {
+                name: name.clone(),
+                arguments: arguments.iter().map(|&a| f(a)).collect(),
+                inputs: inputs.iter().map(|&i| f(i)).collect(),
+                outputs: outputs.iter().map(|&o| f(o)).collect(),
+                mutable: mutable.clone(),
+                apply: apply.clone(),
+            },
+            MethodInner::Async {
+                name,
+                inputs,
+                outputs,
+                apply,
+            } => MethodInner::Async {
+                name: name.clone(),
+                inputs: inputs.iter().map(|&i| f(i)).collect(),
+                outputs: outputs.iter().map(|&o| f(o)).collect(),
+                apply: apply.clone(),
+            },
+        };
+        let conditions = self.conditions.as_ref().map(|c| {
+            Arc::new(Conditions::new(
+                c.precondition.clone(),
+                c.postcondition.clone(),
+                c.variables.iter().map(|&vi| f(vi)).collect(),
+            ))
+        });
+        Self {
+            inner,
+            conditions,
+            timeout: self.timeout,
+            pure: self.pure,
+            memo: self.memo.clone(),
+            output_equality: self.output_equality.clone(),
+        }
+    }
+
+    /// Returns the conditions of the constraint the method belongs to, if it has any.
+    pub(crate) fn conditions(&self) -> Option<&Conditions<T>> {
+        self.conditions.as_deref()
+    }
+
+    /// Sets the conditions to check when running the method.
+    pub(crate) fn set_conditions(&mut self, conditions: Option<Arc<Conditions<T>>>) {
+        self.conditions = conditions;
+    }
+
+    /// Returns the maximum time the method may run for, if it has one.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sets the maximum time the method may run for.
+    ///
+    /// If it has not finished by then, its outputs get [`Reason::Timeout`] errors,
+    /// its [`CancellationToken`] is cancelled, and so is the [`TerminationHandle`](crate::executor::TerminationHandle)
+    /// returned by the executor. Overrides the timeout of the component it belongs to.
+    /// A synchronous function keeps running after it times out, unless it checks its [`CancellationToken`].
+    /// Timeouts are not supported on `wasm32`, where the method runs without one and an error is logged.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Returns true if the method is pure, see [`set_pure`](Self::set_pure).
+    pub fn is_pure(&self) -> bool {
+        self.pure
+    }
+
+    /// Sets whether the method is pure, meaning that its outputs only depend on its inputs.
+    ///
+    /// A pure method is not run again if its inputs are the same values as in its latest call.
+    /// Instead, it reuses the outputs of that call. Inputs are compared by address,
+    /// so a value that is computed again is considered new unless [`compare_outputs`](Self::compare_outputs)
+    /// is used by the method computing it. The outputs are kept until the method is run again.
+    ///
+    /// Methods that mutate their arguments, and methods of constraints with conditions, are always run.
+    pub fn set_pure(&mut self, pure: bool) {
+        self.pure = pure;
+        if !pure {
+            *self.memo.lock().unwrap() = None;
+        }
+    }
+
+    /// Compares the outputs of the method to the previous values of the variables.
+    ///
+    /// Outputs that are equal to their previous values give [`Ready::Unchanged`] events,
+    /// and the previous values are kept. Pure methods that read them are then not run again.
+    pub fn compare_outputs(&mut self)
+    where
+        T: PartialEq + 'static,
+    {
+        self.output_equality = Some(Arc::new(|previous: &T, new: &T| previous == new));
+    }
+
+    /// Sets the function to compare outputs with, see [`compare_outputs`](Self::compare_outputs).
+    pub(crate) fn set_output_equality(&mut self, output_equality: Option<OutputEquality<T>>) {
+        self.output_equality = output_equality;
+    }
+
     /// Calls the method with the provided arguments, but spawns off the computation in a different thread.
     /// Instead of waiting for the values to arrive, return a list of `Value`s that will eventually resolve to them.
+    ///
+    /// If the method has [`Conditions`], `condition_values` must contain the values of their variables before the method is run.
+    /// The method gives up once it has run for longer than `timeout`, if provided.
+    /// Its calls are recorded in `stats`, if provided.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn activate(
         &self,
         inputs: Vec<impl Into<Activation<T>>>,
+        condition_values: Vec<Activation<T>>,
         shared_states: Vec<Arc<RwLock<ActivationInner<T>>>>,
         location: (String, String),
         generation: GenerationId,
+        timeout: Option<Duration>,
+        stats: Option<&SharedStats>,
         me: &impl MethodExecutor,
-        general_callback: impl Fn(EventWithLocation<'_, T, SolveError>) + Send + 'static,
+        general_callback: impl Fn(EventWithLocation<'_, T, SolveError>) + Send + Clone + 'static,
     ) -> Vec<Activation<T>>
     where
         T: Send + Sync + 'static + Debug,
@@ -138,7 +516,7 @@ impl<T> Method<T> {
         // Using `Into<Value<T>>` for convenience, so that
         // one can pass in a vector of non-futures too.
         let inputs: Vec<Activation<T>> = inputs.into_iter().map(|v| v.into()).collect();
-        let n_inputs = self.n_inputs();
+        let n_inputs = self.arguments().len();
         let n_outputs = self.n_outputs();
         let output_indices = self.outputs().to_vec();
         let m_name = self.name().unwrap_or("None").to_string();
@@ -146,6 +524,20 @@ impl<T> Method<T> {
         let component_clone = component.clone();
         let constraint_clone = constraint.clone();
         let method_clone = m_name.clone();
+        let recorder = MethodRecorder::new(stats, &component, &constraint, &m_name);
+        let recorder_clone = recorder.clone();
+        let activated_at = Instant::now();
+
+        // The method may run on another thread, so the span is created here to get the solve as its parent
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "method",
+            component = %component,
+            constraint = %constraint,
+            method = %m_name,
+            inputs = ?self.inputs(),
+            outputs = ?self.outputs(),
+        );
 
         log::trace!("Activating {}", &m_name);
 
@@ -153,57 +545,178 @@ impl<T> Method<T> {
         let shared_states_clone = shared_states.clone();
 
         // We need a clone of the computation to move into the thread
-        let f = match &self.inner {
-            MethodInner::Stay(_) => Arc::new(Ok),
-            MethodInner::Normal { apply, .. } => apply.clone(),
+        let body = match &self.inner {
+            MethodInner::Stay(_) => {
+                Body::Sync(Arc::new(|args: &mut [Arc<T>], _: &_| Ok(args.to_vec())))
+            }
+            MethodInner::Normal { apply, .. } => {
+                let apply = apply.clone();
+                Body::Sync(Arc::new(move |args: &mut [Arc<T>], _: &_| {
+                    apply(args.to_vec())
+                }))
+            }
+            MethodInner::InPlace { apply, .. } => Body::Sync(apply.clone()),
+            MethodInner::Async { apply, .. } => Body::Async(apply.clone()),
         };
+        let spawner = me.spawner();
+        let token = CancellationToken::new();
+        let token_clone = token.clone();
+        // Set once the method is scheduled, so that a timeout can cancel its handle
+        let result_needed = Arc::new(Mutex::new(None::<Arc<AtomicBool>>));
+        let result_needed_clone = result_needed.clone();
+        // Set by whichever comes first of the result and the timeout
+        let settled = Arc::new(AtomicBool::new(false));
+        let settled_clone = settled.clone();
+        let arguments = self.arguments().to_vec();
+        let mutable = match &self.inner {
+            MethodInner::InPlace { mutable, .. } => mutable.clone(),
+            _ => Vec::new(),
+        };
+        let conditions = self.conditions.clone();
+        let memo = Some(self.memo.clone())
+            .filter(|_| self.pure && !self.is_stay() && mutable.is_empty() && conditions.is_none());
+        let output_equality = self.output_equality.clone();
+        let dependencies: Vec<Activation<T>> = inputs
+            .iter()
+            .chain(&condition_values)
+            .map(Activation::weak_clone)
+            .collect();
 
-        // Run the computation in another thread, which
-        // will eventually put the computed values in
+        // Run the computation in another thread once the inputs are resolved,
+        // which will eventually put the computed values in
         // the shared_state slots.
-        let handle = me
-            .schedule(move || {
-                // Block on all the futures. This is ok
-                // since we are not on the main thread.
-                let joined_inputs = futures::future::join_all(inputs);
-                let input_results = futures::executor::block_on(joined_inputs);
-                let formatted_inputs = format!("{:?}", &input_results);
-
-                // Split ok and erroneous inputs
-                let mut inputs = Vec::new();
-                let mut errors = Vec::new();
-                for state in input_results {
-                    match state {
-                        Ok(value) => inputs.push(value),
-                        Err((value, error_data)) => {
-                            inputs.push(value);
-                            errors.extend(error_data.errors().clone());
-                        }
+        let handle = schedule_when_ready(me, dependencies, move || {
+            #[cfg(feature = "tracing")]
+            let _entered = span.enter();
+            // Block on all the futures. They are already resolved
+            // unless the executor can not defer work, in which case
+            // we are not on the main thread.
+            let joined_inputs = futures::future::join_all(inputs);
+            let input_results = futures::executor::block_on(joined_inputs);
+            if token.is_cancelled() {
+                log::trace!("Skipping cancelled method {}", m_name);
+                return;
+            }
+            recorder.started(activated_at.elapsed());
+            let started_at = Instant::now();
+            let formatted_inputs = format!("{:?}", &input_results);
+
+            // Split ok and erroneous inputs
+            let mut inputs = Vec::new();
+            let mut errors = Vec::new();
+            for state in input_results {
+                match state {
+                    Ok(value) => inputs.push(value),
+                    Err((value, error_data)) => {
+                        inputs.push(value);
+                        errors.extend(error_data.errors().clone());
                     }
                 }
+            }
+
+            // Only remember calls without errors in their input
+            let memo = memo.filter(|_| errors.is_empty());
 
-                // If any errors in input, propagate to outputs
-                if !errors.is_empty() {
+            // If any errors in input, propagate to outputs
+            if !errors.is_empty() {
+                handle_error(
+                    &output_indices,
+                    &shared_states_clone,
+                    &general_callback,
+                    generation,
+                    errors,
+                );
+            }
+
+            // Check that all inputs are provided
+            if inputs.len() != n_inputs {
+                let error = SolveError::new(
+                    component.to_owned(),
+                    constraint.to_owned(),
+                    m_name.clone(),
+                    Reason::MethodFailure(MethodFailure::WrongInputCount(n_inputs, inputs.len())),
+                );
+                recorder.failed();
+                settled.store(true, Ordering::SeqCst);
+                handle_error(
+                    &output_indices,
+                    &shared_states_clone,
+                    &general_callback,
+                    generation,
+                    vec![error],
+                );
+                return;
+            }
+
+            // Check the precondition against the values before running the method
+            let condition_values: Vec<Arc<T>> = if conditions.is_some() {
+                futures::executor::block_on(futures::future::join_all(condition_values))
+                    .into_iter()
+                    .map(|state| match state {
+                        Ok(value) | Err((value, _)) => value,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            if let Some(conditions) = &conditions {
+                if !conditions.holds(&conditions.precondition, &condition_values) {
+                    let error = SolveError::new(
+                        component.to_owned(),
+                        constraint.to_owned(),
+                        m_name.clone(),
+                        Reason::PreConditionFailure(constraint.to_owned()),
+                    );
+                    recorder.failed();
+                    settled.store(true, Ordering::SeqCst);
                     handle_error(
                         &output_indices,
                         &shared_states_clone,
                         &general_callback,
                         generation,
-                        errors,
+                        vec![error],
                     );
+                    return;
                 }
+            }
 
-                // Check that all inputs are provided
-                if inputs.len() != n_inputs {
-                    let error = SolveError::new(
-                        component.to_owned(),
-                        constraint.to_owned(),
-                        m_name.clone(),
-                        Reason::MethodFailure(MethodFailure::WrongInputCount(
-                            n_inputs,
-                            inputs.len(),
-                        )),
-                    );
+            // Forget the values of the outputs, since they are replaced after running the method.
+            // Otherwise, they would have to be copied to be mutated in place.
+            let mut condition_values: Vec<Option<Arc<T>>> = match &conditions {
+                Some(conditions) => conditions
+                    .variables
+                    .iter()
+                    .zip(condition_values)
+                    .map(|(vi, value)| Some(value).filter(|_| !output_indices.contains(vi)))
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            // Give up on the method if it does not finish in time
+            let watchdog = timeout.and_then(|timeout| {
+                let output_indices = output_indices.clone();
+                let shared_states_clone = shared_states_clone.clone();
+                let general_callback = general_callback.clone();
+                let token = token.clone();
+                let settled = settled.clone();
+                let recorder = recorder.clone();
+                let error = SolveError::new(
+                    component.to_owned(),
+                    constraint.to_owned(),
+                    m_name.clone(),
+                    Reason::Timeout,
+                );
+                let watched = watch(timeout, move || {
+                    // Finished or superseded methods have nothing left to time out
+                    if settled.swap(true, Ordering::SeqCst) || token.is_cancelled() {
+                        return;
+                    }
+                    log::info!("{} timed out after {:?}", error, timeout);
+                    recorder.failed();
+                    token.cancel();
+                    if let Some(result_needed) = &*result_needed_clone.lock().unwrap() {
+                        result_needed.store(false, Ordering::SeqCst);
+                    }
                     handle_error(
                         &output_indices,
                         &shared_states_clone,
@@ -211,28 +724,147 @@ impl<T> Method<T> {
                         generation,
                         vec![error],
                     );
-                    return;
+                });
+                match watched {
+                    Ok(watchdog) => Some(watchdog),
+                    Err(e) => {
+                        log::error!("Running {} without a timeout: {}", m_name, e);
+                        None
+                    }
                 }
+            });
 
-                // Compute the result
-                let result = f(inputs);
-                log::info!("{}({}) = {:?}", m_name, formatted_inputs, result);
+            // Reuse the outputs of the latest call if the inputs are the same
+            let memo_inputs: Option<Vec<Weak<T>>> = memo
+                .as_ref()
+                .map(|_| inputs.iter().map(Arc::downgrade).collect());
+            let memoized_outputs = memo.as_ref().and_then(|memo| {
+                memo.lock()
+                    .unwrap()
+                    .as_ref()
+                    .filter(|memo| memo.matches(&inputs))
+                    .map(|memo| memo.outputs.clone())
+            });
+            if memoized_outputs.is_some() {
+                log::trace!("Reusing outputs of pure method {}", m_name);
+            }
 
-                // Inspect it
-                match result {
-                    // The method call was successful, zip the values into the shared states
-                    Ok(outputs) => {
-                        // Undefined output variables
-                        if outputs.len() != n_outputs {
+            // Inspect the result once it is computed
+            let finish = {
+                let output_indices = output_indices.clone();
+                let shared_states_clone = shared_states_clone.clone();
+                let token = token.clone();
+                move |result: MethodResult<Arc<T>>| {
+                    let _watchdog = watchdog;
+                    recorder.finished(started_at.elapsed());
+                    log::info!("{}({}) = {:?}", m_name, formatted_inputs, result);
+                    // The outputs of a cancelled or timed out method already have an error
+                    if settled.swap(true, Ordering::SeqCst) || token.is_cancelled() {
+                        log::trace!("Discarding result of cancelled method {}", m_name);
+                        return;
+                    }
+                    match result {
+                        // The method call was successful, zip the values into the shared states
+                        Ok(outputs) => {
+                            // Undefined output variables
+                            if outputs.len() != n_outputs {
+                                let error = SolveError::new(
+                                    component.to_owned(),
+                                    constraint.to_owned(),
+                                    m_name.clone(),
+                                    Reason::MethodFailure(MethodFailure::WrongOutputCount(
+                                        n_outputs,
+                                        outputs.len(),
+                                    )),
+                                );
+                                recorder.failed();
+                                handle_error(
+                                    &output_indices,
+                                    &shared_states_clone,
+                                    &general_callback,
+                                    generation,
+                                    vec![error],
+                                );
+                                return;
+                            }
+                            // Check the postcondition against the values after running the method
+                            if let Some(conditions) = &conditions {
+                                for (value, o) in outputs.iter().zip(&output_indices) {
+                                    if let Some(position) =
+                                        conditions.variables.iter().position(|vi| vi == o)
+                                    {
+                                        condition_values[position] = Some(value.clone());
+                                    }
+                                }
+                                let condition_values: Vec<Arc<T>> =
+                                    condition_values.into_iter().flatten().collect();
+                                if !conditions.holds(&conditions.postcondition, &condition_values) {
+                                    let error = SolveError::new(
+                                        component.to_owned(),
+                                        constraint.to_owned(),
+                                        m_name.clone(),
+                                        Reason::PostConditionFailure(constraint.to_owned()),
+                                    );
+                                    recorder.failed();
+                                    handle_error(
+                                        &output_indices,
+                                        &shared_states_clone,
+                                        &general_callback,
+                                        generation,
+                                        vec![error],
+                                    );
+                                    return;
+                                }
+                            }
+                            // Keep the previous values of outputs that are unchanged
+                            let outputs: Vec<(Arc<T>, bool)> = outputs
+                                .into_iter()
+                                .zip(&shared_states_clone)
+                                .map(|(res, st)| {
+                                    let previous = output_equality.as_ref().and_then(|eq| {
+                                        st.read().unwrap().previous_value().filter(|previous| {
+                                            Arc::ptr_eq(previous, &res) || eq(previous, &res)
+                                        })
+                                    });
+                                    match previous {
+                                        Some(previous) => (previous, false),
+                                        None => (res, true),
+                                    }
+                                })
+                                .collect();
+                            if let (Some(memo), Some(memo_inputs)) = (memo, memo_inputs) {
+                                *memo.lock().unwrap() = Some(Memo {
+                                    inputs: memo_inputs,
+                                    outputs: outputs.iter().map(|(res, _)| res.clone()).collect(),
+                                });
+                            }
+                            // Place values in slots, and send ready events
+                            for ((st, (res, changed)), &o) in
+                                shared_states_clone.iter().zip(outputs).zip(&output_indices)
+                            {
+                                let ready = match changed {
+                                    true => Ready::Changed(&*res),
+                                    false => Ready::Unchanged,
+                                };
+                                general_callback(EventWithLocation::new(
+                                    o,
+                                    generation,
+                                    Event::Ready(ready),
+                                ));
+                                // Set the new value
+                                st.write().unwrap().set_value_arc(res.clone());
+                                ActivationInner::notify_listeners(st);
+                            }
+                        }
+                        // The method call failed
+                        Err(e) => {
                             let error = SolveError::new(
                                 component.to_owned(),
                                 constraint.to_owned(),
                                 m_name.clone(),
-                                Reason::MethodFailure(MethodFailure::WrongOutputCount(
-                                    n_outputs,
-                                    outputs.len(),
-                                )),
+                                Reason::MethodFailure(e),
                             );
+                            recorder.failed();
                             handle_error(
                                 &output_indices,
                                 &shared_states_clone,
@@ -240,41 +872,65 @@ impl<T> Method<T> {
                                 generation,
                                 vec![error],
                             );
-                            return;
-                        }
-                        // Place values in slots, and send ready events
-                        for ((st, res), &o) in
-                            shared_states_clone.iter().zip(outputs).zip(&output_indices)
-                        {
-                            general_callback(EventWithLocation::new(
-                                o,
-                                generation,
-                                Event::Ready(Ready::Changed(&res)),
-                            ));
-                            let mut shared_state = st.write().unwrap();
-                            // Set the new value
-                            shared_state.set_value_arc(res);
                         }
                     }
-                    // The method call failed
-                    Err(e) => {
-                        let error = SolveError::new(
-                            component.to_owned(),
-                            constraint.to_owned(),
-                            m_name.clone(),
-                            Reason::MethodFailure(e),
-                        );
-                        handle_error(
-                            &output_indices,
-                            &shared_states_clone,
-                            &general_callback,
-                            generation,
-                            vec![error],
-                        );
+                }
+            };
+
+            if let Some(outputs) = memoized_outputs {
+                drop(inputs);
+                finish(Ok(outputs));
+                return;
+            }
+
+            match body {
+                Body::Sync(f) => {
+                    // Borrow the values to mutate from the previous activations of the outputs
+                    let lent: Vec<_> = mutable
+                        .iter()
+                        .map(|&p| {
+                            let previous = output_indices
+                                .iter()
+                                .position(|&o| o == arguments[p])
+                                .and_then(|oi| {
+                                    ActivationInner::lend_previous(&shared_states_clone[oi])
+                                });
+                            (p, previous)
+                        })
+                        .collect();
+
+                    // Compute the result
+                    let result = f(&mut inputs, &token);
+
+                    // Give the borrowed values back, possibly mutated
+                    for (p, previous) in lent {
+                        if let Some(previous) = previous {
+                            previous.write().unwrap().set_value_arc(inputs[p].clone());
+                            ActivationInner::notify_listeners(&previous);
+                        }
                     }
+                    let result = result.map(|returned| {
+                        merge_in_place(&arguments, &output_indices, &mutable, &inputs, returned)
+                    });
+                    drop(inputs);
+                    finish(result);
                 }
-            })
-            .expect("Could not spawn worker");
+                Body::Async(f) => {
+                    // Poll the future on the executor, and stop once nobody is waiting for the outputs
+                    let future = f(inputs);
+                    #[cfg(feature = "tracing")]
+                    let future = tracing::Instrument::instrument(future, span.clone());
+                    let is_cancelled = move || {
+                        shared_states_clone
+                            .iter()
+                            .all(|st| !matches!(st.read().unwrap().state(), State::Pending(_)))
+                    };
+                    drive(spawner, future, is_cancelled, finish);
+                }
+            }
+        })
+        .expect("Could not spawn worker");
+        *result_needed.lock().unwrap() = Some(handle.result_needed());
 
         // Wrap the shared states and the thread references in `Value`s
         let output: Vec<Activation<T>> = shared_states
@@ -288,6 +944,10 @@ impl<T> Method<T> {
         // Set activation values to canceled when their values are no longer needed
         let mut output_clone: Vec<Activation<T>> = output.iter().map(|a| a.weak_clone()).collect();
         handle.on_drop(move || {
+            // Methods that have not finished or timed out are stopped
+            if !settled_clone.load(Ordering::SeqCst) {
+                recorder_clone.cancelled();
+            }
             for ss in &mut output_clone {
                 ss.cancel(SolveError::new(
                     component_clone.clone(),
@@ -296,6 +956,8 @@ impl<T> Method<T> {
                     Reason::Cancelled,
                 ));
             }
+            // Let the method know, so that it can stop early
+            token_clone.cancel();
         });
 
         output
@@ -307,20 +969,29 @@ impl<T> Vertex for Method<T> {
     fn inputs(&self) -> &[usize] {
         match &self.inner {
             MethodInner::Stay(index) => slice::from_ref(index),
-            MethodInner::Normal { inputs, .. } => inputs,
+            MethodInner::Normal { inputs, .. }
+            | MethodInner::InPlace { inputs, .. }
+            | MethodInner::Async { inputs, .. } => inputs,
         }
     }
     /// Get the indices of the outputs to this method
     fn outputs(&self) -> &[usize] {
         match &self.inner {
             MethodInner::Stay(index) => slice::from_ref(index),
-            MethodInner::Normal { outputs, .. } => outputs,
+            MethodInner::Normal { outputs, .. }
+            | MethodInner::InPlace { outputs, .. }
+            | MethodInner::Async { outputs, .. } => outputs,
         }
     }
 
     fn stay(index: usize) -> Self {
         Self {
             inner: MethodInner::Stay(index),
+            conditions: None,
+            timeout: None,
+            pure: false,
+            memo: Arc::default(),
+            output_equality: None,
         }
     }
 
@@ -9,22 +9,39 @@
 
 pub(crate) mod activation;
 mod component;
+mod component_plan;
 mod constraint;
 mod constraint_system;
 mod constraint_system_builder;
 pub(crate) mod errors;
+mod event_stream;
+mod explanation;
 pub(crate) mod filtered_callback;
 pub(crate) mod generation_id;
+mod inter_constraint;
 mod method;
+mod plan_cache;
+mod solve_handle;
+mod subscription;
+mod transaction;
 pub mod undo;
 mod variable;
 pub(crate) mod variables;
 
 pub use activation::{Activation, Value};
 pub use component::Component;
+pub use component_plan::ComponentPlan;
 pub use constraint::Constraint;
 pub use constraint_system::ConstraintSystem;
 pub use constraint_system_builder::ConstraintSystemBuilder;
+pub use event_stream::{EventStream, StreamedEvent};
+pub use explanation::{Explanation, RejectedMethod};
+pub use generation_id::GenerationId;
 
 pub use method::Method;
+pub(crate) use method::OutputEquality;
+pub use plan_cache::PlanCache;
+pub use solve_handle::SolveHandle;
+pub use subscription::{Subscription, SubscriptionId};
+pub use transaction::{SystemTransaction, Transaction};
 pub use variable::Variable;
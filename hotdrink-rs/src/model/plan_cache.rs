@@ -0,0 +1,183 @@
+//! A cache of the most recent plans of a [`Component`](super::Component),
+//! see [`Component::enable_plan_cache`](super::Component::enable_plan_cache).
+
+use super::method::Method;
+use crate::planner::OwnedPlan;
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug},
+    sync::Mutex,
+};
+
+/// What a plan of a component depends on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PlanKey {
+    /// The ranking of the variables that the active constraints can write to.
+    /// The ranking of other variables does not affect which methods are selected.
+    ranking: Vec<usize>,
+    /// The names of the active constraints.
+    active: Vec<String>,
+    /// The pinned variables.
+    pinned: Vec<usize>,
+}
+
+impl PlanKey {
+    /// Constructs a new [`PlanKey`].
+    pub fn new(ranking: Vec<usize>, active: Vec<String>, pinned: Vec<usize>) -> Self {
+        Self {
+            ranking,
+            active,
+            pinned,
+        }
+    }
+}
+
+struct PlanCacheInner<T> {
+    capacity: usize,
+    /// The plans, with the most recently used one first.
+    entries: VecDeque<(PlanKey, OwnedPlan<Method<T>>)>,
+    hits: usize,
+    misses: usize,
+}
+
+/// The most recently used plans of a component, along with how often they were reused.
+///
+/// Plans are looked up by the ranking of the variables that can be written to,
+/// the active constraints and the pinned variables.
+/// All plans are removed when the constraints, variables or planner of the component change.
+pub struct PlanCache<T> {
+    inner: Mutex<PlanCacheInner<T>>,
+}
+
+impl<T> PlanCache<T> {
+    /// Constructs a new [`PlanCache`] that keeps at most `capacity` plans.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(PlanCacheInner {
+                capacity,
+                entries: VecDeque::with_capacity(capacity),
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// Returns the maximum number of plans that are kept.
+    pub fn capacity(&self) -> usize {
+        self.inner.lock().unwrap().capacity
+    }
+
+    /// Returns the number of plans that are kept.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Returns true if no plans are kept.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of times a plan was reused.
+    pub fn hits(&self) -> usize {
+        self.inner.lock().unwrap().hits
+    }
+
+    /// Returns the number of times a plan had to be made.
+    pub fn misses(&self) -> usize {
+        self.inner.lock().unwrap().misses
+    }
+
+    /// Returns the plan for `key` if it is kept, and counts the lookup as a hit or miss.
+    pub(crate) fn get(&self, key: &PlanKey) -> Option<OwnedPlan<Method<T>>> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.entries.iter().position(|(k, _)| k == key) {
+            Some(position) => {
+                inner.hits += 1;
+                let entry = inner
+                    .entries
+                    .remove(position)
+                    .expect("position is in range");
+                let plan = entry.1.clone();
+                inner.entries.push_front(entry);
+                Some(plan)
+            }
+            None => {
+                inner.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Keeps the plan for `key`, and removes the least recently used one if there are too many.
+    pub(crate) fn insert(&self, key: PlanKey, plan: OwnedPlan<Method<T>>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.push_front((key, plan));
+        let capacity = inner.capacity;
+        inner.entries.truncate(capacity);
+    }
+
+    /// Removes all plans, but keeps the counters.
+    pub(crate) fn invalidate(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+}
+
+/// Clones start out empty with the same capacity,
+/// since planners clone the component every time they make a plan.
+impl<T> Clone for PlanCache<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.capacity())
+    }
+}
+
+impl<T> Debug for PlanCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("PlanCache")
+            .field("capacity", &inner.capacity)
+            .field("len", &inner.entries.len())
+            .field("hits", &inner.hits)
+            .field("misses", &inner.misses)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlanCache, PlanKey};
+
+    fn key(ranking: Vec<usize>) -> PlanKey {
+        PlanKey::new(ranking, vec!["C".to_owned()], vec![])
+    }
+
+    #[test]
+    fn least_recently_used_plans_are_removed() {
+        let cache: PlanCache<i32> = PlanCache::new(2);
+        assert_eq!(cache.get(&key(vec![0, 1])), None);
+        cache.insert(key(vec![0, 1]), vec![]);
+        cache.insert(key(vec![1, 0]), vec![]);
+        assert_eq!(cache.get(&key(vec![0, 1])), Some(vec![]));
+        cache.insert(key(vec![2, 0]), vec![]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key(vec![1, 0])), None);
+        assert_eq!(cache.get(&key(vec![0, 1])), Some(vec![]));
+        assert_eq!((cache.hits(), cache.misses()), (2, 2));
+
+        cache.invalidate();
+        assert!(cache.is_empty());
+        assert_eq!((cache.hits(), cache.misses()), (2, 2));
+    }
+
+    #[test]
+    fn clones_start_out_empty() {
+        let cache: PlanCache<i32> = PlanCache::new(2);
+        cache.insert(key(vec![0, 1]), vec![]);
+        assert_eq!(cache.get(&key(vec![0, 1])), Some(vec![]));
+        let clone = cache.clone();
+        assert!(clone.is_empty());
+        assert_eq!(clone.capacity(), 2);
+        assert_eq!((clone.hits(), clone.misses()), (0, 0));
+        // The original keeps its plans
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,125 @@
+//! A handle to the methods scheduled by solving a [`Component`](super::Component)
+//! or [`ConstraintSystem`](super::ConstraintSystem).
+
+use super::activation::{Activation, State};
+use crate::{executor::WeakTerminationHandle, solver::SolveError};
+use futures::Future;
+use std::{
+    fmt::Debug,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+/// How many outputs are still pending, and the task to wake once none are.
+#[derive(Debug)]
+struct Progress {
+    remaining: AtomicUsize,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl Progress {
+    /// Notes that one more output is ready or has failed.
+    fn settle(&self) {
+        if self.remaining.fetch_sub(1, Ordering::SeqCst) == 1 {
+            if let Some(waker) = self.waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A handle to all the methods that were scheduled by a call to `par_solve`.
+///
+/// It is a [`Future`] that resolves once every variable written to is either ready or has failed,
+/// to the errors of the failed ones, if any.
+/// Dropping the handle does not affect the methods, but [`cancel`](Self::cancel) aborts all of them.
+pub struct SolveHandle<T> {
+    outputs: Vec<Activation<T>>,
+    producers: Vec<WeakTerminationHandle>,
+    progress: Arc<Progress>,
+}
+
+impl<T> SolveHandle<T> {
+    /// Constructs a new [`SolveHandle`] that waits for `outputs`.
+    pub(crate) fn new(outputs: Vec<Activation<T>>) -> Self {
+        let producers = outputs
+            .iter()
+            .filter_map(|a| a.producer.as_ref().map(|p| p.downgrade()))
+            .collect();
+        let outputs: Vec<Activation<T>> = outputs.iter().map(Activation::weak_clone).collect();
+        let progress = Arc::new(Progress {
+            remaining: AtomicUsize::new(outputs.len()),
+            waker: Mutex::new(None),
+        });
+        for output in &outputs {
+            let progress = progress.clone();
+            output.on_settled(Box::new(move || progress.settle()));
+        }
+        Self {
+            outputs,
+            producers,
+            progress,
+        }
+    }
+
+    /// Returns true if every variable written to is either ready or has failed.
+    pub fn is_finished(&self) -> bool {
+        self.progress.remaining.load(Ordering::SeqCst) == 0
+    }
+
+    /// Returns the errors of the variables that have failed so far.
+    /// An error that caused multiple variables to fail is only included once.
+    pub fn errors(&self) -> Vec<SolveError> {
+        let mut errors: Vec<SolveError> = Vec::new();
+        for output in &self.outputs {
+            if let State::Error(error_data) = output.inner().read().unwrap().state() {
+                for error in error_data.errors() {
+                    if !errors.contains(error) {
+                        errors.push(error.clone());
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// Aborts the methods that have not finished yet.
+    /// Their outputs fail with [`Reason::Cancelled`](crate::solver::Reason::Cancelled),
+    /// and the methods are told to stop early.
+    pub fn cancel(&self) {
+        for producer in &self.producers {
+            producer.abort();
+        }
+    }
+}
+
+impl<T> Future for SolveHandle<T> {
+    type Output = Result<(), Vec<SolveError>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Store the waker before checking, so that the last output to settle can not be missed
+        *self.progress.waker.lock().unwrap() = Some(cx.waker().clone());
+        if !self.is_finished() {
+            return Poll::Pending;
+        }
+        let errors = self.errors();
+        if errors.is_empty() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Ready(Err(errors))
+        }
+    }
+}
+
+impl<T> Debug for SolveHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SolveHandle")
+            .field("outputs", &self.outputs.len())
+            .field("remaining", &self.progress.remaining.load(Ordering::SeqCst))
+            .finish()
+    }
+}
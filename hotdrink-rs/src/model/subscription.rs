@@ -0,0 +1,119 @@
+//! Handles for callbacks subscribed to events from variables.
+
+use super::generation_id::GenerationId;
+use crate::{event::Event, solver::SolveError};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// A callback to events from any variable of a [`Component`](super::Component),
+/// along with the name of the variable and the generation the event is from.
+pub(crate) type NamedEventCallback<T> =
+    Arc<dyn Fn(&str, GenerationId, Event<'_, T, SolveError>) + Send + Sync>;
+
+/// A callback to events from any variable of a [`ConstraintSystem`](super::ConstraintSystem),
+/// along with the names of the component and the variable, and the generation the event is from.
+pub(crate) type SystemEventCallback<T> =
+    Arc<dyn Fn(&str, &str, GenerationId, Event<'_, T, SolveError>) + Send + Sync>;
+
+/// The source of new subscription ids.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Identifies a callback subscribed to events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(usize);
+
+impl SubscriptionId {
+    /// Returns a new id that has not been used before.
+    pub(crate) fn next() -> Self {
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A callback subscribed to events, which is unsubscribed when this is dropped.
+///
+/// Use [`detach`](Self::detach) to keep the callback subscribed after dropping it.
+#[must_use = "the callback is unsubscribed when the subscription is dropped"]
+pub struct Subscription {
+    id: SubscriptionId,
+    unsubscribe: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl Subscription {
+    /// Constructs a new [`Subscription`] that calls `unsubscribe` when it is dropped.
+    pub(crate) fn new(
+        id: SubscriptionId,
+        unsubscribe: impl FnOnce() + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            id,
+            unsubscribe: Some(Box::new(unsubscribe)),
+        }
+    }
+
+    /// Returns the id of the subscription.
+    pub fn id(&self) -> SubscriptionId {
+        self.id
+    }
+
+    /// Keeps the callback subscribed for as long as what it is subscribed to exists.
+    ///
+    /// Callbacks subscribed to a variable can still be removed with
+    /// [`Component::unsubscribe`](super::Component::unsubscribe).
+    pub fn detach(mut self) -> SubscriptionId {
+        self.unsubscribe = None;
+        self.id
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(unsubscribe) = self.unsubscribe.take() {
+            unsubscribe();
+        }
+    }
+}
+
+impl Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id)
+            .field("detached", &self.unsubscribe.is_none())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Subscription, SubscriptionId};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    fn subscription(unsubscribed: &Arc<AtomicBool>) -> Subscription {
+        let unsubscribed = unsubscribed.clone();
+        Subscription::new(SubscriptionId::next(), move || {
+            unsubscribed.store(true, Ordering::SeqCst)
+        })
+    }
+
+    #[test]
+    fn dropped_subscriptions_unsubscribe() {
+        let unsubscribed = Arc::new(AtomicBool::new(false));
+        drop(subscription(&unsubscribed));
+        assert!(unsubscribed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn detached_subscriptions_stay_subscribed() {
+        let unsubscribed = Arc::new(AtomicBool::new(false));
+        let id = subscription(&unsubscribed).detach();
+        assert!(!unsubscribed.load(Ordering::SeqCst));
+        assert_ne!(id, SubscriptionId::next());
+    }
+}
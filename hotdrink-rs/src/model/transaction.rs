@@ -0,0 +1,111 @@
+//! Types for batching edits of a [`Component`](super::Component) or [`ConstraintSystem`](super::ConstraintSystem).
+//!
+//! The edits of a transaction are collected first, and only applied if all of them succeed.
+//! They end up in the same undo step, and can optionally be followed by a single solve.
+
+use super::errors::TransactionError;
+use std::convert::TryInto;
+
+/// A batch of edits to the variables of a [`Component`](super::Component).
+/// See [`Component::transaction`](super::Component::transaction).
+#[derive(Debug)]
+pub struct Transaction<T> {
+    edits: Vec<(String, T)>,
+    error: Option<TransactionError>,
+    solve: bool,
+}
+
+impl<T> Transaction<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            edits: Vec::new(),
+            error: None,
+            solve: false,
+        }
+    }
+
+    /// Edits the specified variable's value.
+    ///
+    /// If the value can not be converted, the whole transaction is rolled back.
+    pub fn edit<V: TryInto<T>>(&mut self, variable: &str, value: V) {
+        match value.try_into() {
+            Ok(value) => self.edits.push((variable.to_owned(), value)),
+            Err(_) => {
+                self.error
+                    .get_or_insert_with(|| TransactionError::Conversion(variable.to_owned()));
+            }
+        }
+    }
+
+    /// Solves the component after the edits have been applied.
+    pub fn solve(&mut self) {
+        self.solve = true;
+    }
+
+    /// Returns the edits and whether to solve afterwards, or the first error.
+    pub(crate) fn finish(self) -> Result<(Vec<(String, T)>, bool), TransactionError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok((self.edits, self.solve)),
+        }
+    }
+}
+
+/// A batch of edits to the variables of a [`ConstraintSystem`](super::ConstraintSystem).
+/// See [`ConstraintSystem::transaction`](super::ConstraintSystem::transaction).
+#[derive(Debug)]
+pub struct SystemTransaction<T> {
+    edits: Vec<(String, String, T)>,
+    error: Option<TransactionError>,
+    solve: bool,
+}
+
+impl<T> SystemTransaction<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            edits: Vec::new(),
+            error: None,
+            solve: false,
+        }
+    }
+
+    /// Edits the value of the variable with name `variable` in `component`.
+    ///
+    /// If the value can not be converted, the whole transaction is rolled back.
+    pub fn edit<V: TryInto<T>>(&mut self, component: &str, variable: &str, value: V) {
+        match value.try_into() {
+            Ok(value) => self
+                .edits
+                .push((component.to_owned(), variable.to_owned(), value)),
+            Err(_) => {
+                self.error.get_or_insert_with(|| {
+                    TransactionError::Conversion(format!("{}.{}", component, variable))
+                });
+            }
+        }
+    }
+
+    /// Solves the constraint system after the edits have been applied.
+    pub fn solve(&mut self) {
+        self.solve = true;
+    }
+
+    /// Returns the edits and whether to solve afterwards, or the first error.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn finish(self) -> Result<(Vec<(String, String, T)>, bool), TransactionError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok((self.edits, self.solve)),
+        }
+    }
+}
+
+/// Keeps only the last edit of each variable, in the order of those edits.
+pub(crate) fn last_edits<K: PartialEq, T>(edits: Vec<(K, T)>) -> Vec<(K, T)> {
+    let mut last: Vec<(K, T)> = Vec::with_capacity(edits.len());
+    for (key, value) in edits {
+        last.retain(|(k, _)| k != &key);
+        last.push((key, value));
+    }
+    last
+}
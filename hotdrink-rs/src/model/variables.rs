@@ -60,6 +60,11 @@ impl<T> Variables<T> {
         self.variables.len()
     }
 
+    /// Returns true if values have been modified since the last [`commit`](#method.commit).
+    pub fn is_modified(&self) -> bool {
+        self.is_modified
+    }
+
     /// Returns the number of generations stored.
     pub fn generations(&self) -> usize {
         self.diff.len() + 1
@@ -115,20 +120,41 @@ impl<T> Variables<T> {
     pub fn set(&mut self, index: usize, value: T) {
         self.clear_future();
 
-        if !self.is_modified {
+        // Without undo history, the previous generation is deleted right away,
+        // so a new one must be begun for every change.
+        if !self.is_modified || self.current_generation == 0 {
             self.begin_generation();
         }
 
         self.diff[self.current_generation - 1].push(index);
         self.variables[index].set(value);
 
-        if !self.is_modified {
-            self.clear_past();
-        }
+        // This also drops the old value right away if there is no undo history
+        self.clear_past();
 
         self.is_modified = true;
     }
 
+    /// Adds a new variable with the specified value.
+    /// It has no earlier values to undo to.
+    pub fn push(&mut self, value: T) {
+        self.variables.push(Variable::from(value));
+    }
+
+    /// Removes the variable at `index` along with its history,
+    /// and moves the variables after it one index down.
+    pub fn remove(&mut self, index: usize) {
+        self.variables.remove(index);
+        for diff in &mut self.diff {
+            diff.retain(|&vi| vi != index);
+            for vi in diff.iter_mut() {
+                if *vi > index {
+                    *vi -= 1;
+                }
+            }
+        }
+    }
+
     /// Returns references to the current variables.
     pub fn variables(&self) -> &[Variable<T>] {
         &self.variables
@@ -144,9 +170,19 @@ impl<T> Variables<T> {
         self.is_modified = false;
     }
 
+    /// Returns true if there is an earlier generation to [`undo`](#method.undo) to.
+    pub fn can_undo(&self) -> bool {
+        self.current_generation > 0
+    }
+
+    /// Returns true if there is a later generation to [`redo`](#method.redo) to.
+    pub fn can_redo(&self) -> bool {
+        self.current_generation < self.generations() - 1
+    }
+
     /// Moves back to the last [`commit`](#method.commit).
     pub fn undo(&mut self) -> Result<(), NoMoreUndo> {
-        if self.current_generation == 0 {
+        if !self.can_undo() {
             return Err(NoMoreUndo);
         }
 
@@ -165,7 +201,7 @@ impl<T> Variables<T> {
 
     /// Moves forward to the next [`commit`](#method.commit).
     pub fn redo(&mut self) -> Result<(), NoMoreRedo> {
-        if self.current_generation == self.generations() - 1 {
+        if !self.can_redo() {
             return Err(NoMoreRedo);
         }
 
@@ -199,7 +235,7 @@ impl<T> From<Vec<T>> for Variables<T> {
 
 #[cfg(test)]
 mod tests {
-    use super::{NoMoreRedo, NoMoreUndo, Variables};
+    use super::{NoMoreRedo, NoMoreUndo, UndoLimit, Variables};
 
     #[test]
     fn new_has_correct_len() {
@@ -306,6 +342,53 @@ mod tests {
         assert_eq!(gs.undo(), Err(NoMoreUndo));
     }
 
+    #[test]
+    fn removed_variables_are_not_undone() {
+        let mut gs = Variables::new(vec![0, 0, 0]);
+        gs.set(0, 1);
+        gs.set(2, 2);
+        gs.commit();
+        gs.remove(0);
+        gs.push(3);
+        assert_eq!(gs.values(), vec![&0, &2, &3]);
+        assert_eq!(gs.undo(), Ok(()));
+        assert_eq!(gs.values(), vec![&0, &0, &3]);
+        assert_eq!(gs.redo(), Ok(()));
+        assert_eq!(gs.values(), vec![&0, &2, &3]);
+    }
+
+    #[test]
+    fn undo_limit_zero_keeps_no_history() {
+        let mut gs = Variables::new_with_limit(vec![0, 0], 0);
+        gs.set(0, 1);
+        gs.set(1, 2);
+        gs.set(0, 3);
+        gs.commit();
+        assert_eq!(gs.values(), vec![&3, &2]);
+        assert_eq!(gs.generations(), 1);
+        assert_eq!(gs.undo(), Err(NoMoreUndo));
+    }
+
+    #[test]
+    fn undo_limit_zero_keeps_undo_and_redo_working() {
+        let mut gs = Variables::new_with_limit(vec![0, 0], 0);
+        gs.set(0, 1);
+        gs.set(1, 2);
+        gs.commit();
+        assert_eq!(gs.undo(), Err(NoMoreUndo));
+        assert_eq!(gs.redo(), Err(NoMoreRedo));
+        assert_eq!(gs.values(), vec![&1, &2]);
+
+        // History is kept again once the limit is raised
+        gs.set_limit(UndoLimit::Limited(1));
+        gs.set(0, 3);
+        gs.commit();
+        assert_eq!(gs.undo(), Ok(()));
+        assert_eq!(gs.values(), vec![&1, &2]);
+        assert_eq!(gs.redo(), Ok(()));
+        assert_eq!(gs.values(), vec![&3, &2]);
+    }
+
     #[test]
     fn undo_limit_one_gives_one_undo() {
         // Without commit
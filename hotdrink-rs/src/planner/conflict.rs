@@ -0,0 +1,290 @@
+//! Finding small sets of constraints that can not be enforced at the same time.
+//! This is used to explain why planning failed.
+
+use super::{
+    hierarchical::{OwnedEnforcedConstraint, Vertex},
+    simple::simple_planner_or_remaining,
+    subgraph::Subgraph,
+    ComponentSpec, Conflict, ConstraintSpec, PlanError, Strength,
+};
+
+/// Creates a [`PlanError`] describing why `constraints` of `component` could not be enforced.
+pub fn overconstrained<C, Comp>(component: &Comp, constraints: &[&C]) -> PlanError
+where
+    C: ConstraintSpec + Clone,
+    Comp: ComponentSpec<Constraint = C>,
+{
+    let (conflicting, variables) = find_conflict(component.n_variables(), constraints);
+    PlanError::Overconstrained(Conflict::new(
+        conflicting
+            .into_iter()
+            .map(|ci| constraints[ci].name().to_owned())
+            .collect(),
+        variables
+            .into_iter()
+            .map(|vi| variable_name(component, vi))
+            .collect(),
+    ))
+}
+
+/// Creates a [`PlanError`] describing why the methods selected in `plan` can not be run in any order.
+pub fn cyclic<M, Comp>(component: &Comp, plan: &[OwnedEnforcedConstraint<M>]) -> PlanError
+where
+    M: Vertex,
+    Comp: ComponentSpec,
+{
+    let (methods, variables) = find_cycle(component.n_variables(), plan).unwrap_or_default();
+    PlanError::Overconstrained(Conflict::new(
+        methods
+            .into_iter()
+            .map(|mi| plan[mi].name().to_owned())
+            .collect(),
+        variables
+            .into_iter()
+            .map(|vi| variable_name(component, vi))
+            .collect(),
+    ))
+}
+
+/// Returns the name of a variable, or its index if it has no name.
+fn variable_name<Comp: ComponentSpec>(component: &Comp, vi: usize) -> String {
+    match component.variable_name(vi) {
+        Some(name) => name.to_owned(),
+        None => vi.to_string(),
+    }
+}
+
+/// Finds a cycle among `methods`, where each method must run before the ones that read its outputs.
+///
+/// Returns the indices of the methods along the cycle,
+/// and the variables that each of them writes to and the next one reads.
+pub fn find_cycle<M: Vertex>(
+    n_variables: usize,
+    methods: &[M],
+) -> Option<(Vec<usize>, Vec<usize>)> {
+    let mut readers = vec![Vec::new(); n_variables];
+    for (mi, m) in methods.iter().enumerate() {
+        for &vi in m.inputs() {
+            readers[vi].push(mi);
+        }
+    }
+    // The edges from a method, as pairs of variable and the method reading it
+    let edges = |mi: usize| -> Vec<(usize, usize)> {
+        methods[mi]
+            .outputs()
+            .iter()
+            .flat_map(|&vi| readers[vi].iter().map(move |&next| (vi, next)))
+            .filter(|&(_, next)| next != mi)
+            .collect()
+    };
+
+    // Depth-first search, where a method that is reached again while on the path closes a cycle
+    let mut on_path = vec![false; methods.len()];
+    let mut done = vec![false; methods.len()];
+    for start in 0..methods.len() {
+        if done[start] {
+            continue;
+        }
+        // The methods on the path along with their unvisited edges, and the variables between them
+        let mut path = vec![(start, edges(start))];
+        let mut via = Vec::new();
+        on_path[start] = true;
+        while let Some((mi, remaining)) = path.last_mut() {
+            let mi = *mi;
+            match remaining.pop() {
+                Some((vi, next)) if on_path[next] => {
+                    let begin = path.iter().position(|&(m, _)| m == next)?;
+                    let cycle = path[begin..].iter().map(|&(m, _)| m).collect();
+                    let mut variables = via.split_off(begin);
+                    variables.push(vi);
+                    return Some((cycle, variables));
+                }
+                Some((vi, next)) if !done[next] => {
+                    on_path[next] = true;
+                    via.push(vi);
+                    path.push((next, edges(next)));
+                }
+                Some(_) => {}
+                None => {
+                    on_path[mi] = false;
+                    done[mi] = true;
+                    path.pop();
+                    via.pop();
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Finds a small set of constraints that can not be enforced together,
+/// and the variables they share.
+/// Required constraints are checked by themselves first,
+/// since weaker constraints are dropped before causing a conflict.
+///
+/// Returns the indices of the constraints in `constraints`, and the indices of the variables.
+pub fn find_conflict<C>(n_variables: usize, constraints: &[&C]) -> (Vec<usize>, Vec<usize>)
+where
+    C: ConstraintSpec + Clone,
+{
+    let all: Vec<usize> = (0..constraints.len()).collect();
+    let required: Vec<usize> = all
+        .iter()
+        .copied()
+        .filter(|&ci| constraints[ci].strength() == Strength::Required)
+        .collect();
+    let conflicting = unenforceable(n_variables, constraints, &required)
+        .or_else(|| unenforceable(n_variables, constraints, &all))
+        .unwrap_or(all);
+    let conflicting = minimize(n_variables, constraints, conflicting);
+    let variables = shared_variables(n_variables, constraints, &conflicting);
+    (conflicting, variables)
+}
+
+/// Returns the constraints among `subset` that could not be enforced, if any.
+fn unenforceable<C>(n_variables: usize, constraints: &[&C], subset: &[usize]) -> Option<Vec<usize>>
+where
+    C: ConstraintSpec + Clone,
+{
+    let subgraph = Subgraph::from_constraints(
+        n_variables,
+        subset.iter().map(|&ci| constraints[ci].clone()).collect(),
+    );
+    simple_planner_or_remaining(&subgraph)
+        .err()
+        .map(|remaining| remaining.into_iter().map(|i| subset[i]).collect())
+}
+
+/// Removes constraints from the conflict one at a time, as long as the remaining ones still conflict.
+fn minimize<C>(n_variables: usize, constraints: &[&C], mut conflicting: Vec<usize>) -> Vec<usize>
+where
+    C: ConstraintSpec + Clone,
+{
+    let mut i = 0;
+    while i < conflicting.len() {
+        let mut without = conflicting.clone();
+        without.remove(i);
+        match unenforceable(n_variables, constraints, &without) {
+            Some(remaining) => conflicting = remaining,
+            None => i += 1,
+        }
+    }
+    conflicting
+}
+
+/// Returns the variables referenced by more than one of the conflicting constraints,
+/// or all of their variables if they share none.
+fn shared_variables<C>(n_variables: usize, constraints: &[&C], conflicting: &[usize]) -> Vec<usize>
+where
+    C: ConstraintSpec,
+{
+    let mut references = vec![0; n_variables];
+    for &ci in conflicting {
+        for &vi in constraints[ci].variables() {
+            references[vi] += 1;
+        }
+    }
+    let shared: Vec<usize> = (0..n_variables).filter(|&vi| references[vi] > 1).collect();
+    if shared.is_empty() {
+        (0..n_variables).filter(|&vi| references[vi] > 0).collect()
+    } else {
+        shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cyclic, find_conflict, find_cycle};
+    use crate::{
+        planner::{
+            ComponentSpec, Conflict, ConstraintSpec, OwnedEnforcedConstraint, PlanError, Strength,
+        },
+        ret,
+    };
+
+    #[test]
+    fn finds_minimal_conflict() {
+        let component = dummy_component! {
+            let a, b, c, d;
+            constraint A {
+                a1(a) -> [b];
+                a2(b) -> [a];
+            }
+            constraint B {
+                b1(c) -> [d];
+            }
+            constraint C {
+                c1(a) -> [b];
+                c2(b) -> [a];
+            }
+            constraint D {
+                d1(b) -> [c];
+            }
+        };
+        let constraints: Vec<_> = component.constraints().iter().collect();
+        assert_eq!(
+            find_conflict(component.n_variables(), &constraints),
+            (vec![0, 2], vec![0, 1])
+        );
+    }
+
+    #[test]
+    fn required_constraints_are_checked_first() {
+        let mut component = dummy_component! {
+            let a, b, c, d;
+            constraint A {
+                a1(a) -> [b];
+            }
+            constraint B {
+                b1(b) -> [a];
+            }
+            constraint C {
+                c1(c) -> [d];
+            }
+            constraint D {
+                d1(c) -> [d];
+            }
+        };
+        component
+            .set_constraint_strength("A", Strength::Weak)
+            .unwrap();
+        let constraints: Vec<_> = component.constraints().iter().collect();
+        assert_eq!(
+            find_conflict(component.n_variables(), &constraints),
+            (vec![2, 3], vec![2, 3])
+        );
+    }
+
+    #[test]
+    fn finds_cycle_among_methods() {
+        let component = dummy_component! {
+            let a, b, c, d;
+            constraint A {
+                a1(a, c) -> [b];
+            }
+            constraint B {
+                b1(b) -> [c];
+            }
+            constraint C {
+                c1(a) -> [d];
+            }
+        };
+        let plan: Vec<_> = component
+            .constraints()
+            .iter()
+            .map(|c| OwnedEnforcedConstraint::new(c.name(), c.methods()[0].clone()))
+            .collect();
+        assert_eq!(
+            find_cycle(component.n_variables(), &plan),
+            Some((vec![0, 1], vec![1, 2]))
+        );
+        assert_eq!(find_cycle(component.n_variables(), &plan[1..]), None);
+        assert_eq!(
+            cyclic(&component, &plan),
+            PlanError::Overconstrained(Conflict::new(
+                vec!["A".to_owned(), "B".to_owned()],
+                vec!["b".to_owned(), "c".to_owned()]
+            ))
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Dependencies between variables that follow from a plan,
+//! used to find which variables are computed from which.
+
+use super::hierarchical::{OwnedEnforcedConstraint, Vertex};
+use std::collections::BTreeSet;
+
+/// Finds the variables that are related to a variable in a plan, like [`downstream`] and [`upstream`].
+pub(crate) type Follow<M> = fn(&[OwnedEnforcedConstraint<M>], usize) -> Vec<usize>;
+
+/// Returns the variables that are computed from `variable` in `plan`, directly or indirectly.
+/// The result is sorted by index, and does not include `variable` itself.
+pub(crate) fn downstream<M: Vertex>(
+    plan: &[OwnedEnforcedConstraint<M>],
+    variable: usize,
+) -> Vec<usize> {
+    follow(plan, variable, M::inputs, M::outputs)
+}
+
+/// Returns the variables that `variable` is computed from in `plan`, directly or indirectly.
+/// The result is sorted by index, and does not include `variable` itself.
+pub(crate) fn upstream<M: Vertex>(
+    plan: &[OwnedEnforcedConstraint<M>],
+    variable: usize,
+) -> Vec<usize> {
+    follow(plan, variable, M::outputs, M::inputs)
+}
+
+/// Collects the variables reachable from `variable` by going from the `from` side
+/// to the `to` side of the methods in `plan`.
+fn follow<M>(
+    plan: &[OwnedEnforcedConstraint<M>],
+    variable: usize,
+    from: fn(&M) -> &[usize],
+    to: fn(&M) -> &[usize],
+) -> Vec<usize> {
+    let mut reached = BTreeSet::new();
+    let mut stack = vec![variable];
+    while let Some(vi) = stack.pop() {
+        for method in plan.iter().map(OwnedEnforcedConstraint::method) {
+            if !from(method).contains(&vi) {
+                continue;
+            }
+            for &next in to(method) {
+                if next != variable && reached.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+    }
+    reached.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{downstream, upstream};
+    use crate::planner::{OwnedEnforcedConstraint, Vertex};
+
+    /// A method that only has inputs and outputs.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Edge {
+        inputs: Vec<usize>,
+        outputs: Vec<usize>,
+    }
+
+    impl Vertex for Edge {
+        fn inputs(&self) -> &[usize] {
+            &self.inputs
+        }
+
+        fn outputs(&self) -> &[usize] {
+            &self.outputs
+        }
+
+        fn stay(index: usize) -> Self {
+            Self {
+                inputs: vec![index],
+                outputs: vec![index],
+            }
+        }
+
+        fn is_stay(&self) -> bool {
+            self.inputs == self.outputs
+        }
+    }
+
+    fn edge(inputs: Vec<usize>, outputs: Vec<usize>) -> OwnedEnforcedConstraint<Edge> {
+        OwnedEnforcedConstraint::new("c", Edge { inputs, outputs })
+    }
+
+    #[test]
+    fn follows_methods_in_both_directions() {
+        // 0 and 1 give 2, 2 gives 3, and 4 is pinned
+        let plan = vec![
+            edge(vec![0, 1], vec![2]),
+            edge(vec![2], vec![3]),
+            edge(vec![4], vec![4]),
+        ];
+        assert_eq!(downstream(&plan, 0), vec![2, 3]);
+        assert_eq!(downstream(&plan, 2), vec![3]);
+        assert_eq!(downstream(&plan, 3), Vec::<usize>::new());
+        assert_eq!(downstream(&plan, 4), Vec::<usize>::new());
+        assert_eq!(upstream(&plan, 3), vec![0, 1, 2]);
+        assert_eq!(upstream(&plan, 0), Vec::<usize>::new());
+        assert_eq!(upstream(&plan, 4), Vec::<usize>::new());
+    }
+}
@@ -30,12 +30,16 @@
 //! [`Component`]: crate::model::Component
 
 use super::{
+    conflict::{cyclic, overconstrained},
     pruner::{create_var_to_constraint, prune},
-    simple::{simple_planner, EnforcedConstraint},
+    simple::{simple_planner, simple_planner_indexed, EnforcedConstraint},
+    subgraph::Subgraph,
     Plan,
 };
-use crate::planner::{toposorter::toposort, ComponentSpec, ConstraintSpec, MethodSpec, PlanError};
-use std::fmt::Debug;
+use crate::planner::{
+    toposorter::toposort, ComponentSpec, ConstraintSpec, MethodSpec, PlanError, Strength,
+};
+use std::{cmp::Reverse, fmt::Debug};
 
 /// Represents a type with input- and output-indices.
 /// This can be used to represent Vertices in graphs.
@@ -129,18 +133,19 @@ impl<M: Vertex> Vertex for OwnedEnforcedConstraint<M> {
 /// and the graph must also be a DAG.
 pub type OwnedPlan<M> = Vec<OwnedEnforcedConstraint<M>>;
 
-/// TODO: Implement
+/// A planner that enforces all active constraints while avoiding
+/// modifying the highest ranked variables, using [`hierarchical_planner`].
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct HierarchicalPlanner;
 
-impl Plan for HierarchicalPlanner {
-    fn plan<T, M, C, Comp>(_component: &Comp) -> Result<Vec<EnforcedConstraint<'_, T>>, PlanError>
-    where
-        M: MethodSpec<Arg = T> + Clone,
-        C: ConstraintSpec<Method = M> + Debug + Clone,
-        Comp: ComponentSpec<Constraint = C> + Clone,
-    {
-        todo!()
+impl<T, M, C, Comp> Plan<Comp> for HierarchicalPlanner
+where
+    M: MethodSpec<Arg = T> + Clone,
+    C: ConstraintSpec<Method = M> + Debug + Clone,
+    Comp: ComponentSpec<Constraint = C> + Clone,
+{
+    fn plan(&self, component: &Comp) -> Result<OwnedPlan<M>, PlanError> {
+        hierarchical_planner(component)
     }
 }
 
@@ -165,6 +170,21 @@ pub(crate) fn hierarchical_planner_with_ranking<T, M, C, Comp>(
     component: &Comp,
     ranking: &[usize],
 ) -> Result<OwnedPlan<M>, PlanError>
+where
+    M: MethodSpec<Arg = T> + Clone,
+    C: ConstraintSpec<Method = M> + Debug + Clone,
+    Comp: ComponentSpec<Constraint = C> + Clone,
+{
+    hierarchical_planner_with_stays(component, ranking).map(|(plan, _)| plan)
+}
+
+/// Plans like [`hierarchical_planner_with_ranking`], and also returns the variables
+/// whose stay constraints could be enforced, meaning that the plan does not write to them.
+/// Variables that were never considered, since they had to be written to, are not included.
+pub(crate) fn hierarchical_planner_with_stays<T, M, C, Comp>(
+    component: &Comp,
+    ranking: &[usize],
+) -> Result<(OwnedPlan<M>, Vec<usize>), PlanError>
 where
     M: MethodSpec<Arg = T> + Clone,
     C: ConstraintSpec<Method = M> + Debug + Clone,
@@ -173,15 +193,34 @@ where
     log::trace!("Calling hierarchical planner");
     // The initial solution with no stay constraints. If this fails, just return.
     let mut best_solution: Option<OwnedPlan<M>> = None;
+    // Conflicts are reported in terms of the active constraints of the original component
+    let active: Vec<&C> = component
+        .constraints()
+        .iter()
+        .filter(|c| c.is_active())
+        .collect();
+    let overconstrained = || overconstrained(component, &active);
 
     // Clone the component to be able to modify it
     let mut component = component.clone();
     // Filter out inactive constraints
     component.constraints_mut().retain(C::is_active);
+    // Drop the weakest constraints that conflict with stronger ones
+    let enforced = enforceable_constraints(&component).ok_or_else(overconstrained)?;
+    if enforced.len() < component.n_constraints() {
+        let constraints = std::mem::take(component.constraints_mut());
+        *component.constraints_mut() = constraints
+            .into_iter()
+            .enumerate()
+            .filter(|(ci, _)| enforced.binary_search(ci).is_ok())
+            .map(|(_, c)| c)
+            .collect();
+    }
     // Lock variables that can't have a stay constraint
     let mut can_stay = vec![true; ranking.len()];
     // Create a map from variables to constraints
     let mut var_to_constraint = create_var_to_constraint(&component);
+    let mut stays = Vec::new();
 
     // Try to find the best combination of stay constraints that works (lexicographic order)
     for &var_id in ranking {
@@ -196,6 +235,7 @@ where
         if let Some(bs) = &best_solution {
             let is_source = bs.iter().all(|m| !m.outputs().contains(&var_id));
             if is_source {
+                stays.push(var_id);
                 var_to_constraint[var_id].insert(component.constraints().len() - 1);
                 prune(
                     &mut var_to_constraint,
@@ -211,6 +251,7 @@ where
         // Check if this new solution works
         match simple_planner(&component) {
             Some(new_solution) => {
+                stays.push(var_id);
                 var_to_constraint[var_id].insert(component.constraints().len() - 1);
                 // Update best solution
                 best_solution = Some(new_solution.into_iter().map(|sc| sc.into()).collect());
@@ -237,22 +278,70 @@ where
     // Remove stay constraints
     let best_solution = best_solution
         .or_else(|| simple_planner(&component).map(|p| p.into_iter().map(|sc| sc.into()).collect()))
-        .ok_or(PlanError::Overconstrained)?;
+        .ok_or_else(overconstrained)?;
     let best_solution: Vec<OwnedEnforcedConstraint<_>> =
         best_solution.into_iter().filter(|m| !m.is_stay()).collect();
     let sorted =
         toposort(&best_solution, component.n_variables()).map(|v| v.into_iter().cloned().collect());
 
-    sorted.ok_or(PlanError::Overconstrained)
+    let sorted = sorted.ok_or_else(|| cyclic(&component, &best_solution))?;
+    Ok((sorted, stays))
+}
+
+/// Finds the constraints of the component that should be enforced.
+/// If they can not all be enforced at once, constraints are added from strongest to weakest,
+/// and those that conflict with the ones already added are left out.
+///
+/// Returns the indices of the constraints to enforce in increasing order,
+/// or `None` if the required constraints conflict.
+pub(crate) fn enforceable_constraints<M, C, Comp>(component: &Comp) -> Option<Vec<usize>>
+where
+    M: MethodSpec,
+    C: ConstraintSpec<Method = M> + Clone,
+    Comp: ComponentSpec<Constraint = C>,
+{
+    let constraints = component.constraints();
+    let all = (0..constraints.len()).collect();
+    if constraints
+        .iter()
+        .all(|c| c.strength() == Strength::Required)
+        || simple_planner_indexed(component).is_some()
+    {
+        return Some(all);
+    }
+
+    // Strongest first, and in the original order among equally strong constraints
+    let mut by_strength: Vec<usize> = all;
+    by_strength.sort_by_key(|&ci| Reverse(constraints[ci].strength()));
+
+    let mut candidate = Subgraph::from_constraints(component.n_variables(), Vec::new());
+    let mut enforced = Vec::new();
+    for ci in by_strength {
+        candidate.add_constraint(constraints[ci].clone());
+        if simple_planner_indexed(&candidate).is_some() {
+            enforced.push(ci);
+        } else if constraints[ci].strength() == Strength::Required {
+            return None;
+        } else {
+            log::trace!("Leaving constraint {} unenforced", constraints[ci].name());
+            candidate.pop_constraint();
+        }
+    }
+
+    enforced.sort_unstable();
+    Some(enforced)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{hierarchical_planner, hierarchical_planner_with_ranking, OwnedEnforcedConstraint};
+    use super::{
+        hierarchical_planner, hierarchical_planner_with_ranking, hierarchical_planner_with_stays,
+        HierarchicalPlanner, OwnedEnforcedConstraint,
+    };
     use crate::examples::components::{ComponentFactory, Ladder};
     use crate::{
         model::{Component, Constraint, Method},
-        planner::{ComponentSpec, ConstraintSpec, MethodSpec},
+        planner::{ComponentSpec, Conflict, ConstraintSpec, MethodSpec, Plan, PlanError, Strength},
         ret,
     };
     use std::sync::Arc;
@@ -297,6 +386,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stays_of_kept_variables_are_returned() {
+        let component: Component<i32> = crate::component! {
+            component Comp {
+                let a: i32 = 0, b: i32 = 0, c: i32 = 0;
+                constraint C {
+                    m1(a: &i32, b: &i32) -> [c] = ret![*a + *b];
+                    m2(b: &i32, c: &i32) -> [a] = ret![*c - *b];
+                    m3(c: &i32, a: &i32) -> [b] = ret![*c - *a];
+                }
+            }
+        };
+        let (plan, stays) = hierarchical_planner_with_stays(&component, &[2, 0, 1]).unwrap();
+        assert_eq!(
+            plan,
+            vec![OwnedEnforcedConstraint::new(
+                "C",
+                component["C"]["m3"].clone()
+            )]
+        );
+        assert_eq!(stays, vec![2, 0]);
+    }
+
     #[test]
     fn hierarchical_planner_with_ranking_two_way_chain() {
         let component = dummy_component! {
@@ -324,6 +436,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hierarchical_planner_plan_matches_function() {
+        let component: Component<()> = Ladder::build(6);
+        assert_eq!(
+            HierarchicalPlanner.plan(&component),
+            hierarchical_planner(&component)
+        );
+    }
+
+    #[test]
+    fn weakest_conflicting_constraint_is_dropped() {
+        let mut component = dummy_component! {
+            let a, b, c;
+            constraint Strong {
+                s1(a) -> [b];
+                s2(b) -> [a];
+            }
+            constraint Weak {
+                w1(a) -> [b];
+                w2(b) -> [a];
+            }
+            constraint Other {
+                o1(b) -> [c];
+            }
+        };
+        // Both are required, so they can not both be enforced
+        assert_eq!(
+            hierarchical_planner(&component),
+            Err(PlanError::Overconstrained(Conflict::new(
+                vec!["Strong".to_owned(), "Weak".to_owned()],
+                vec!["a".to_owned(), "b".to_owned()]
+            )))
+        );
+
+        component["Strong"].set_strength(Strength::Strong);
+        component["Weak"].set_strength(Strength::Weak);
+        assert_eq!(
+            hierarchical_planner_with_ranking(&component, &[0, 1, 2]),
+            Ok(vec![
+                OwnedEnforcedConstraint::new("Strong", component["Strong"]["s1"].clone()),
+                OwnedEnforcedConstraint::new("Other", component["Other"]["o1"].clone()),
+            ])
+        );
+
+        // Swapping the strengths should drop the other one
+        component["Strong"].set_strength(Strength::Weak);
+        component["Weak"].set_strength(Strength::Medium);
+        assert_eq!(
+            hierarchical_planner_with_ranking(&component, &[0, 1, 2]),
+            Ok(vec![
+                OwnedEnforcedConstraint::new("Weak", component["Weak"]["w1"].clone()),
+                OwnedEnforcedConstraint::new("Other", component["Other"]["o1"].clone()),
+            ])
+        );
+    }
+
     #[test]
     fn hierarchical_planner_with_ranking_ladder() {
         env_logger::builder()
@@ -0,0 +1,583 @@
+//! An incremental version of the [hierarchical planner](super::hierarchical_planner)
+//! in the style of DeltaBlue and QuickPlan.
+//!
+//! The hierarchical planner goes through the variables from highest to lowest priority,
+//! and enforces the stay constraint of each one if it can be enforced together with the ones before it.
+//! The incremental planner keeps these decisions along with the previous plan.
+//! When variables are moved to the top of the ranking, such as by an edit,
+//! the plan is repaired by only revisiting the decisions that may have changed:
+//!
+//! - A variable that the current plan does not write to keeps its value without any replanning.
+//!   This is always the case when editing the same variable again, like when typing in a field.
+//! - A variable that could not keep its value before still can not,
+//!   as long as all the variables ranked above it that kept their values still do.
+//! - Otherwise, only the constraints reachable from the variable without passing through
+//!   variables that keep their values are replanned, since those separate it from the rest of the graph.
+//!
+//! The same methods are selected as with the hierarchical planner, although they may be run in a different order.
+//! Changes to the variables or constraints, or other changes to the ranking, cause it to plan from scratch.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hotdrink_rs::{component, ret, planner::{hierarchical_planner, IncrementalPlanner, Plan, Vertex}, model::Component};
+//! let mut component: Component<i32> = component! {
+//!     component Comp {
+//!         let a: i32 = 0, b: i32 = 0, c: i32 = 0;
+//!         constraint C {
+//!             m1(a: &i32, b: &i32) -> [c] = ret![*a + *b];
+//!             m2(b: &i32, c: &i32) -> [a] = ret![*c - *b];
+//!             m3(c: &i32, a: &i32) -> [b] = ret![*c - *a];
+//!         }
+//!     }
+//! };
+//! let planner = IncrementalPlanner::new();
+//! assert_eq!(planner.plan(&component), hierarchical_planner(&component));
+//!
+//! // The previous plan is repaired to not write to c
+//! component.edit("c", 3).unwrap();
+//! let plan = planner.plan(&component).unwrap();
+//! assert!(plan.iter().all(|ec| !ec.method().outputs().contains(&2)));
+//! ```
+
+use super::{
+    hierarchical::{hierarchical_planner_with_stays, OwnedPlan},
+    simple::simple_planner_indexed,
+    subgraph::Subgraph,
+    toposorter::toposort_indices,
+    ComponentSpec, ConstraintSpec, MethodSpec, OwnedEnforcedConstraint, Plan, PlanError,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    sync::Mutex,
+};
+
+/// The previous plan, along with the decisions that were made to get it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Previous {
+    /// The revision of the component the plan was made for.
+    revision: usize,
+    /// The ranking the plan was made for.
+    ranking: Vec<usize>,
+    /// Whether the stay constraint of each variable was enforced, meaning that it keeps its value.
+    kept: Vec<bool>,
+    /// The selected methods in the order they run, as pairs of constraint index and method index.
+    plan: Vec<(usize, usize)>,
+    /// The enforced constraints that reference each variable.
+    references: Vec<Vec<usize>>,
+}
+
+impl Previous {
+    /// Constructs the plan from the selected methods of the component.
+    fn to_plan<M, C, Comp>(&self, component: &Comp) -> OwnedPlan<M>
+    where
+        M: MethodSpec + Clone,
+        C: ConstraintSpec<Method = M>,
+        Comp: ComponentSpec<Constraint = C>,
+    {
+        let constraints = component.constraints();
+        self.plan
+            .iter()
+            .map(|&(ci, mi)| {
+                let constraint = &constraints[ci];
+                OwnedEnforcedConstraint::new(constraint.name(), constraint.methods()[mi].clone())
+            })
+            .collect()
+    }
+}
+
+/// A planner that selects the same methods as [`HierarchicalPlanner`](super::HierarchicalPlanner),
+/// but keeps the previous plan and repairs it when variables are edited instead of planning from scratch.
+///
+/// Planning from scratch happens when the component does not provide a [`revision`](ComponentSpec::revision),
+/// or when it changes.
+#[derive(Debug, Default)]
+pub struct IncrementalPlanner {
+    previous: Mutex<Option<Previous>>,
+}
+
+impl IncrementalPlanner {
+    /// Constructs a new [`IncrementalPlanner`] without any previous plan.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the previous plan, meaning that the next invocation will plan from scratch.
+    pub fn clear(&self) {
+        *self.previous.lock().unwrap() = None;
+    }
+}
+
+impl Clone for IncrementalPlanner {
+    fn clone(&self) -> Self {
+        Self {
+            previous: Mutex::new(self.previous.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl<T, M, C, Comp> Plan<Comp> for IncrementalPlanner
+where
+    M: MethodSpec<Arg = T> + Clone,
+    C: ConstraintSpec<Method = M> + Debug + Clone,
+    Comp: ComponentSpec<Constraint = C> + Clone,
+{
+    fn plan(&self, component: &Comp) -> Result<OwnedPlan<M>, PlanError> {
+        log::trace!("Calling incremental planner");
+        let ranking = component.ranking();
+        let mut previous = self.previous.lock().unwrap();
+
+        if let Some(prev) = previous
+            .as_mut()
+            .filter(|prev| component.revision() == Some(prev.revision))
+        {
+            if prev.ranking == ranking {
+                return Ok(prev.to_plan(component));
+            }
+            if let Some(n_promoted) = promoted(&prev.ranking, &ranking) {
+                if let Some((next, replanned)) =
+                    repair(component, prev, ranking.clone(), n_promoted)
+                {
+                    log::trace!("Repaired plan by replanning constraints {:?}", replanned);
+                    *prev = next;
+                    return Ok(prev.to_plan(component));
+                }
+            }
+        }
+
+        log::trace!("Planning from scratch");
+        *previous = None;
+        let (plan, stays) = hierarchical_planner_with_stays(component, &ranking)?;
+        if let Some(revision) = component.revision() {
+            *previous = remember(component, revision, ranking, &plan, &stays);
+        }
+        Ok(plan)
+    }
+
+    /// Plans with a copy of the previous plan, which is discarded afterwards.
+    fn plan_uncached(&self, component: &Comp) -> Result<OwnedPlan<M>, PlanError> {
+        self.clone().plan(component)
+    }
+}
+
+/// Creates a [`Previous`] from a plan made from scratch,
+/// or returns `None` if its methods can not be told apart by their names.
+fn remember<M, C, Comp>(
+    component: &Comp,
+    revision: usize,
+    ranking: Vec<usize>,
+    plan: &[OwnedEnforcedConstraint<M>],
+    stays: &[usize],
+) -> Option<Previous>
+where
+    M: MethodSpec,
+    C: ConstraintSpec<Method = M>,
+    Comp: ComponentSpec<Constraint = C>,
+{
+    let constraints = component.constraints();
+    let mut by_name = HashMap::with_capacity(constraints.len());
+    for (ci, constraint) in constraints.iter().enumerate() {
+        if constraint.is_active() && by_name.insert(constraint.name(), ci).is_some() {
+            return None;
+        }
+    }
+
+    let n_variables = component.n_variables();
+    let mut references = vec![Vec::new(); n_variables];
+    let mut selected = Vec::with_capacity(plan.len());
+    for ec in plan {
+        let ci = *by_name.get(ec.name())?;
+        let method = ec.method();
+        let mi = constraints[ci].methods().iter().position(|m| {
+            m.name() == method.name()
+                && m.inputs() == method.inputs()
+                && m.outputs() == method.outputs()
+        })?;
+        selected.push((ci, mi));
+        for &vi in constraints[ci].variables() {
+            references[vi].push(ci);
+        }
+    }
+
+    let mut kept = vec![false; n_variables];
+    for &vi in stays {
+        kept[vi] = true;
+    }
+
+    Some(Previous {
+        revision,
+        ranking,
+        kept,
+        plan: selected,
+        references,
+    })
+}
+
+/// Returns the number of variables that were moved to the top of the ranking, if that is all that changed.
+/// That is, the number `n` such that the rest of `ranking` is `previous` without the first `n` variables of `ranking`.
+fn promoted(previous: &[usize], ranking: &[usize]) -> Option<usize> {
+    if previous.len() != ranking.len() {
+        return None;
+    }
+    let mut position = vec![0; ranking.len()];
+    for (i, &vi) in ranking.iter().enumerate() {
+        position[vi] = i;
+    }
+    // Match the variables from the bottom, skipping the ones that were moved above the unmatched part
+    let mut i = ranking.len();
+    for &vi in previous.iter().rev() {
+        if i > 0 && ranking[i - 1] == vi {
+            i -= 1;
+        } else if position[vi] >= i {
+            return None;
+        }
+    }
+    Some(i)
+}
+
+/// Repairs the previous plan after the first `n_promoted` variables of `ranking` were moved to the top.
+///
+/// Returns the new decisions along with the constraints that were replanned,
+/// or `None` if the plan could not be repaired.
+fn repair<M, C, Comp>(
+    component: &Comp,
+    previous: &Previous,
+    ranking: Vec<usize>,
+    n_promoted: usize,
+) -> Option<(Previous, Vec<usize>)>
+where
+    M: MethodSpec + Clone,
+    C: ConstraintSpec<Method = M> + Clone,
+    Comp: ComponentSpec<Constraint = C>,
+{
+    let constraints = component.constraints();
+    let n_variables = component.n_variables();
+
+    // The current plan, which keeps the values of the variables that were decided to keep them so far
+    let mut selected: HashMap<usize, usize> = previous.plan.iter().copied().collect();
+    let mut written = vec![false; n_variables];
+    for (&ci, &mi) in &selected {
+        for &vi in constraints[ci].methods()[mi].outputs() {
+            written[vi] = true;
+        }
+    }
+
+    let mut previous_position = vec![0; n_variables];
+    for (i, &vi) in previous.ranking.iter().enumerate() {
+        previous_position[vi] = i;
+    }
+
+    let mut kept = vec![false; n_variables];
+    // The highest previous position of a variable that no longer keeps its value
+    let mut first_lost = usize::MAX;
+    let mut replanned = Vec::new();
+
+    for (i, &vi) in ranking.iter().enumerate() {
+        // If nothing writes to the variable, its stay constraint can be added as is
+        if !written[vi] {
+            kept[vi] = true;
+            continue;
+        }
+
+        // It was not possible to keep the value before, and every variable that was kept above it still is
+        let is_promoted = i < n_promoted;
+        if !is_promoted && !previous.kept[vi] && first_lost > previous_position[vi] {
+            continue;
+        }
+
+        log::trace!("Replanning around variable {}", vi);
+        let region = reachable(constraints, &previous.references, &kept, vi);
+        let mut subgraph = Subgraph::from_constraints(
+            n_variables,
+            region.iter().map(|&ci| constraints[ci].clone()).collect(),
+        );
+        let mut stays = HashSet::new();
+        for &ci in &region {
+            stays.extend(constraints[ci].variables().iter().filter(|&&v| kept[v]));
+        }
+        for &v in stays.iter().chain(Some(&vi)) {
+            subgraph.add_constraint(C::stay(v));
+        }
+
+        match simple_planner_indexed(&subgraph) {
+            Some(plan) => {
+                kept[vi] = true;
+                for &ci in &region {
+                    for &v in constraints[ci].methods()[selected[&ci]].outputs() {
+                        written[v] = false;
+                    }
+                }
+                for (li, method) in plan.into_iter().filter(|&(li, _)| li < region.len()) {
+                    let ci = region[li];
+                    let mi = subgraph.constraints()[li]
+                        .methods()
+                        .iter()
+                        .position(|m| std::ptr::eq(m, method))?;
+                    for &v in method.outputs() {
+                        written[v] = true;
+                    }
+                    selected.insert(ci, mi);
+                }
+                replanned.extend(region);
+            }
+            None => {
+                if previous.kept[vi] {
+                    first_lost = first_lost.min(previous_position[vi]);
+                }
+            }
+        }
+    }
+
+    // Keep the previous order of the methods, and sort them again if any were replaced
+    let mut plan: Vec<(usize, usize)> = previous
+        .plan
+        .iter()
+        .map(|&(ci, _)| (ci, selected[&ci]))
+        .collect();
+    if !replanned.is_empty() {
+        let methods: Vec<&M> = plan
+            .iter()
+            .map(|&(ci, mi)| &constraints[ci].methods()[mi])
+            .collect();
+        let order = toposort_indices::<M, _>(&methods, n_variables)?;
+        plan = order.into_iter().map(|i| plan[i]).collect();
+    }
+
+    replanned.sort_unstable();
+    replanned.dedup();
+    Some((
+        Previous {
+            revision: previous.revision,
+            ranking,
+            kept,
+            plan,
+            references: previous.references.clone(),
+        },
+        replanned,
+    ))
+}
+
+/// Returns the enforced constraints that can be reached from `variable`
+/// without passing through variables that keep their values.
+/// Since nothing writes to those, the constraints on either side of them can be planned separately.
+fn reachable<C: ConstraintSpec>(
+    constraints: &[C],
+    references: &[Vec<usize>],
+    kept: &[bool],
+    variable: usize,
+) -> Vec<usize> {
+    let mut region = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![variable];
+    visited.insert(variable);
+    while let Some(vi) = stack.pop() {
+        for &ci in &references[vi] {
+            if region.insert(ci) {
+                for &other in constraints[ci].variables() {
+                    if !kept[other] && visited.insert(other) {
+                        stack.push(other);
+                    }
+                }
+            }
+        }
+    }
+    let mut region: Vec<usize> = region.into_iter().collect();
+    region.sort_unstable();
+    region
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{promoted, repair, IncrementalPlanner};
+    use crate::{
+        examples::components::{ComponentFactory, Ladder, LinearTwoway, Random},
+        model::Component,
+        planner::{hierarchical_planner, OwnedPlan, Plan, PlanError, Strength},
+        ret,
+    };
+
+    /// Sorts the plan by constraint name, since the order of independent methods may differ.
+    fn sorted<M>(plan: Result<OwnedPlan<M>, PlanError>) -> Result<OwnedPlan<M>, PlanError> {
+        plan.map(|mut plan| {
+            plan.sort_by(|a, b| a.name().cmp(b.name()));
+            plan
+        })
+    }
+
+    #[test]
+    fn promoted_variables_are_found() {
+        assert_eq!(promoted(&[0, 1, 2], &[0, 1, 2]), Some(0));
+        assert_eq!(promoted(&[0, 1, 2], &[2, 0, 1]), Some(1));
+        assert_eq!(promoted(&[0, 1, 2], &[1, 2, 0]), Some(2));
+        assert_eq!(promoted(&[0, 1, 2], &[2, 1, 0]), Some(2));
+        assert_eq!(promoted(&[0, 1, 2], &[0, 1]), None);
+    }
+
+    #[test]
+    fn same_plans_as_hierarchical_planner_after_edits() {
+        let mut component: Component<()> = Random::build(100);
+        let names: Vec<String> = component
+            .variable_names()
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        let planner = IncrementalPlanner::new();
+        for (i, name) in names.iter().enumerate().cycle().take(300) {
+            assert_eq!(
+                sorted(planner.plan(&component)),
+                sorted(hierarchical_planner(&component)),
+                "plans differ after {} edits",
+                i
+            );
+            component.edit(name, ()).unwrap();
+        }
+    }
+
+    #[test]
+    fn uncached_plans_keep_the_previous_plan() {
+        let mut component: Component<()> = Ladder::build(20);
+        let planner = IncrementalPlanner::new();
+        planner.plan(&component).unwrap();
+        let previous = planner.previous.lock().unwrap().clone();
+        assert!(previous.is_some());
+
+        component.edit("var3", ()).unwrap();
+        assert_eq!(
+            sorted(planner.plan_uncached(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+        assert_eq!(*planner.previous.lock().unwrap(), previous);
+    }
+
+    #[test]
+    fn same_plans_as_hierarchical_planner_on_ladder_and_chain() {
+        for mut component in vec![Ladder::build(20), LinearTwoway::build(20)] {
+            let component: &mut Component<()> = &mut component;
+            let mut names: Vec<String> = component
+                .variable_names()
+                .into_iter()
+                .map(str::to_owned)
+                .collect();
+            names.sort();
+            let planner = IncrementalPlanner::new();
+            for name in names.iter().step_by(3).chain(names.iter().rev().step_by(2)) {
+                component.edit(name, ()).unwrap();
+                assert_eq!(
+                    sorted(planner.plan(component)),
+                    sorted(hierarchical_planner(component))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn only_edited_part_is_replanned() {
+        let mut component = dummy_component! {
+            let a, b, c, d;
+            constraint Ab {
+                ab(a) -> [b];
+                ba(b) -> [a];
+            }
+            constraint Cd {
+                cd(c) -> [d];
+                dc(d) -> [c];
+            }
+        };
+        let planner = IncrementalPlanner::new();
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+        let previous = planner.previous.lock().unwrap().clone().unwrap();
+
+        // Editing d should not affect the plan of the part with a and b
+        component.edit("d", ()).unwrap();
+        let (_, replanned) = repair(&component, &previous, component.ranking(), 1).unwrap();
+        assert_eq!(replanned, vec![1]);
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+
+        // Editing d again should not replan anything
+        let previous = planner.previous.lock().unwrap().clone().unwrap();
+        component.edit("d", ()).unwrap();
+        let (_, replanned) = repair(&component, &previous, component.ranking(), 1).unwrap();
+        assert_eq!(replanned, Vec::<usize>::new());
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+    }
+
+    #[test]
+    fn disabled_constraints_cause_replanning() {
+        let mut component = dummy_component! {
+            let a, b, c;
+            constraint Ab {
+                ab(a) -> [b];
+                ba(b) -> [a];
+            }
+            constraint Bc {
+                bc(b) -> [c];
+                cb(c) -> [b];
+            }
+        };
+        let planner = IncrementalPlanner::new();
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+        component.disable_constraint("Bc").unwrap();
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+        component.enable_constraint("Bc").unwrap();
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+    }
+
+    #[test]
+    fn changed_strengths_cause_replanning() {
+        let mut component = dummy_component! {
+            let a, b;
+            constraint Ab {
+                ab(a) -> [b];
+                ba(b) -> [a];
+            }
+            constraint Ba {
+                ab(a) -> [b];
+                ba(b) -> [a];
+            }
+        };
+        let planner = IncrementalPlanner::new();
+        component
+            .set_constraint_strength("Ab", Strength::Weak)
+            .unwrap();
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+        component
+            .set_constraint_strength("Ab", Strength::Strong)
+            .unwrap();
+        component
+            .set_constraint_strength("Ba", Strength::Weak)
+            .unwrap();
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+        component
+            .set_constraint_strength("Ba", Strength::Required)
+            .unwrap();
+        assert_eq!(
+            sorted(planner.plan(&component)),
+            sorted(hierarchical_planner(&component))
+        );
+    }
+}
@@ -3,20 +3,25 @@
 // TODO: Finish experimental planners.
 // pub mod experimental;
 
+pub(self) mod conflict;
+pub(crate) mod dependencies;
 mod hierarchical;
+mod incremental;
 mod plan_trait;
 pub(crate) mod priority_adjuster;
 pub(self) mod pruner;
 mod simple;
 mod spec;
+pub(self) mod subgraph;
 pub(self) mod toposorter;
 
 pub use hierarchical::{
-    hierarchical_planner, HierarchicalPlanner, OwnedEnforcedConstraint, Vertex,
+    hierarchical_planner, HierarchicalPlanner, OwnedEnforcedConstraint, OwnedPlan, Vertex,
 };
-pub use plan_trait::Plan;
-pub use simple::{simple_planner, simple_planner_toposort, EnforcedConstraint};
+pub use incremental::IncrementalPlanner;
+pub use plan_trait::{Plan, SharedPlanner};
+pub use simple::{simple_planner, simple_planner_toposort, EnforcedConstraint, SimplePlanner};
 pub use spec::{
-    ComponentSpec, ConstraintSpec, MethodFailure, MethodFunction, MethodResult, MethodSpec,
-    PlanError,
+    AsyncMethodFunction, ComponentSpec, Conflict, ConstraintSpec, MethodFailure, MethodFunction,
+    MethodFunctionMut, MethodFuture, MethodResult, MethodSpec, PlanError, Strength,
 };
@@ -1,13 +1,26 @@
-use super::EnforcedConstraint;
-use super::{ComponentSpec, ConstraintSpec, MethodSpec, PlanError};
-use std::fmt::Debug;
+use super::{hierarchical::OwnedPlan, ComponentSpec, ConstraintSpec, PlanError};
+use std::sync::Arc;
 
 /// A trait for planners to implement.
-pub trait Plan {
+///
+/// A planner takes a component and selects one method per active constraint,
+/// returning them in an order in which they can be executed.
+pub trait Plan<Comp: ComponentSpec> {
     /// Constructs a plan for the component.
-    fn plan<T, M, C, Comp>(component: &Comp) -> Result<Vec<EnforcedConstraint<'_, T>>, PlanError>
-    where
-        M: MethodSpec<Arg = T> + Clone,
-        C: ConstraintSpec<Method = M> + Debug + Clone,
-        Comp: ComponentSpec<Constraint = C> + Clone;
+    fn plan(
+        &self,
+        component: &Comp,
+    ) -> Result<OwnedPlan<<Comp::Constraint as ConstraintSpec>::Method>, PlanError>;
+
+    /// Constructs a plan for the component that is only inspected, not run.
+    /// Planners that keep state between plans should leave it unchanged.
+    fn plan_uncached(
+        &self,
+        component: &Comp,
+    ) -> Result<OwnedPlan<<Comp::Constraint as ConstraintSpec>::Method>, PlanError> {
+        self.plan(component)
+    }
 }
+
+/// A shared, type-erased planner that can be stored in a component.
+pub type SharedPlanner<Comp> = Arc<dyn Plan<Comp> + Send + Sync>;
@@ -3,8 +3,14 @@
 //! Given a component, it will find one method per constraint to enforce it, such that the methods and the variables they
 //! read from and write to form a directed acyclic graph.
 
-use super::{hierarchical::Vertex, toposorter::toposort};
-use crate::planner::{ComponentSpec, ConstraintSpec, MethodSpec};
+use super::{
+    conflict::overconstrained,
+    hierarchical::{OwnedPlan, Vertex},
+    plan_trait,
+    toposorter::toposort,
+    OwnedEnforcedConstraint,
+};
+use crate::planner::{ComponentSpec, ConstraintSpec, MethodSpec, PlanError};
 use std::{collections::VecDeque, fmt::Debug};
 
 /// Maintains a list of constraints that reference this variable.
@@ -132,11 +138,43 @@ where
     M: MethodSpec,
     C: ConstraintSpec<Method = M> + 'a + Debug,
     Comp: ComponentSpec<Constraint = C>,
+{
+    let constraints = component.constraints();
+    let plan = simple_planner_indexed(component)?;
+    Some(
+        plan.into_iter()
+            .map(|(ci, m)| EnforcedConstraint::new(constraints[ci].name(), m))
+            .collect(),
+    )
+}
+
+/// Like [`simple_planner`], but pairs each selected method with the index of its constraint.
+pub(crate) fn simple_planner_indexed<'a, M, C, Comp>(
+    component: &'a Comp,
+) -> Option<Vec<(usize, &'a M)>>
+where
+    M: MethodSpec,
+    C: ConstraintSpec<Method = M> + 'a,
+    Comp: ComponentSpec<Constraint = C>,
+{
+    simple_planner_or_remaining(component).ok()
+}
+
+/// Like [`simple_planner_indexed`], but returns the indices of the constraints
+/// that could not be enforced if planning fails.
+pub(crate) fn simple_planner_or_remaining<'a, M, C, Comp>(
+    component: &'a Comp,
+) -> Result<Vec<(usize, &'a M)>, Vec<usize>>
+where
+    M: MethodSpec,
+    C: ConstraintSpec<Method = M> + 'a,
+    Comp: ComponentSpec<Constraint = C>,
 {
     let mut plan = Vec::with_capacity(component.constraints().len());
     let n_variables = component.n_variables();
     let constraints = component.constraints();
     let mut remaining_constraints = constraints.len();
+    let mut is_enforced = vec![false; constraints.len()];
 
     // Find the total use-count for each variable (n where n number of constraints)
     let mut variables = VariableRefCounter::count_variable_refs(component);
@@ -151,7 +189,14 @@ where
 
     while remaining_constraints != 0 {
         // Pick the first current interesting variable
-        let idx = potentially_free_variables.pop_front()?;
+        let idx = match potentially_free_variables.pop_front() {
+            Some(idx) => idx,
+            None => {
+                return Err((0..constraints.len())
+                    .filter(|&ci| !is_enforced[ci])
+                    .collect())
+            }
+        };
         let interesting_variable = &variables[idx];
 
         // May have become uninteresting since we added it.
@@ -188,7 +233,8 @@ where
 
             if let Some(m) = free_method {
                 // Add this method to the plan
-                plan.push(EnforcedConstraint::new(constraint.name(), m));
+                plan.push((ci, m));
+                is_enforced[ci] = true;
                 remaining_constraints -= 1;
 
                 // Remove all references to this constraint
@@ -203,7 +249,7 @@ where
         }
     }
 
-    Some(plan)
+    Ok(plan)
 }
 
 /// Runs the [`simple_planner`], and then topologically sorts the resulting plan.
@@ -222,13 +268,50 @@ where
     Some(sorted_plan.into_iter().cloned().collect())
 }
 
+/// A planner that enforces all active constraints using [`simple_planner_toposort`].
+/// Unlike [`HierarchicalPlanner`](super::HierarchicalPlanner), it does not take the ranking of variables
+/// or the strength of constraints into account.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimplePlanner;
+
+impl<M, C, Comp> plan_trait::Plan<Comp> for SimplePlanner
+where
+    M: MethodSpec + Clone,
+    C: ConstraintSpec<Method = M> + Debug + Clone,
+    Comp: ComponentSpec<Constraint = C> + Clone,
+{
+    fn plan(&self, component: &Comp) -> Result<OwnedPlan<M>, PlanError> {
+        let to_owned = |plan: Vec<EnforcedConstraint<'_, M>>| -> OwnedPlan<M> {
+            plan.into_iter()
+                .map(OwnedEnforcedConstraint::from)
+                .collect()
+        };
+        if component.constraints().iter().all(C::is_active) {
+            simple_planner_toposort(component).map(to_owned)
+        } else {
+            // Filter out inactive constraints
+            let mut component = component.clone();
+            component.constraints_mut().retain(C::is_active);
+            simple_planner_toposort(&component).map(to_owned)
+        }
+        .ok_or_else(|| {
+            let active: Vec<&C> = component
+                .constraints()
+                .iter()
+                .filter(|c| c.is_active())
+                .collect();
+            overconstrained(component, &active)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::EnforcedConstraint;
+    use super::{EnforcedConstraint, SimplePlanner};
     use crate::planner::{
         simple::{simple_planner, simple_planner_toposort},
         toposorter::toposort,
-        ComponentSpec,
+        ComponentSpec, OwnedEnforcedConstraint, Plan,
     };
     use crate::{
         model::{Component, ConstraintSystem},
@@ -380,6 +463,38 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn simple_planner_ignores_inactive_constraints() {
+        let mut comp: Component<i32> = crate::component! {
+            component Comp {
+                let a: i32 = 0, b: i32 = 0;
+                constraint A {
+                    a1(a: &i32) -> [b] = ret![*a];
+                }
+                constraint B {
+                    b1(a: &i32) -> [b] = ret![*a + 1];
+                }
+            }
+        };
+        assert_eq!(
+            SimplePlanner.plan(&comp),
+            Err(crate::planner::PlanError::Overconstrained(
+                crate::planner::Conflict::new(
+                    vec!["A".to_owned(), "B".to_owned()],
+                    vec!["a".to_owned(), "b".to_owned()]
+                )
+            ))
+        );
+        comp.disable_constraint("B").unwrap();
+        assert_eq!(
+            SimplePlanner.plan(&comp),
+            Ok(vec![OwnedEnforcedConstraint::new(
+                "A",
+                comp["A"]["a1"].clone()
+            )])
+        );
+    }
+
     extern crate test;
     use test::Bencher;
 
@@ -1,9 +1,13 @@
 //! Useful traits for constraint systems that define the interface required for planning.
 
-use crate::{builders::method_builder::MutabilityMismatch, planner::Vertex};
+use crate::{
+    builders::method_builder::MutabilityMismatch, executor::CancellationToken, planner::Vertex,
+};
 use std::{
     fmt::{Debug, Display},
+    future::Future,
     ops::{Index, IndexMut},
+    pin::Pin,
     sync::Arc,
 };
 
@@ -30,6 +34,19 @@ pub type MethodResult<T> = Result<Vec<T>, MethodFailure>;
 /// The function contained within a method.
 pub type MethodFunction<T> = Arc<dyn Fn(Vec<Arc<T>>) -> MethodResult<Arc<T>> + Send + Sync>;
 
+/// The function contained within a method that mutates some of its arguments in place.
+/// It may only modify the arguments it declared as mutable, for instance with [`Arc::make_mut`],
+/// and returns the values of its other outputs.
+/// The [`CancellationToken`] is cancelled once the result is no longer needed.
+pub type MethodFunctionMut<T> =
+    Arc<dyn Fn(&mut [Arc<T>], &CancellationToken) -> MethodResult<Arc<T>> + Send + Sync>;
+
+/// The future returned by the function of an asynchronous method.
+pub type MethodFuture<T> = Pin<Box<dyn Future<Output = MethodResult<Arc<T>>> + Send>>;
+
+/// The function contained within an asynchronous method.
+pub type AsyncMethodFunction<T> = Arc<dyn Fn(Vec<Arc<T>>) -> MethodFuture<T> + Send + Sync>;
+
 /// An extension of the [`Vertex`] trait for methods.
 pub trait MethodSpec: Vertex {
     /// The input and output type of the method.
@@ -70,21 +87,118 @@ pub trait ConstraintSpec {
     fn variables(&self) -> &[usize];
     /// Whether or not this constraint is active.
     fn is_active(&self) -> bool;
+    /// The strength of the constraint.
+    /// Weaker constraints are left unenforced if they conflict with stronger ones.
+    fn strength(&self) -> Strength {
+        Strength::Required
+    }
+}
+
+/// The strength of a constraint in a constraint hierarchy.
+///
+/// Strengths are ordered from weakest to strongest, and all of them are stronger
+/// than the stay constraints used to avoid modifying recently edited variables.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Strength {
+    /// The first constraint to be dropped in case of conflicts.
+    Weak,
+    /// Dropped before strong and required constraints.
+    Medium,
+    /// Only dropped in case of conflicts with required constraints.
+    Strong,
+    /// Must always be enforced.
+    Required,
+}
+
+impl Default for Strength {
+    fn default() -> Self {
+        Strength::Required
+    }
+}
+
+impl Display for Strength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Strength::Weak => "weak",
+            Strength::Medium => "medium",
+            Strength::Strong => "strong",
+            Strength::Required => "required",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 /// Errors that can occur during planning.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum PlanError {
     /// The system was overconstrained, and no plan was found.
-    Overconstrained,
+    Overconstrained(Conflict),
 }
 
 impl Display for PlanError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "No valid plan was found since the system is overconstrained"
-        )
+        match self {
+            PlanError::Overconstrained(conflict) => write!(
+                f,
+                "No valid plan was found since the system is overconstrained{}",
+                conflict
+            ),
+        }
+    }
+}
+
+/// A set of constraints that can not be enforced at the same time,
+/// and the variables they conflict over.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Conflict {
+    component: Option<String>,
+    constraints: Vec<String>,
+    variables: Vec<String>,
+}
+
+impl Conflict {
+    /// Constructs a new [`Conflict`] between the specified constraints over the specified variables.
+    pub fn new(constraints: Vec<String>, variables: Vec<String>) -> Self {
+        Self {
+            component: None,
+            constraints,
+            variables,
+        }
+    }
+
+    /// Returns the name of the component the conflict is in, if known.
+    pub fn component(&self) -> Option<&str> {
+        self.component.as_deref()
+    }
+
+    /// Sets the name of the component the conflict is in.
+    pub fn set_component(&mut self, component: impl Into<String>) {
+        self.component = Some(component.into());
+    }
+
+    /// Returns the names of the conflicting constraints.
+    pub fn constraints(&self) -> &[String] {
+        &self.constraints
+    }
+
+    /// Returns the names of the variables the constraints conflict over.
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(component) = &self.component {
+            write!(f, " in component {}", component)?;
+        }
+        if !self.constraints.is_empty() {
+            write!(f, ": constraints {}", self.constraints.join(", "))?;
+            if !self.variables.is_empty() {
+                write!(f, " conflict over {}", self.variables.join(", "))?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -120,4 +234,15 @@ pub trait ComponentSpec: Index<&'static str> + IndexMut<&'static str> {
     fn remove_constraint(&mut self, idx: usize) -> Self::Constraint;
     /// Returns the ranking of variables.
     fn ranking(&self) -> Vec<usize>;
+    /// Returns the name of the variable with the specified index, if it has one.
+    fn variable_name(&self, _index: usize) -> Option<&str> {
+        None
+    }
+    /// Returns a number that identifies the current variables and constraints of the component,
+    /// or `None` if changes to them are not tracked.
+    /// It changes whenever they are added, removed or modified, but not when the ranking changes,
+    /// so that planners can tell whether what they learned from a previous plan still applies.
+    fn revision(&self) -> Option<usize> {
+        None
+    }
 }
@@ -0,0 +1,80 @@
+//! Parts of a constraint graph that can be planned by themselves.
+
+use crate::planner::{ComponentSpec, ConstraintSpec};
+use std::ops::{Index, IndexMut};
+
+/// A part of a constraint graph, such as the constraints involved in a conflict.
+/// Variables keep the indices they have in the original component,
+/// so that methods can be used without being rewritten.
+#[derive(Clone, Debug)]
+pub struct Subgraph<C> {
+    n_variables: usize,
+    constraints: Vec<C>,
+}
+
+impl<C> Subgraph<C> {
+    /// Constructs a new subgraph over `n_variables` variables with the specified constraints.
+    pub fn from_constraints(n_variables: usize, constraints: Vec<C>) -> Self {
+        Self {
+            n_variables,
+            constraints,
+        }
+    }
+}
+
+impl<C: ConstraintSpec> ComponentSpec for Subgraph<C> {
+    type Value = ();
+    type Constraint = C;
+
+    fn new(_: String, values: Vec<impl Into<Self::Value>>, constraints: Vec<C>) -> Self {
+        Self::from_constraints(values.len(), constraints)
+    }
+
+    fn n_variables(&self) -> usize {
+        self.n_variables
+    }
+
+    fn constraints(&self) -> &[C] {
+        &self.constraints
+    }
+
+    fn constraints_mut(&mut self) -> &mut Vec<C> {
+        &mut self.constraints
+    }
+
+    fn add_constraint(&mut self, constraint: C) {
+        self.constraints.push(constraint)
+    }
+
+    fn pop_constraint(&mut self) -> Option<C> {
+        self.constraints.pop()
+    }
+
+    fn remove_constraint(&mut self, idx: usize) -> C {
+        self.constraints.remove(idx)
+    }
+
+    fn ranking(&self) -> Vec<usize> {
+        (0..self.n_variables).collect()
+    }
+}
+
+impl<C: ConstraintSpec> Index<&str> for Subgraph<C> {
+    type Output = C;
+
+    fn index(&self, index: &str) -> &Self::Output {
+        self.constraints
+            .iter()
+            .find(|c| c.name() == index)
+            .unwrap_or_else(|| panic!("No constraint named {}", index))
+    }
+}
+
+impl<C: ConstraintSpec> IndexMut<&str> for Subgraph<C> {
+    fn index_mut(&mut self, index: &str) -> &mut Self::Output {
+        self.constraints
+            .iter_mut()
+            .find(|c| c.name() == index)
+            .unwrap_or_else(|| panic!("No constraint named {}", index))
+    }
+}
@@ -2,18 +2,29 @@
 //! This must be done before executing them.
 
 use super::hierarchical::Vertex;
+use std::borrow::Borrow;
 
 /// Topologically sort a slice of method references.
 pub fn toposort<M>(methods: &[M], n_variables: usize) -> Option<Vec<&M>>
 where
     M: Vertex,
+{
+    let order = toposort_indices::<M, M>(methods, n_variables)?;
+    Some(order.into_iter().map(|m_id| &methods[m_id]).collect())
+}
+
+/// Topologically sort a slice of methods or method references, and return their indices in sorted order.
+pub fn toposort_indices<M, R>(methods: &[R], n_variables: usize) -> Option<Vec<usize>>
+where
+    M: Vertex,
+    R: Borrow<M>,
 {
     let n_methods = methods.len();
     // Create adjacency list to go from a variable to methods using it as input
     let mut var_to_methods = vec![Vec::new(); n_variables];
     for (m_id, m) in methods.iter().enumerate() {
         // Variable -> methods
-        for &var_id in m.inputs() {
+        for &var_id in m.borrow().inputs() {
             var_to_methods[var_id].push(m_id);
         }
     }
@@ -23,7 +34,7 @@ where
     // For each method and its id
     for (m_id, m) in methods.iter().enumerate() {
         // For each of its outputs
-        for &out_var in m.outputs() {
+        for &out_var in m.borrow().outputs() {
             // Add an edge to methods using this output as input
             for &m_target in &var_to_methods[out_var] {
                 // But ignore self-loops
@@ -50,7 +61,8 @@ where
         }
     }
 
-    Some(order.into_iter().map(|m_id| &methods[m_id]).rev().collect())
+    order.reverse();
+    Some(order)
 }
 
 /// Dfs through an adjacency list and store post-numbers.
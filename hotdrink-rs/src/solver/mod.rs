@@ -5,4 +5,4 @@ mod solve_error;
 mod solver;
 
 pub use solve_error::{Reason, SolveError};
-pub(crate) use solver::solve;
+pub(crate) use solver::{solve, solve_with_locations};
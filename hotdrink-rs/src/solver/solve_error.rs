@@ -24,6 +24,11 @@ impl SolveError {
             reason,
         }
     }
+
+    /// Returns the reason for the error.
+    pub fn reason(&self) -> &Reason {
+        &self.reason
+    }
 }
 
 impl Display for SolveError {
@@ -53,6 +58,7 @@ impl Display for SolveError {
                 },
             },
             Reason::Cancelled => "Computation was cancelled due to new input".to_string(),
+            Reason::Timeout => "Computation did not finish in time".to_string(),
         };
         write!(
             f,
@@ -73,6 +79,8 @@ pub enum Reason {
     MethodFailure(MethodFailure),
     /// The computation was cancelled.
     Cancelled,
+    /// The computation did not finish before its timeout.
+    Timeout,
 }
 
 impl Display for Reason {
@@ -82,6 +90,7 @@ impl Display for Reason {
             Reason::PostConditionFailure(msg) => write!(f, "a postcondition not holding: {}", msg),
             Reason::MethodFailure(me) => write!(f, "a method failure: {:?}", me),
             Reason::Cancelled => write!(f, "computation was cancelled"),
+            Reason::Timeout => write!(f, "computation timing out"),
         }
     }
 }
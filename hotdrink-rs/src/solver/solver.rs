@@ -16,10 +16,12 @@ use crate::{
 use crate::{
     model::Method,
     planner::{OwnedEnforcedConstraint, Vertex},
+    stats::SharedStats,
 };
 use std::{
     fmt::Debug,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use super::SolveError;
@@ -31,13 +33,18 @@ use super::SolveError;
 /// 2. The current values of a component.
 /// 3. The component name for better error messages.
 /// 4. The generation to know which solve new values came from.
-/// 5. A [`MethodExecutor`] implementation for running methods in a plan.
-/// 6. A callback to pass new produced values to. These events include the component name and the generation.
+/// 5. The timeout of methods that do not have their own.
+/// 6. The statistics to record method calls in, if they are collected.
+/// 7. A [`MethodExecutor`] implementation for running methods in a plan.
+/// 8. A callback to pass new produced values to. These events include the component name and the generation.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn solve<T>(
     plan: &[OwnedEnforcedConstraint<Method<T>>],
     current_values: &mut Variables<Activation<T>>,
     component_name: String,
     generation: GenerationId,
+    default_timeout: Option<Duration>,
+    stats: Option<&SharedStats>,
     me: &impl MethodExecutor,
     general_callback: impl Fn(EventWithLocation<'_, T, SolveError>) + Send + 'static + Clone,
 ) where
@@ -47,8 +54,37 @@ pub(crate) fn solve<T>(
         log::info!("Solving {}", component_name);
     }
 
+    solve_with_locations(
+        plan,
+        current_values,
+        |constraint| (component_name.clone(), constraint.to_owned()),
+        |_| default_timeout,
+        generation,
+        stats,
+        me,
+        general_callback,
+    )
+}
+
+/// Schedules methods of plan to be run on a method executor, like [`solve`].
+///
+/// Instead of a component name, it takes a function that returns the component and constraint name
+/// to use in errors from each constraint in the plan, and one that returns the default timeout of its methods.
+/// This is used when the plan contains constraints from multiple components.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn solve_with_locations<T>(
+    plan: &[OwnedEnforcedConstraint<Method<T>>],
+    current_values: &mut Variables<Activation<T>>,
+    locate: impl Fn(&str) -> (String, String),
+    default_timeout: impl Fn(&str) -> Option<Duration>,
+    generation: GenerationId,
+    stats: Option<&SharedStats>,
+    me: &impl MethodExecutor,
+    general_callback: impl Fn(EventWithLocation<'_, T, SolveError>) + Send + 'static + Clone,
+) where
+    T: Send + Sync + 'static + Debug,
+{
     for osc in plan {
-        let constraint_name = osc.name();
         let m = osc.method();
         log::info!("Activating {:?}", m);
 
@@ -72,9 +108,9 @@ pub(crate) fn solve<T>(
             general_callback(EventWithLocation::new(o, generation, Event::Pending));
         }
 
-        // Pick inputs from current values
+        // Pick inputs from current values, including the old values of variables to mutate
         let mut inputs: Vec<Activation<_>> = m
-            .inputs()
+            .arguments()
             .iter()
             .map(|&i| current_values[i].clone())
             .collect();
@@ -86,30 +122,70 @@ pub(crate) fn solve<T>(
             a.clear_error();
         }
 
+        // Keep the values the conditions of the constraint are checked against
+        let condition_values = match m.conditions() {
+            Some(conditions) => conditions
+                .variables()
+                .iter()
+                .map(|&vi| {
+                    let mut activation = current_values[vi].weak_clone();
+                    activation.clear_error();
+                    activation
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // The old values of mutated variables are kept alive as previous values
+        let mutated = m.mutated();
+        let dependencies: Vec<Activation<_>> = inputs
+            .iter()
+            .zip(m.arguments())
+            .filter(|(_, vi)| !mutated.contains(vi))
+            .map(|(a, _)| a.clone())
+            .collect();
+
         let mut shared_states = Vec::with_capacity(m.outputs().len());
         for &o in m.outputs() {
             current_values.get_mut(o).unwrap().detach();
             // Keep the old value from the previous state, but set to pending
             let previous = current_values[o].clone();
-            let shared_state = ActivationInner::new(previous, inputs.clone());
+            let shared_state = ActivationInner::new(previous, dependencies.clone());
             shared_states.push(Arc::new(RwLock::new(shared_state)));
         }
+        drop(dependencies);
+
+        // Replace the old values before the method runs,
+        // so that they are only kept alive if needed for undo.
+        for (shared_state, &o) in shared_states.iter().zip(m.outputs()) {
+            current_values.set(
+                o,
+                Activation {
+                    inner: shared_state.clone(),
+                    producer: None,
+                },
+            );
+        }
 
         // Compute outputs
         let weak_clone_inputs = inputs.iter().map(|a| a.weak_clone()).collect();
+        drop(inputs);
         let outputs = m.activate(
             weak_clone_inputs,
+            condition_values,
             shared_states,
-            (component_name.to_owned(), constraint_name.to_owned()),
+            locate(osc.name()),
             generation,
+            m.timeout().or_else(|| default_timeout(osc.name())),
+            stats,
             me,
             general_callback.clone(),
         );
 
-        // Re-insert outputs
+        // Keep the outputs along with their producers
         outputs
             .into_iter()
             .zip(m.outputs())
-            .for_each(|(v, &o)| current_values.set(o, v));
+            .for_each(|(v, &o)| *current_values.get_mut(o).unwrap().get_mut() = v);
     }
 }
@@ -0,0 +1,277 @@
+//! Statistics about how much time solving takes, to find the methods that dominate response times.
+//!
+//! Collecting them is opt-in, see [`Component::enable_stats`](crate::model::Component::enable_stats)
+//! and [`ConstraintSystem::enable_stats`](crate::model::ConstraintSystem::enable_stats).
+//! The timer works in WebAssembly too, where [`std::time::Instant`] is not available.
+
+use crate::util::table::Table;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+pub(crate) use instant::Instant;
+
+/// Statistics about the calls to one method.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MethodStats {
+    invocations: usize,
+    wall_time: Duration,
+    wait_time: Duration,
+    cancellations: usize,
+    errors: usize,
+}
+
+impl MethodStats {
+    /// Returns the number of times the method has been called.
+    pub fn invocations(&self) -> usize {
+        self.invocations
+    }
+
+    /// Returns the total time spent running the method.
+    pub fn wall_time(&self) -> Duration {
+        self.wall_time
+    }
+
+    /// Returns the total time from the method being scheduled until it starts, waiting for inputs and the executor.
+    pub fn wait_time(&self) -> Duration {
+        self.wait_time
+    }
+
+    /// Returns the number of times the method has been cancelled before it finished.
+    pub fn cancellations(&self) -> usize {
+        self.cancellations
+    }
+
+    /// Returns the number of times the method has failed, including timeouts and broken conditions.
+    /// Errors in its inputs are not counted.
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// Returns the average time spent running the method.
+    pub fn mean_wall_time(&self) -> Duration {
+        match self.invocations {
+            0 => Duration::default(),
+            n => self.wall_time / n as u32,
+        }
+    }
+}
+
+/// The component, constraint and method names that identify a method.
+type MethodKey = (String, String, String);
+
+/// Statistics about the methods and planning of a [`Component`](crate::model::Component)
+/// or [`ConstraintSystem`](crate::model::ConstraintSystem).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    methods: BTreeMap<MethodKey, MethodStats>,
+    solves: usize,
+    planning_time: Duration,
+}
+
+impl Stats {
+    /// Returns the statistics of a method, if it has been scheduled.
+    pub fn method(&self, component: &str, constraint: &str, method: &str) -> Option<&MethodStats> {
+        self.methods.get(&(
+            component.to_owned(),
+            constraint.to_owned(),
+            method.to_owned(),
+        ))
+    }
+
+    /// Returns the statistics of each method that has been scheduled,
+    /// along with its component, constraint and method name.
+    pub fn methods(&self) -> impl Iterator<Item = ((&str, &str, &str), &MethodStats)> {
+        self.methods
+            .iter()
+            .map(|((component, constraint, method), stats)| {
+                (
+                    (component.as_str(), constraint.as_str(), method.as_str()),
+                    stats,
+                )
+            })
+    }
+
+    /// Returns the number of plans that have been run.
+    pub fn solves(&self) -> usize {
+        self.solves
+    }
+
+    /// Returns the total time spent planning.
+    /// Plans that were reused from a cache, or only made to be inspected, are not included.
+    pub fn planning_time(&self) -> Duration {
+        self.planning_time
+    }
+
+    /// Returns the average time spent planning per solve.
+    pub fn mean_planning_time(&self) -> Duration {
+        match self.solves {
+            0 => Duration::default(),
+            n => self.planning_time / n as u32,
+        }
+    }
+
+    /// Returns a table with a row for each method, sorted by the total time spent running it.
+    /// Times are in milliseconds.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(vec![
+            "component",
+            "constraint",
+            "method",
+            "invocations",
+            "wall time (ms)",
+            "mean wall time (ms)",
+            "wait time (ms)",
+            "cancellations",
+            "errors",
+        ]);
+        let mut methods: Vec<_> = self.methods().collect();
+        methods.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.wall_time));
+        let ms = |duration: Duration| format!("{:.3}", duration.as_secs_f64() * 1000.0);
+        for ((component, constraint, method), stats) in methods {
+            table.push_row(vec![
+                component.to_owned(),
+                constraint.to_owned(),
+                method.to_owned(),
+                stats.invocations.to_string(),
+                ms(stats.wall_time),
+                ms(stats.mean_wall_time()),
+                ms(stats.wait_time),
+                stats.cancellations.to_string(),
+                stats.errors.to_string(),
+            ]);
+        }
+        table
+    }
+
+    /// Notes that a plan was run.
+    pub(crate) fn record_solve(&mut self) {
+        self.solves += 1;
+    }
+
+    /// Notes that the planner spent `planning_time` making a plan to run.
+    pub(crate) fn record_planning(&mut self, planning_time: Duration) {
+        self.planning_time += planning_time;
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} solves, {:?} planning ({:?} per solve)",
+            self.solves,
+            self.planning_time,
+            self.mean_planning_time()
+        )?;
+        write!(f, "{}", self.to_table())
+    }
+}
+
+/// Statistics that are shared with the methods that update them.
+pub(crate) type SharedStats = Arc<Mutex<Stats>>;
+
+/// Updates the statistics of one method, or does nothing if they are not collected.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MethodRecorder {
+    target: Option<(SharedStats, MethodKey)>,
+}
+
+impl MethodRecorder {
+    /// Constructs a new [`MethodRecorder`] for the specified method.
+    pub fn new(
+        stats: Option<&SharedStats>,
+        component: &str,
+        constraint: &str,
+        method: &str,
+    ) -> Self {
+        Self {
+            target: stats.map(|stats| {
+                let key = (
+                    component.to_owned(),
+                    constraint.to_owned(),
+                    method.to_owned(),
+                );
+                (stats.clone(), key)
+            }),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut MethodStats)) {
+        if let Some((stats, key)) = &self.target {
+            f(stats
+                .lock()
+                .unwrap()
+                .methods
+                .entry(key.clone())
+                .or_default());
+        }
+    }
+
+    /// Notes that the method started after waiting for `wait_time`.
+    pub fn started(&self, wait_time: Duration) {
+        self.update(|stats| stats.wait_time += wait_time);
+    }
+
+    /// Notes that a call to the method took `wall_time`.
+    pub fn finished(&self, wall_time: Duration) {
+        self.update(|stats| {
+            stats.invocations += 1;
+            stats.wall_time += wall_time;
+        });
+    }
+
+    /// Notes that the method was cancelled.
+    pub fn cancelled(&self) {
+        self.update(|stats| stats.cancellations += 1);
+    }
+
+    /// Notes that the method failed.
+    pub fn failed(&self) {
+        self.update(|stats| stats.errors += 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MethodRecorder, Stats};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[test]
+    fn records_methods_by_name() {
+        let stats = Arc::new(Mutex::new(Stats::default()));
+        let recorder = MethodRecorder::new(Some(&stats), "A", "Sum", "abc");
+        recorder.started(Duration::from_millis(1));
+        recorder.finished(Duration::from_millis(4));
+        recorder.finished(Duration::from_millis(2));
+        recorder.failed();
+        MethodRecorder::new(None, "A", "Sum", "acb").cancelled();
+
+        let stats = stats.lock().unwrap();
+        let abc = stats.method("A", "Sum", "abc").unwrap();
+        assert_eq!(abc.invocations(), 2);
+        assert_eq!(abc.wall_time(), Duration::from_millis(6));
+        assert_eq!(abc.mean_wall_time(), Duration::from_millis(3));
+        assert_eq!(abc.wait_time(), Duration::from_millis(1));
+        assert_eq!(abc.errors(), 1);
+        assert_eq!(abc.cancellations(), 0);
+        assert_eq!(stats.method("A", "Sum", "acb"), None);
+    }
+
+    #[test]
+    fn tables_are_sorted_by_wall_time() {
+        let stats = Arc::new(Mutex::new(Stats::default()));
+        MethodRecorder::new(Some(&stats), "A", "C", "fast").finished(Duration::from_millis(1));
+        MethodRecorder::new(Some(&stats), "A", "C", "slow").finished(Duration::from_millis(5));
+        let table = stats.lock().unwrap().to_table();
+        let methods: Vec<&str> = table.rows().iter().map(|row| row[2].as_str()).collect();
+        assert_eq!(methods, vec!["slow", "fast"]);
+        assert_eq!(table.rows()[0][4], "5.000");
+    }
+}
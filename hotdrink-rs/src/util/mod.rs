@@ -1,3 +1,4 @@
 //! Utility functions.
 
 pub mod fib;
+pub mod table;
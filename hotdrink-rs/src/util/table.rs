@@ -0,0 +1,121 @@
+//! A simple table of text, for printing measurements such as [`Stats`](crate::stats::Stats).
+
+use std::fmt::{self, Display};
+
+/// A table with a header and rows of text.
+///
+/// It is displayed with aligned columns, and can be exported as CSV.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Constructs a new [`Table`] with the specified column names.
+    pub fn new<S: ToString>(header: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            header: header.into_iter().map(|s| s.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row to the table.
+    /// Missing cells are left empty, and extra ones are ignored.
+    pub fn push_row<S: ToString>(&mut self, row: impl IntoIterator<Item = S>) {
+        let mut row: Vec<String> = row
+            .into_iter()
+            .take(self.header.len())
+            .map(|s| s.to_string())
+            .collect();
+        row.resize(self.header.len(), String::new());
+        self.rows.push(row);
+    }
+
+    /// Returns the column names.
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    /// Returns the rows.
+    pub fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+
+    /// Returns the table as comma-separated values, with the header on the first line.
+    pub fn to_csv(&self) -> String {
+        let escape = |cell: &String| {
+            if cell.contains(&[',', '"', '\n'][..]) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        };
+        std::iter::once(&self.header)
+            .chain(&self.rows)
+            .map(|row| row.iter().map(escape).collect::<Vec<_>>().join(",") + "\n")
+            .collect()
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths: Vec<usize> = (0..self.header.len())
+            .map(|ci| {
+                std::iter::once(&self.header)
+                    .chain(&self.rows)
+                    .map(|row| row[ci].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let write_row = |f: &mut fmt::Formatter<'_>, row: &[String]| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+                .collect();
+            writeln!(f, "{}", cells.join(" | ").trim_end())
+        };
+        write_row(f, &self.header)?;
+        let separator: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+        writeln!(f, "{}", separator.join("-|-"))?;
+        for row in &self.rows {
+            write_row(f, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Table;
+
+    fn table() -> Table {
+        let mut table = Table::new(vec!["name", "count"]);
+        table.push_row(vec!["a", "1"]);
+        table.push_row(vec!["long, name", "10", "ignored"]);
+        table.push_row(vec!["missing"]);
+        table
+    }
+
+    #[test]
+    fn displays_aligned_columns() {
+        assert_eq!(
+            table().to_string(),
+            "name       | count\n\
+             -----------|------\n\
+             a          | 1\n\
+             long, name | 10\n\
+             missing    |\n"
+        );
+    }
+
+    #[test]
+    fn exports_csv() {
+        assert_eq!(
+            table().to_csv(),
+            "name,count\na,1\n\"long, name\",10\nmissing,\n"
+        );
+    }
+}
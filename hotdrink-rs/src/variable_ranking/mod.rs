@@ -1,9 +1,11 @@
-//! Rank variables based on when they were last updated.
+//! Rank variables based on when they were last updated, or on priorities assigned to them.
 
 pub mod linked_list_ranker;
+pub mod ranking_strategy;
 pub mod sort_ranker;
 pub mod variable_ranker;
 
 pub use linked_list_ranker::LinkedListRanker;
+pub use ranking_strategy::{Rank, RankingStrategy, SharedRankingStrategy};
 pub use sort_ranker::SortRanker;
 pub use variable_ranker::VariableRanker;
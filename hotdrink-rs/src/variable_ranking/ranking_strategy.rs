@@ -0,0 +1,81 @@
+//! Strategies for ranking the variables of a component,
+//! see [`Component::set_ranking_strategy`](crate::model::Component::set_ranking_strategy).
+
+use std::{cmp::Reverse, sync::Arc};
+
+/// A trait for ranking strategies to implement.
+///
+/// A component keeps track of how recently each variable was edited with a [`SortRanker`](super::SortRanker),
+/// and a ranking strategy turns that into the ranking used when planning.
+pub trait Rank {
+    /// Ranks variables given the ranking by recency and the priority level of each variable, by index.
+    /// The first variable of the result has the highest priority,
+    /// and every variable of `recency` must be included once.
+    fn rank(&self, recency: Vec<usize>, levels: &[i32]) -> Vec<usize>;
+}
+
+/// A shared, type-erased ranking strategy that can be stored in a component.
+pub type SharedRankingStrategy = Arc<dyn Rank + Send + Sync>;
+
+/// How the variables of a component are ranked when planning,
+/// which decides which ones are kept and which ones are written to.
+///
+/// The priority levels used by [`Fixed`](Self::Fixed) and [`Hybrid`](Self::Hybrid) are set with
+/// [`Component::set_priority`](crate::model::Component::set_priority), and are 0 by default.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RankingStrategy {
+    /// The most recently edited variables have the highest priority.
+    Recency,
+    /// Variables with higher priority levels have higher priority, regardless of edits.
+    /// Variables with the same level are ranked in the order they were declared.
+    Fixed,
+    /// Variables with higher priority levels have higher priority,
+    /// and variables with the same level are ranked by recency.
+    Hybrid,
+}
+
+impl Default for RankingStrategy {
+    fn default() -> Self {
+        Self::Recency
+    }
+}
+
+impl Rank for RankingStrategy {
+    fn rank(&self, recency: Vec<usize>, levels: &[i32]) -> Vec<usize> {
+        let mut ranking = match self {
+            RankingStrategy::Recency => return recency,
+            RankingStrategy::Fixed => (0..recency.len()).collect(),
+            RankingStrategy::Hybrid => recency,
+        };
+        // The sort is stable, so variables with the same level keep their order
+        ranking.sort_by_key(|&vi| Reverse(levels.get(vi).copied().unwrap_or_default()));
+        ranking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rank, RankingStrategy};
+
+    #[test]
+    fn levels_are_ranked_before_recency() {
+        let recency = vec![3, 1, 0, 2];
+        let levels = [0, 1, 0, 1];
+        assert_eq!(
+            RankingStrategy::Recency.rank(recency.clone(), &levels),
+            vec![3, 1, 0, 2]
+        );
+        assert_eq!(
+            RankingStrategy::Fixed.rank(recency.clone(), &levels),
+            vec![1, 3, 0, 2]
+        );
+        assert_eq!(
+            RankingStrategy::Hybrid.rank(recency, &levels),
+            vec![3, 1, 0, 2]
+        );
+        assert_eq!(
+            RankingStrategy::Hybrid.rank(vec![0, 2, 1, 3], &levels),
+            vec![1, 3, 0, 2]
+        );
+    }
+}
@@ -177,7 +177,7 @@ macro_rules! constraint_system_wrapper {
                 let mut inner = self.inner.lock().unwrap();
                 let pool = self.pool.lock().unwrap();
                 match inner.par_solve(&*pool) {
-                    Ok(()) => self.handle_events(),
+                    Ok(_) => self.handle_events(),
                     Err(e) => {
                         log::error!("Update failed: {}", e);
                     }
@@ -1,10 +1,10 @@
 //! A thread pool implementation that changes its number of workers dynamically depending on need.
 
 use crate::thread::{worker::generic_worker::GenericWorker, TerminationStrategy};
-use hotdrink_rs::executor::{MethodExecutor, TerminationHandle};
+use hotdrink_rs::executor::{MethodExecutor, Spawner, TerminationHandle};
 use js_sys::Date;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
     mpsc::Sender,
     Arc, Mutex,
 };
@@ -23,35 +23,52 @@ struct Work {
 struct WorkerInfo {
     /// The worker itself.
     worker: GenericWorker,
+    /// The parts of the worker that can be used from other threads.
+    handle: Arc<WorkerHandle>,
+}
+
+/// The parts of a worker that can be used from other threads.
+/// Unlike the worker itself, they can be captured by a [`Spawner`].
+#[derive(Debug)]
+struct WorkerHandle {
     /// A flag that will be set when the result of the
     /// current computation is no longer required.
-    result_needed: Arc<AtomicBool>,
-    /// A flag that is set after the computation is complete.
-    ready: Arc<AtomicBool>,
+    result_needed: Mutex<Arc<AtomicBool>>,
+    /// The number of tasks that have been sent to the worker but are not complete.
+    pending: Arc<AtomicUsize>,
     /// The sender-half of a channel that sends work to the worker.
-    sender: Sender<Work>,
+    sender: Mutex<Sender<Work>>,
     /// The time when the task was started
     time_started: Arc<Mutex<Option<f64>>>,
 }
 
-impl WorkerInfo {
+impl WorkerHandle {
     /// Passes the work to be executed into the work-channel.
-    pub fn execute(&mut self, f: impl FnOnce() + Send + 'static) {
-        self.ready.store(false, Ordering::SeqCst);
-        self.result_needed = Arc::new(AtomicBool::new(true));
-        self.sender.send(Work { work: Box::new(f) }).unwrap();
+    pub fn execute(&self, f: impl FnOnce() + Send + 'static, result_needed: Arc<AtomicBool>) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        *self.result_needed.lock().unwrap() = result_needed;
+        self.sender
+            .lock()
+            .unwrap()
+            .send(Work { work: Box::new(f) })
+            .unwrap();
     }
 
     /// Checks if the termination flag has been set,
     /// meaning that the result of the current computation
     /// likely has been dropped.
     pub fn result_needed(&self) -> bool {
-        self.result_needed.load(Ordering::SeqCst)
+        self.result_needed.lock().unwrap().load(Ordering::SeqCst)
     }
 
-    /// Checks if the current computation has been completed.
+    /// Checks if all computations sent to the worker have been completed.
     pub fn is_ready(&self) -> bool {
-        self.ready.load(Ordering::SeqCst)
+        self.pending() == 0
+    }
+
+    /// Returns the number of computations sent to the worker that have not been completed.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
     }
 }
 
@@ -60,8 +77,8 @@ fn spawn_worker(wasm_bindgen_shim_url: &str) -> Result<WorkerInfo, JsValue> {
     let worker = GenericWorker::from_url("PoolWorker", wasm_bindgen_shim_url)?;
 
     // Set up the shared data
-    let ready = Arc::new(AtomicBool::new(true));
-    let ready_clone = ready.clone();
+    let pending = Arc::new(AtomicUsize::new(0));
+    let pending_clone = pending.clone();
     let time_started = Arc::new(Mutex::new(None));
     let time_started_clone = time_started.clone();
     let (sender, receiver) = std::sync::mpsc::channel::<Work>();
@@ -72,7 +89,7 @@ fn spawn_worker(wasm_bindgen_shim_url: &str) -> Result<WorkerInfo, JsValue> {
                 *time_started_clone.lock().unwrap() = Some(Date::now());
                 (task.work)();
                 *time_started_clone.lock().unwrap() = None;
-                ready_clone.store(true, Ordering::SeqCst);
+                pending_clone.fetch_sub(1, Ordering::SeqCst);
             }
             Err(e) => {
                 log::error!("Could not receive work: {}", e);
@@ -83,10 +100,12 @@ fn spawn_worker(wasm_bindgen_shim_url: &str) -> Result<WorkerInfo, JsValue> {
 
     Ok(WorkerInfo {
         worker,
-        result_needed: Arc::new(AtomicBool::new(true)),
-        ready,
-        sender,
-        time_started,
+        handle: Arc::new(WorkerHandle {
+            result_needed: Mutex::new(Arc::new(AtomicBool::new(true))),
+            pending,
+            sender: Mutex::new(sender),
+            time_started,
+        }),
     })
 }
 
@@ -94,6 +113,9 @@ fn spawn_worker(wasm_bindgen_shim_url: &str) -> Result<WorkerInfo, JsValue> {
 #[derive(Debug)]
 pub struct DynamicPool {
     workers: Mutex<Vec<WorkerInfo>>,
+    /// The handles of the current workers, shared with spawners.
+    /// Workers can only be created and terminated on the thread that owns the pool.
+    handles: Arc<Mutex<Vec<Arc<WorkerHandle>>>>,
     termination_strategy: TerminationStrategy,
     wasm_bindgen_shim_url: String,
 }
@@ -109,17 +131,20 @@ impl MethodExecutor for DynamicPool {
         let mut workers = self.workers.lock().unwrap();
         // Cancel and remove stale workers
         workers.drain_filter(|w| {
+            let handle = &w.handle;
             let should_be_terminated = match termination_strategy {
                 TerminationStrategy::Never => false,
-                TerminationStrategy::UnusedResultAndNotDone => !w.result_needed() && !w.is_ready(),
+                TerminationStrategy::UnusedResultAndNotDone => {
+                    !handle.result_needed() && !handle.is_ready()
+                }
                 TerminationStrategy::UnusedResultAndNotDoneInMs(ms) => {
-                    let not_done_in_ms = w
+                    let not_done_in_ms = handle
                         .time_started
                         .lock()
                         .unwrap()
                         .map(|tss| Date::now() - tss > ms as f64)
                         .unwrap_or(false);
-                    !w.result_needed() && !w.is_ready() && not_done_in_ms
+                    !handle.result_needed() && !handle.is_ready() && not_done_in_ms
                 }
             };
             if should_be_terminated {
@@ -130,25 +155,42 @@ impl MethodExecutor for DynamicPool {
         });
 
         // Check if any workers are ready, and spawn a new one if not
-        let any_ready = workers.iter().any(|w| w.is_ready());
+        let any_ready = workers.iter().any(|w| w.handle.is_ready());
         if !any_ready {
             let worker_info = spawn_worker(&self.wasm_bindgen_shim_url)?;
             workers.push(worker_info);
         }
+        *self.handles.lock().unwrap() = workers.iter().map(|w| w.handle.clone()).collect();
 
         // Execute task on a ready worker.
         // We know that one must be ready, as we spawned a new one if none were.
         let (th, result_needed) = TerminationHandle::new();
-        for worker_info in workers.iter_mut() {
-            if worker_info.is_ready() {
-                worker_info.execute(f);
-                worker_info.result_needed = result_needed;
+        for worker_info in workers.iter() {
+            if worker_info.handle.is_ready() {
+                worker_info.handle.execute(f, result_needed);
                 break;
             }
         }
 
         Ok(th)
     }
+
+    /// Gives deferred work to the worker with the fewest pending tasks,
+    /// since new workers can only be spawned on the thread that owns the pool.
+    /// The pool grows again the next time a method is scheduled while all workers are busy.
+    fn spawner(&self) -> Option<Spawner> {
+        let handles = self.handles.clone();
+        Some(Arc::new(move |work| {
+            let handles = handles.lock().unwrap();
+            match handles.iter().min_by_key(|h| h.pending()) {
+                Some(handle) => {
+                    let result_needed = work.result_needed().clone();
+                    handle.execute(move || work.run(), result_needed);
+                }
+                None => log::error!("Could not find a worker for deferred work"),
+            }
+        }))
+    }
 }
 
 impl WebWorkerPool for DynamicPool {
@@ -169,8 +211,10 @@ impl WebWorkerPool for DynamicPool {
             workers.push(worker);
         }
 
+        let handles = workers.iter().map(|w| w.handle.clone()).collect();
         Ok(Self {
             workers: Mutex::new(workers),
+            handles: Arc::new(Mutex::new(handles)),
             termination_strategy,
             wasm_bindgen_shim_url: wasm_bindgen_shim_url.to_owned(),
         })
@@ -3,7 +3,7 @@
 
 use super::{pool_worker::Work, PoolWorker, WebWorkerPool};
 use crate::thread::TerminationStrategy;
-use hotdrink_rs::executor::{MethodExecutor, TerminationHandle};
+use hotdrink_rs::executor::{MethodExecutor, Spawner, TerminationHandle};
 use std::sync::{
     mpsc::{self, Sender},
     Arc, Mutex,
@@ -40,6 +40,19 @@ impl MethodExecutor for StaticPool {
         // Return a handle that will set a flag once it is dropped
         Ok(th)
     }
+
+    /// Sends deferred work through the same channel,
+    /// along with its flag so that stuck workers can be restarted once it is no longer needed.
+    fn spawner(&self) -> Option<Spawner> {
+        let work_sender = Mutex::new(self.work_sender.clone());
+        Some(Arc::new(move |work: hotdrink_rs::executor::Work| {
+            let result_needed = work.result_needed().clone();
+            let work = Work::new(move || work.run(), result_needed);
+            if let Err(e) = work_sender.lock().unwrap().send(work) {
+                log::error!("Could not send deferred work: {}", e);
+            }
+        }))
+    }
 }
 
 impl WebWorkerPool for StaticPool {
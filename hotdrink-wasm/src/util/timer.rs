@@ -1,5 +1,6 @@
 //! A struct for counting the time some operation takes.
 
+use hotdrink_rs::util::table::Table;
 use web_sys::Performance;
 
 /// A struct for counting the time some operation takes.
@@ -88,4 +89,14 @@ impl Timer {
     pub fn deltas(&self) -> &[f64] {
         &self.times
     }
+
+    /// Returns a table with the time passed between each checkpoint in milliseconds,
+    /// in the same format as the statistics from [`hotdrink_rs::stats::Stats::to_table`].
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(vec!["checkpoint", "time (ms)"]);
+        for (i, delta) in self.times.iter().enumerate() {
+            table.push_row(vec![i.to_string(), format!("{:.3}", delta)]);
+        }
+        table
+    }
 }
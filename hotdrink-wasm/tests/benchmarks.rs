@@ -30,7 +30,7 @@ fn bench_update<Cb: ComponentFactory>(n_constraints: usize) {
         let result = component.par_solve(&DummyExecutor);
         total_time += performance.now() - start;
         // web_sys::console::time_end_with_label(&format!("{} & {}", name, n_variables));
-        assert_eq!(result, Ok(()));
+        assert!(result.is_ok());
     }
 
     console_log!(
@@ -62,7 +62,7 @@ fn bench_update_max<Cb: ComponentFactory>(n_constraints: usize) {
         let result = component.par_solve(&DummyExecutor);
         max = max.max(performance.now() - start);
         // web_sys::console::time_end_with_label(&format!("{} & {}", name, n_variables));
-        assert_eq!(result, Ok(()));
+        assert!(result.is_ok());
     }
 
     console_log!("{} & {} & {}", Cb::name(), n_constraints, max);